@@ -0,0 +1,137 @@
+//! Benchmarks des indicateurs : `cargo bench --bench indicators`
+
+use hft_rust_core::bench::{calc_chikou, calc_ichimoku, calc_ichimoku_line, calc_sma};
+use std::time::{Duration, Instant};
+
+/// Barres synthétiques (highs, lows, closes) autour de 2000
+fn bars(len: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let closes: Vec<f64> = (0..len)
+        .map(|i| 2000.0 + 10.0 * (i as f64 / 500.0).sin() + (i as f64 / 7.0).cos())
+        .collect();
+    let highs = closes.iter().map(|c| c + 0.8).collect();
+    let lows = closes.iter().map(|c| c - 0.8).collect();
+    (highs, lows, closes)
+}
+
+/// Meilleur temps sur `runs` exécutions
+fn best_of<T>(runs: usize, mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut best = Duration::MAX;
+    let mut result = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        let value = f();
+        best = best.min(start.elapsed());
+        result = Some(value);
+    }
+    (best, result.unwrap())
+}
+
+/// Ichimoku sur 1M barres : trois lignes en parallèle (rayon::join)
+/// contre les mêmes lignes calculées l'une après l'autre
+fn ichimoku_parallel(highs: &[f64], lows: &[f64], closes: &[f64]) {
+    let (parallel, (tenkan, kijun, _, senkou_b, _)) =
+        best_of(5, || calc_ichimoku(highs, lows, closes, 9, 26, 52));
+    let (sequential, lines) = best_of(5, || {
+        let tenkan = calc_ichimoku_line(highs, lows, 9);
+        let kijun = calc_ichimoku_line(highs, lows, 26);
+        let senkou_b = calc_ichimoku_line(highs, lows, 52);
+        let senkou_a = tenkan.iter().zip(&kijun).map(|(t, k)| (t + k) / 2.0).collect::<Vec<_>>();
+        (tenkan, kijun, senkou_a, senkou_b, calc_chikou(closes, 26))
+    });
+    
+    let same = |a: &[f64], b: &[f64]| {
+        a.iter().zip(b).all(|(x, y)| x == y || (x.is_nan() && y.is_nan()))
+    };
+    assert!(same(&tenkan, &lines.0) && same(&kijun, &lines.1) && same(&senkou_b, &lines.3));
+    
+    let threads = rayon::current_num_threads();
+    let speedup = sequential.as_secs_f64() / parallel.as_secs_f64();
+    println!(
+        "ichimoku_parallel : {} barres, parallèle {:.2?}, séquentiel {:.2?}, \
+         accélération x{:.2} sur {} threads",
+        closes.len(),
+        parallel,
+        sequential,
+        speedup,
+        threads,
+    );
+    if threads >= 3 {
+        assert!(speedup > 1.5, "accélération insuffisante : x{:.2}", speedup);
+    }
+}
+
+/// SMA à somme glissante : le coût par barre ne dépend pas de la période,
+/// contrairement à la moyenne recalculée sur chaque fenêtre
+fn sma_large_window(closes: &[f64]) {
+    const PERIOD: usize = 5_000;
+    let (short, _) = best_of(5, || calc_sma(closes, 20));
+    let (long, sma) = best_of(5, || calc_sma(closes, PERIOD));
+    
+    // Référence naïve O(n * period) sur les 100k premières barres
+    let head = &closes[..100_000];
+    let (naive, expected) = best_of(1, || {
+        let mut result = vec![f64::NAN; head.len()];
+        for i in PERIOD - 1..head.len() {
+            result[i] = head[i + 1 - PERIOD..=i].iter().sum::<f64>() / PERIOD as f64;
+        }
+        result
+    });
+    let (rolling, _) = best_of(5, || calc_sma(head, PERIOD));
+    assert!(expected[PERIOD - 1..]
+        .iter()
+        .zip(&sma[PERIOD - 1..])
+        .all(|(want, got)| (want - got).abs() < 1e-9));
+    
+    println!(
+        "sma_large_window : {} barres, période 20 {:.2?}, période {} {:.2?} ; \
+         100k barres : somme glissante {:.2?}, naïf {:.2?} (x{:.0})",
+        closes.len(),
+        short,
+        PERIOD,
+        long,
+        rolling,
+        naive,
+        naive.as_secs_f64() / rolling.as_secs_f64(),
+    );
+    assert!(
+        long < short * 3,
+        "coût croissant avec la période : {:.2?} contre {:.2?}",
+        long,
+        short
+    );
+}
+
+/// Ligne Ichimoku à période 200 : deques monotones contre recherche du
+/// plus haut / plus bas sur chaque fenêtre
+fn ichimoku_line_deque(highs: &[f64], lows: &[f64]) {
+    const PERIOD: usize = 200;
+    let (deque, line) = best_of(5, || calc_ichimoku_line(highs, lows, PERIOD));
+    let (naive, expected) = best_of(1, || {
+        let mut result = vec![f64::NAN; PERIOD - 1];
+        result.extend(highs.windows(PERIOD).zip(lows.windows(PERIOD)).map(|(h, l)| {
+            let high = h.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let low = l.iter().cloned().fold(f64::INFINITY, f64::min);
+            (high + low) / 2.0
+        }));
+        result
+    });
+    assert!(line.iter().zip(&expected).all(|(x, y)| x == y || (x.is_nan() && y.is_nan())));
+    
+    let speedup = naive.as_secs_f64() / deque.as_secs_f64();
+    println!(
+        "ichimoku_line_deque : {} barres, période {}, deques {:.2?}, naïf {:.2?} (x{:.1})",
+        highs.len(),
+        PERIOD,
+        deque,
+        naive,
+        speedup,
+    );
+    assert!(speedup > 5.0, "accélération insuffisante : x{:.1}", speedup);
+}
+
+fn main() {
+    let (highs, lows, closes) = bars(1_000_000);
+    ichimoku_parallel(&highs, &lows, &closes);
+    sma_large_window(&closes);
+    ichimoku_line_deque(&highs, &lows);
+}
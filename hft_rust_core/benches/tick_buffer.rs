@@ -0,0 +1,163 @@
+//! Benchmarks TickBuffer : `cargo bench --bench tick_buffer`
+
+use hft_rust_core::{PriceSource, Tick, TickBuffer, TickBufferF32};
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tick synthétique autour de 2000, un tick toutes les 100 ms
+fn tick(i: usize) -> Tick {
+    let mid = 2000.0 + (i as f64 / 50.0).sin();
+    Tick {
+        symbol: "XAUUSD".into(),
+        bid: mid - 0.1,
+        ask: mid + 0.1,
+        timestamp: 1_700_000_000_000_000 + i as i64 * 100_000,
+        volume: 1,
+        bid_size: 0.0,
+        ask_size: 0.0,
+    }
+}
+
+/// 10M ticks dans un buffer de 100k : chaque tranche de 1M ticks doit
+/// coûter autant que la première (éviction O(1), pas de dérive quadratique)
+fn ring_buffer_eviction(py: Python<'_>) {
+    const TOTAL: usize = 10_000_000;
+    const SEGMENT: usize = 1_000_000;
+    let buffer =
+        TickBuffer::new(100_000, "XAUUSD".into(), false, true, false, None, PriceSource::Mid)
+            .unwrap();
+    
+    let mut segments: Vec<Duration> = Vec::new();
+    for segment in 0..TOTAL / SEGMENT {
+        let start = Instant::now();
+        for i in segment * SEGMENT..(segment + 1) * SEGMENT {
+            buffer.add_tick(py, tick(i)).unwrap();
+        }
+        segments.push(start.elapsed());
+    }
+    
+    let total: Duration = segments.iter().sum();
+    let first = segments[0];
+    let slowest = segments.iter().copied().max().unwrap_or_default();
+    println!(
+        "ring_buffer_eviction : {} ticks en {:.2?} ({:.0} ns/tick), tranche 1M : \
+         première {:.2?}, plus lente {:.2?}",
+        TOTAL,
+        total,
+        total.as_nanos() as f64 / TOTAL as f64,
+        first,
+        slowest,
+    );
+    assert_eq!(buffer.tick_count(), 100_000);
+    assert!(
+        slowest < first * 3,
+        "coût par tranche croissant : {:.2?} contre {:.2?}",
+        slowest,
+        first
+    );
+}
+
+/// Empreinte mémoire de 1M ticks, stockage f64 contre f32
+fn f32_memory_footprint(py: Python<'_>) {
+    const TICKS: usize = 1_000_000;
+    let wide =
+        TickBuffer::new(TICKS, "XAUUSD".into(), false, true, false, None, PriceSource::Mid)
+            .unwrap();
+    let compact = TickBufferF32::new(TICKS, "XAUUSD".into()).unwrap();
+    
+    let start = Instant::now();
+    for i in 0..TICKS {
+        wide.add_tick(py, tick(i)).unwrap();
+    }
+    let wide_time = start.elapsed();
+    let start = Instant::now();
+    compact.add_ticks(py, (0..TICKS).map(tick).collect()).unwrap();
+    let compact_time = start.elapsed();
+    
+    let (wide_bytes, compact_bytes) = (wide.memory_bytes(), compact.memory_bytes());
+    println!(
+        "f32_memory_footprint : {} ticks, f64 {:.1} Mo ({:.2?}), f32 {:.1} Mo ({:.2?}), \
+         rapport {:.2}",
+        TICKS,
+        wide_bytes as f64 / 1e6,
+        wide_time,
+        compact_bytes as f64 / 1e6,
+        compact_time,
+        wide_bytes as f64 / compact_bytes as f64,
+    );
+    assert_eq!((wide.tick_count(), compact.tick_count()), (TICKS, TICKS));
+    assert!(
+        compact_bytes * 2 <= wide_bytes,
+        "stockage f32 trop gros : {} octets contre {}",
+        compact_bytes,
+        wide_bytes
+    );
+}
+
+/// `add_tick` bloqué sur le verrou du buffer doit rendre le GIL : un
+/// thread Rust tient le verrou d'écriture (un `add_ticks` de 3M ticks,
+/// GIL relâché), un thread Python appelle `add_tick` et attend, un troisième
+/// exécute du code Python en boucle. Ce dernier doit avancer pendant toute
+/// l'attente ; sans `allow_threads` dans `add_tick`, il serait figé.
+fn gil_released_while_add_tick_waits(py: Python<'_>) {
+    const BATCH: usize = 3_000_000;
+    let buffer =
+        TickBuffer::new(BATCH + 1, "XAUUSD".into(), false, true, false, None, PriceSource::Mid)
+            .unwrap();
+    let batch: Vec<Tick> = (0..BATCH).map(tick).collect();
+    let iterations = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    
+    // Le thread principal rend le GIL aux trois autres
+    let (blocked, progress) = py.allow_threads(|| {
+        let holder = {
+            let buffer = buffer.clone();
+            thread::spawn(move || Python::with_gil(|py| buffer.add_ticks(py, batch).unwrap()))
+        };
+        let spinner = {
+            let (iterations, stop) = (iterations.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::Acquire) {
+                    Python::with_gil(|py| {
+                        py.eval("sum(range(100))", None, None).unwrap();
+                    });
+                    iterations.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+        };
+        
+        // Laisse le lot prendre le verrou, puis attend derrière lui
+        thread::sleep(Duration::from_millis(100));
+        let waited = Python::with_gil(|py| {
+            let before = iterations.load(Ordering::Relaxed);
+            let start = Instant::now();
+            buffer.add_tick(py, tick(BATCH)).unwrap();
+            (start.elapsed(), iterations.load(Ordering::Relaxed) - before)
+        });
+        
+        stop.store(true, Ordering::Release);
+        holder.join().unwrap();
+        spinner.join().unwrap();
+        waited
+    });
+    
+    println!(
+        "gil_released_while_add_tick_waits : add_tick bloqué {:.2?} derrière un lot de {} \
+         ticks, {} itérations Python pendant l'attente",
+        blocked, BATCH, progress,
+    );
+    assert_eq!(buffer.tick_count(), BATCH + 1);
+    assert!(blocked > Duration::from_millis(50), "verrou non disputé : {:.2?}", blocked);
+    assert!(progress >= 100, "GIL tenu pendant l'attente : {} itérations", progress);
+}
+
+fn main() {
+    Python::with_gil(|py| {
+        ring_buffer_eviction(py);
+        f32_memory_footprint(py);
+        gil_released_while_add_tick_waits(py);
+    });
+}
@@ -0,0 +1,114 @@
+//! Erreurs de validation communes
+//! Toutes remontent en Python sous forme de HftException (sous-classe de
+//! ValueError) portant un attribut `code` stable, indépendant du message
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::fmt;
+
+create_exception!(
+    hft_rust_core,
+    HftException,
+    PyValueError,
+    "Erreur de validation ; l'attribut `code` identifie le cas (ex. EMPTY_INPUT)"
+);
+
+/// Erreur de validation des entrées
+#[derive(Clone, Debug, PartialEq)]
+pub enum HftError {
+    /// Tableau vide (nom du tableau)
+    EmptyInput(&'static str),
+    /// Tableaux de longueurs différentes ou vides
+    LengthMismatch,
+    /// Période nulle ou incompatible avec les données
+    InvalidPeriod(String),
+    /// Paramètre hors de son domaine
+    InvalidParameter(String),
+    /// Tick incohérent
+    InvalidTick(String),
+    /// Timeframe non enregistré (secondes)
+    UnknownTimeframe(u64),
+    /// Checkpoint illisible
+    InvalidCheckpoint(&'static str),
+    /// Sortie dépendant de données futures (mode sans look-ahead)
+    LookAhead(String),
+}
+
+impl HftError {
+    /// Code machine exposé en Python (`HftException.code`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            HftError::EmptyInput(_) => "EMPTY_INPUT",
+            HftError::LengthMismatch => "LENGTH_MISMATCH",
+            HftError::InvalidPeriod(_) => "INVALID_PERIOD",
+            HftError::InvalidParameter(_) => "INVALID_PARAMETER",
+            HftError::InvalidTick(_) => "INVALID_TICK",
+            HftError::UnknownTimeframe(_) => "UNKNOWN_TIMEFRAME",
+            HftError::InvalidCheckpoint(_) => "INVALID_CHECKPOINT",
+            HftError::LookAhead(_) => "LOOK_AHEAD",
+        }
+    }
+}
+
+impl fmt::Display for HftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HftError::EmptyInput(name) => {
+                write!(f, "Le tableau de {} ne peut pas être vide", name)
+            }
+            HftError::LengthMismatch => {
+                write!(f, "Les tableaux doivent avoir la même longueur non nulle")
+            }
+            HftError::InvalidPeriod(detail) => write!(f, "Période invalide : {}", detail),
+            HftError::InvalidParameter(detail) => write!(f, "Paramètre invalide : {}", detail),
+            HftError::InvalidTick(detail) => write!(f, "Tick invalide {}", detail),
+            HftError::UnknownTimeframe(tf) => write!(f, "Timeframe non enregistré : {}s", tf),
+            HftError::InvalidCheckpoint(detail) => write!(f, "Checkpoint invalide : {}", detail),
+            HftError::LookAhead(detail) => write!(f, "Look-ahead détecté : {}", detail),
+        }
+    }
+}
+
+impl std::error::Error for HftError {}
+
+impl From<HftError> for PyErr {
+    fn from(err: HftError) -> PyErr {
+        let py_err = HftException::new_err(err.to_string());
+        Python::with_gil(|py| {
+            // Ne peut échouer que si l'instance refuse les attributs
+            let _ = py_err.value(py).setattr("code", err.code());
+        });
+        py_err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::{check_period, check_same_len};
+    
+    #[test]
+    fn errors_raise_hft_exception_with_code_and_message() {
+        Python::with_gil(|py| {
+            let err: PyErr = HftError::EmptyInput("highs").into();
+            assert!(err.is_instance_of::<HftException>(py));
+            assert!(err.is_instance_of::<PyValueError>(py));
+            assert_eq!(err.value(py).to_string(), "Le tableau de highs ne peut pas être vide");
+            let code: String = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, "EMPTY_INPUT");
+            
+            // Les validations des calculateurs passent par le même type
+            let err = check_same_len(&[&[1.0, 2.0], &[1.0]]).unwrap_err();
+            assert!(err.is_instance_of::<HftException>(py));
+            let code: String = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, HftError::LengthMismatch.code());
+            
+            let err = check_period("tenkan_period", 0, 10).unwrap_err();
+            let code: String = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, "INVALID_PERIOD");
+            assert!(err.value(py).to_string().starts_with("Période invalide : "));
+            assert!(err.value(py).to_string().contains("tenkan_period"));
+        });
+    }
+}
@@ -1,8 +1,172 @@
 //! Calcul d'indicateurs techniques haute performance
 //! Optimisé avec SIMD et parallélisation
 
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
 use pyo3::prelude::*;
-use ndarray::Array1;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+use crate::errors::HftError;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Sortie Ichimoku : (tenkan, kijun, senkou_a, senkou_b, chikou)
+type IchimokuOutput = (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>);
+
+/// Série retournée à Python sous forme de numpy.ndarray
+pub(crate) type PySeries = Py<PyArray1<f64>>;
+
+/// Série de prix acceptée depuis Python
+/// Un numpy.ndarray float64 contigu est lu sans copie ; une liste
+/// (ou un ndarray non contigu) est copiée
+#[derive(FromPyObject)]
+pub(crate) enum PriceSeries<'py> {
+    Array(PyReadonlyArray1<'py, f64>),
+    List(Vec<f64>),
+}
+
+impl PriceSeries<'_> {
+    /// Vue sur les données, empruntée quand c'est possible
+    /// Avec `NanPolicy::Ffill`, copie avec les NaN remplacés
+    pub(crate) fn as_slice(&self, nan_policy: NanPolicy) -> Cow<'_, [f64]> {
+        let data = match self {
+            PriceSeries::Array(array) => match array.as_slice() {
+                Ok(slice) => Cow::Borrowed(slice),
+                Err(_) => Cow::Owned(array.as_array().to_vec()),
+            },
+            PriceSeries::List(values) => Cow::Borrowed(values.as_slice()),
+        };
+        
+        if nan_policy == NanPolicy::Ffill && data.iter().any(|x| x.is_nan()) {
+            Cow::Owned(forward_fill(&data))
+        } else {
+            data
+        }
+    }
+}
+
+/// Traitement des NaN des séries passées aux calculateurs (argument
+/// `nan_policy` de chaque `calculate`)
+///
+/// Skip (défaut) : les NaN sont conservés. Les extremums glissants
+/// (Ichimoku, Donchian, Stochastic, rolling_apply max/min) les ignorent
+/// et ne renvoient NaN que si toute la fenêtre est NaN ; les moyennes
+/// glissantes (SMA, WMA, VWMA, écart-type, Bollinger), la médiane de
+/// rolling_apply, rolling_correlation et rolling_beta valent NaN tant
+/// qu'un NaN est dans la fenêtre puis reprennent ; les NaN de tête sont
+/// sautés ; un NaN en cours de série se propage dans les indicateurs
+/// récursifs ou cumulés (EMA, RSI, ATR...).
+///
+/// Ffill : chaque NaN est remplacé par la dernière valeur valide avant
+/// le calcul (les NaN de tête restent). Les méthodes incrémentales
+/// (`update`) ne sont pas concernées.
+#[pyclass]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NanPolicy {
+    Skip,
+    Ffill,
+}
+
+/// Remplace chaque NaN par la dernière valeur valide (NaN de tête conservés)
+fn forward_fill(data: &[f64]) -> Vec<f64> {
+    let mut last = f64::NAN;
+    data.iter()
+        .map(|&x| {
+            if !x.is_nan() {
+                last = x;
+            }
+            last
+        })
+        .collect()
+}
+
+/// Vérifie que les tableaux ont tous la même longueur non nulle
+pub(crate) fn check_same_len(series: &[&[f64]]) -> PyResult<()> {
+    let len = series[0].len();
+    if len == 0 || series.iter().any(|s| s.len() != len) {
+        return Err(HftError::LengthMismatch.into());
+    }
+    Ok(())
+}
+
+/// Vérifie qu'une période est dans [1, len]
+pub(crate) fn check_period(name: &str, period: usize, len: usize) -> PyResult<()> {
+    if period == 0 || period > len {
+        return Err(HftError::InvalidPeriod(format!(
+            "{} = {} hors de [1, {}]", name, period, len
+        )).into());
+    }
+    Ok(())
+}
+
+/// Vérifie un ensemble de périodes (nom, valeur) contre la longueur
+fn check_periods(periods: &[(&str, usize)], len: usize) -> PyResult<()> {
+    periods.iter().try_for_each(|&(name, period)| check_period(name, period, len))
+}
+
+/// Mode « sans look-ahead » : chaque calcul batch vérifie sa causalité
+static NO_LOOKAHEAD: AtomicBool = AtomicBool::new(false);
+
+/// Active la vérification de causalité des calculateurs (CI, backtests)
+/// Chaque `calculate`, ainsi que rolling_correlation, rolling_beta et
+/// rolling_apply, recalcule alors l'indicateur sur quelques préfixes
+/// des entrées et lève HftException (code LOOK_AHEAD) si une sortie en i
+/// change quand les entrées après i sont retirées. Coût : quelques
+/// recalculs complets par appel.
+///
+/// Tous les indicateurs sont causaux, sauf la Chikou d'Ichimoku
+/// (chikou[i] = closes[i + kijun_period]), volontairement exclue du
+/// contrôle : ne pas l'utiliser comme information disponible en i.
+#[pyfunction]
+pub(crate) fn set_no_lookahead(enabled: bool) {
+    NO_LOOKAHEAD.store(enabled, Ordering::Relaxed);
+}
+
+/// Vrai si la vérification de causalité est active
+#[pyfunction]
+pub(crate) fn no_lookahead_enabled() -> bool {
+    NO_LOOKAHEAD.load(Ordering::Relaxed)
+}
+
+/// Vérifie, en mode sans look-ahead, que les sorties calculées sur toute
+/// la série coïncident avec celles recalculées sur des préfixes
+/// `compute(k)` calcule les sorties sur les `k` premières entrées
+pub(crate) fn check_causal(
+    name: &str,
+    outputs: &[&[f64]],
+    compute: impl Fn(usize) -> Vec<Vec<f64>>,
+) -> PyResult<()> {
+    if !no_lookahead_enabled() {
+        return Ok(());
+    }
+    
+    let len = outputs.first().map_or(0, |o| o.len());
+    let mut cuts = vec![len / 3, len / 2, len * 2 / 3, len.saturating_sub(1)];
+    cuts.dedup();
+    
+    for k in cuts.into_iter().filter(|&k| k > 0) {
+        let prefix = compute(k);
+        for (line, (full, part)) in outputs.iter().zip(&prefix).enumerate() {
+            let same = |i: usize| {
+                let (a, b) = (full[i], part[i]);
+                (a.is_nan() && b.is_nan()) || (a - b).abs() <= 1e-9 * a.abs().max(1.0)
+            };
+            if let Some(i) = (0..k).find(|&i| !same(i)) {
+                return Err(HftError::LookAhead(format!(
+                    "{} : sortie {} à l'indice {} modifiée par les données après {}",
+                    name, line, i, k - 1
+                ))
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Convertit un résultat en numpy.ndarray sans recopie
+pub(crate) fn to_pyarray(py: Python<'_>, values: Vec<f64>) -> PySeries {
+    values.into_pyarray(py).to_owned()
+}
 
 /// Calculateur Ichimoku optimisé
 #[pyclass]
@@ -17,66 +181,304 @@ impl IchimokuCalculator {
     
     /// Calcule Ichimoku avec parallélisation
     /// Retourne: (tenkan, kijun, senkou_a, senkou_b, chikou)
-    #[pyo3(signature = (highs, lows, closes, tenkan_period=9, kijun_period=26, senkou_b_period=52))]
+    ///
+    /// Chikou : chikou[i] = closes[i + kijun_period], les kijun_period
+    /// dernières valeurs sont NaN. Cette valeur provient du futur de la
+    /// barre i : ne pas l'utiliser comme information disponible en i.
+    /// Le calcul s'exécute sans le GIL, les trois lignes en parallèle
+    ///
+    /// Senkou : sans `displace`, senkou[i] est calculée et alignée sur la
+    /// barre i. Avec `displace`, le nuage est projeté de kijun_period barres :
+    /// senkou[i] est le nuage tracé en i, calculé en i - kijun_period ; les
+    /// kijun_period premières valeurs sont NaN et les tableaux Senkou
+    /// comptent kijun_period valeurs de plus que les entrées (projection
+    /// au-delà de la dernière barre).
+    ///
+    /// Entrées : numpy.ndarray float64 (lu sans copie) ou listes
+    /// Sorties : numpy.ndarray float64
+    #[pyo3(signature = (
+        highs, lows, closes, tenkan_period=9, kijun_period=26, senkou_b_period=52,
+        displace=false, nan_policy=NanPolicy::Skip
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn calculate(
         &self,
-        highs: Vec<f64>,
-        lows: Vec<f64>,
-        closes: Vec<f64>,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        closes: PriceSeries<'_>,
         tenkan_period: usize,
         kijun_period: usize,
         senkou_b_period: usize,
-    ) -> PyResult<(Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>)> {
+        displace: bool,
+        nan_policy: NanPolicy,
+    ) -> PyResult<IchimokuArrays> {
         
-        let len = highs.len();
-        if len == 0 || highs.len() != lows.len() || highs.len() != closes.len() {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Les tableaux doivent avoir la même longueur non nulle"
-            ));
+        let (highs, lows, closes) = (
+            highs.as_slice(nan_policy),
+            lows.as_slice(nan_policy),
+            closes.as_slice(nan_policy),
+        );
+        ichimoku_arrays(
+            py, &highs, &lows, &closes, tenkan_period, kijun_period, senkou_b_period, displace,
+        )
+    }
+}
+
+/// Lignes Ichimoku en numpy.ndarray : (tenkan, kijun, senkou_a, senkou_b, chikou)
+pub(crate) type IchimokuArrays = (PySeries, PySeries, PySeries, PySeries, PySeries);
+
+/// Validation et calcul Ichimoku partagés par le calculateur et TickBuffer
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn ichimoku_arrays(
+    py: Python<'_>,
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    tenkan_period: usize,
+    kijun_period: usize,
+    senkou_b_period: usize,
+    displace: bool,
+) -> PyResult<IchimokuArrays> {
+    check_same_len(&[highs, lows, closes])?;
+    
+    check_periods(&[
+        ("tenkan_period", tenkan_period),
+        ("kijun_period", kijun_period),
+        ("senkou_b_period", senkou_b_period),
+    ], closes.len())?;
+    
+    let (tenkan, kijun, senkou_a, senkou_b, chikou) = py.allow_threads(|| {
+        calc_ichimoku(highs, lows, closes, tenkan_period, kijun_period, senkou_b_period)
+    });
+    // Chikou exclue : non causale par construction
+    check_causal("ichimoku", &[&tenkan, &kijun, &senkou_a, &senkou_b], |k| {
+        let (t, kj, a, b, _) = calc_ichimoku(
+            &highs[..k], &lows[..k], &closes[..k], tenkan_period, kijun_period, senkou_b_period
+        );
+        vec![t, kj, a, b]
+    })?;
+    
+    let (senkou_a, senkou_b) = if displace {
+        (displace_forward(senkou_a, kijun_period), displace_forward(senkou_b, kijun_period))
+    } else {
+        (senkou_a, senkou_b)
+    };
+    
+    Ok((
+        to_pyarray(py, tenkan),
+        to_pyarray(py, kijun),
+        to_pyarray(py, senkou_a),
+        to_pyarray(py, senkou_b),
+        to_pyarray(py, chikou),
+    ))
+}
+
+/// Décale une ligne de `shift` barres vers l'avant : out[i + shift] = line[i]
+/// Les `shift` premières valeurs sont NaN, la sortie est plus longue de
+/// `shift` valeurs
+fn displace_forward(line: Vec<f64>, shift: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; shift];
+    out.extend(line);
+    out
+}
+
+/// Fonction helper pour calculer l'ensemble des lignes Ichimoku
+/// Tenkan, Kijun et Senkou B sont indépendantes : calculées via rayon::join
+pub fn calc_ichimoku(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    tenkan_period: usize,
+    kijun_period: usize,
+    senkou_b_period: usize,
+) -> IchimokuOutput {
+    let (tenkan, (kijun, senkou_b)) = rayon::join(
+        || calc_ichimoku_line(highs, lows, tenkan_period),
+        || rayon::join(
+            || calc_ichimoku_line(highs, lows, kijun_period),
+            || calc_ichimoku_line(highs, lows, senkou_b_period),
+        ),
+    );
+    
+    // Senkou Span A = (Tenkan + Kijun) / 2
+    let senkou_a: Vec<f64> = tenkan.iter()
+        .zip(kijun.iter())
+        .map(|(t, k)| (t + k) / 2.0)
+        .collect();
+    
+    // Chikou Span = Close décalé de kijun_period vers le passé
+    let chikou = calc_chikou(closes, kijun_period);
+    
+    (tenkan, kijun, senkou_a, senkou_b, chikou)
+}
+
+/// Fonction helper pour calculer la Chikou Span
+/// result[i] = closes[i + shift], NaN pour les `shift` dernières barres
+pub fn calc_chikou(closes: &[f64], shift: usize) -> Vec<f64> {
+    let len = closes.len();
+    let mut result = vec![f64::NAN; len];
+    
+    if shift < len {
+        result[..len - shift].copy_from_slice(&closes[shift..]);
+    }
+    
+    result
+}
+
+/// Fonction helper pour calculer une ligne Ichimoku
+/// Fenêtre de `period` barres incluant la barre courante, NaN avant
+/// O(n) quelle que soit la période grâce aux deques monotones
+pub fn calc_ichimoku_line(highs: &[f64], lows: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 {
+        return vec![f64::NAN; highs.len()];
+    }
+    
+    let mut line = RollingMidpoint::new(period);
+    highs
+        .iter()
+        .zip(lows)
+        .map(|(&high, &low)| line.push(high, low))
+        .collect()
+}
+
+/// Fenêtre glissante d'extremum (deque monotone), O(1) amorti par valeur
+#[derive(Clone)]
+pub(crate) struct RollingExtremum {
+    period: usize,
+    keep_max: bool,
+    deque: VecDeque<(usize, f64)>,
+    count: usize,
+}
+
+impl RollingExtremum {
+    pub(crate) fn new(period: usize, keep_max: bool) -> Self {
+        RollingExtremum {
+            period,
+            keep_max,
+            deque: VecDeque::with_capacity(period),
+            count: 0,
         }
+    }
+    
+    /// Ajoute une valeur, retourne l'extremum de la fenêtre (NaN tant
+    /// que `period` valeurs n'ont pas été vues)
+    /// Les NaN occupent leur place dans la fenêtre sans y participer ;
+    /// NaN si la fenêtre ne contient que des NaN
+    pub(crate) fn push(&mut self, value: f64) -> f64 {
+        let idx = self.count;
+        self.count += 1;
         
-        // Calcul Tenkan
-        let tenkan = calc_ichimoku_line(&highs, &lows, tenkan_period);
-        
-        // Calcul Kijun
-        let kijun = calc_ichimoku_line(&highs, &lows, kijun_period);
-        
-        // Calcul Senkou B
-        let senkou_b = calc_ichimoku_line(&highs, &lows, senkou_b_period);
-        
-        // Senkou Span A = (Tenkan + Kijun) / 2
-        let senkou_a: Vec<f64> = tenkan.iter()
-            .zip(kijun.iter())
-            .map(|(t, k)| (t + k) / 2.0)
-            .collect();
+        if !value.is_nan() {
+            // Les valeurs dominées ne peuvent plus devenir l'extremum
+            while let Some(&(_, back)) = self.deque.back() {
+                let dominated = if self.keep_max { back <= value } else { back >= value };
+                if !dominated {
+                    break;
+                }
+                self.deque.pop_back();
+            }
+            self.deque.push_back((idx, value));
+        }
         
-        // Chikou Span = Close décalé
-        let mut chikou = vec![0.0; len];
-        for i in 26..len {
-            chikou[i - 26] = closes[i];
+        // Sortie des valeurs hors fenêtre
+        while let Some(&(front_idx, _)) = self.deque.front() {
+            if front_idx + self.period > idx {
+                break;
+            }
+            self.deque.pop_front();
         }
         
-        Ok((tenkan, kijun, senkou_a, senkou_b, chikou))
+        if self.is_full() {
+            self.deque.front().map_or(f64::NAN, |&(_, v)| v)
+        } else {
+            f64::NAN
+        }
+    }
+    
+    /// Vrai une fois `period` valeurs vues
+    fn is_full(&self) -> bool {
+        self.count >= self.period
     }
 }
 
-/// Fonction helper pour calculer une ligne Ichimoku
-fn calc_ichimoku_line(highs: &[f64], lows: &[f64], period: usize) -> Vec<f64> {
-    let len = highs.len();
-    let mut result = vec![0.0; len];
+/// Ligne Ichimoku incrémentale : (plus haut + plus bas) / 2 sur la fenêtre
+#[derive(Clone)]
+struct RollingMidpoint {
+    highs: RollingExtremum,
+    lows: RollingExtremum,
+}
+
+impl RollingMidpoint {
+    fn new(period: usize) -> Self {
+        RollingMidpoint {
+            highs: RollingExtremum::new(period, true),
+            lows: RollingExtremum::new(period, false),
+        }
+    }
     
-    for i in period..len {
-        let start = i - period;
-        let high_slice = &highs[start..i];
-        let low_slice = &lows[start..i];
+    fn push(&mut self, high: f64, low: f64) -> f64 {
+        (self.highs.push(high) + self.lows.push(low)) / 2.0
+    }
+    
+    fn is_ready(&self) -> bool {
+        self.highs.is_full()
+    }
+}
+
+/// Ichimoku incrémental pour le temps réel, O(1) amorti par barre
+#[pyclass]
+#[derive(Clone)]
+pub struct IchimokuState {
+    tenkan: RollingMidpoint,
+    kijun: RollingMidpoint,
+    senkou_b: RollingMidpoint,
+}
+
+#[pymethods]
+impl IchimokuState {
+    #[new]
+    #[pyo3(signature = (tenkan_period=9, kijun_period=26, senkou_b_period=52))]
+    fn new(tenkan_period: usize, kijun_period: usize, senkou_b_period: usize) -> PyResult<Self> {
+        if tenkan_period == 0 || kijun_period == 0 || senkou_b_period == 0 {
+            return Err(HftError::InvalidPeriod(
+                "les périodes doivent être strictement positives".into()
+            )
+            .into());
+        }
         
-        let max_high = high_slice.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let min_low = low_slice.iter().cloned().fold(f64::INFINITY, f64::min);
+        Ok(IchimokuState {
+            tenkan: RollingMidpoint::new(tenkan_period),
+            kijun: RollingMidpoint::new(kijun_period),
+            senkou_b: RollingMidpoint::new(senkou_b_period),
+        })
+    }
+    
+    /// Intègre une nouvelle barre
+    /// Retourne: (tenkan, kijun, senkou_a, senkou_b, chikou)
+    ///
+    /// Les quatre premières valeurs correspondent à l'index i du calcul
+    /// batch. La chikou retournée est la clôture courante, soit
+    /// chikou[i - kijun_period] du calcul batch.
+    pub(crate) fn update(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+    ) -> (f64, f64, f64, f64, f64) {
+        let tenkan = self.tenkan.push(high, low);
+        let kijun = self.kijun.push(high, low);
+        let senkou_b = self.senkou_b.push(high, low);
+        let senkou_a = (tenkan + kijun) / 2.0;
         
-        result[i] = (max_high + min_low) / 2.0;
+        (tenkan, kijun, senkou_a, senkou_b, close)
     }
     
-    result
+    /// Vrai dès que tenkan, kijun et senkou sont définies (assez de barres
+    /// pour la plus longue période ; entrées supposées non NaN)
+    fn is_ready(&self) -> bool {
+        self.tenkan.is_ready() && self.kijun.is_ready() && self.senkou_b.is_ready()
+    }
 }
 
 /// Calculateur STC (Schaff Trend Cycle) optimisé
@@ -91,86 +493,3142 @@ impl STCCalculator {
     }
     
     /// Calcule le STC
-    #[pyo3(signature = (closes, period=10, fast_length=23, slow_length=50))]
+    /// Chaque passe stochastique est lissée par un facteur `smoothing`
+    /// (0.5 par défaut, comme la définition de Schaff)
+    ///
+    /// `flat_range` : valeur d'une passe stochastique sur une fenêtre sans
+    /// amplitude (marché plat). "carry" (défaut) reconduit la valeur
+    /// précédente, sans faux croisement au retour du mouvement ; "nan"
+    /// laisse un trou, le lissage reprenant ensuite ; "mid" force 50,
+    /// l'ancien comportement, qui fait traverser les niveaux 25 / 75.
+    ///
+    /// Entrée : numpy.ndarray float64 (lu sans copie) ou liste
+    /// Sortie : numpy.ndarray float64
+    #[pyo3(signature = (
+        closes, period=10, fast_length=23, slow_length=50, smoothing=0.5, flat_range="carry",
+        nan_policy=NanPolicy::Skip
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn calculate(
         &self,
-        closes: Vec<f64>,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
         period: usize,
         fast_length: usize,
         slow_length: usize,
-    ) -> PyResult<Vec<f64>> {
+        smoothing: f64,
+        flat_range: &str,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
         
-        if closes.is_empty() {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Le tableau de closes ne peut pas être vide"
-            ));
+        let closes = closes.as_slice(nan_policy);
+        let flat_range = FlatRange::parse(flat_range)?;
+        stc_array(py, &closes, period, fast_length, slow_length, smoothing, flat_range)
+    }
+}
+
+/// Validation et calcul du STC partagés par le calculateur et TickBuffer
+pub(crate) fn stc_array(
+    py: Python<'_>,
+    closes: &[f64],
+    period: usize,
+    fast_length: usize,
+    slow_length: usize,
+    smoothing: f64,
+    flat_range: FlatRange,
+) -> PyResult<PySeries> {
+    if closes.is_empty() {
+        return Err(HftError::EmptyInput("closes").into());
+    }
+    
+    if !(smoothing > 0.0 && smoothing <= 1.0) {
+        return Err(HftError::InvalidParameter(
+            "le facteur de lissage doit être dans ]0, 1]".into()
+        )
+        .into());
+    }
+    
+    check_periods(&[
+        ("period", period),
+        ("fast_length", fast_length),
+        ("slow_length", slow_length),
+    ], closes.len())?;
+    
+    let stc = py.allow_threads(|| {
+        calc_stc(closes, period, fast_length, slow_length, smoothing, flat_range)
+    });
+    check_causal("stc", &[&stc], |k| {
+        vec![calc_stc(&closes[..k], period, fast_length, slow_length, smoothing, flat_range)]
+    })?;
+    
+    Ok(to_pyarray(py, stc))
+}
+
+/// Fonction helper pour calculer le STC
+fn calc_stc(
+    closes: &[f64],
+    period: usize,
+    fast_length: usize,
+    slow_length: usize,
+    smoothing: f64,
+    flat_range: FlatRange,
+) -> Vec<f64> {
+    // Calcul MACD
+    let macd = calc_macd_line(closes, fast_length, slow_length);
+    
+    // Stochastic sur MACD, lissé
+    let stoch1 = calc_smoothing(&calc_stochastic(&macd, period, flat_range), smoothing);
+    
+    // Stochastic sur Stochastic, lissé
+    calc_smoothing(&calc_stochastic(&stoch1, period, flat_range), smoothing)
+}
+
+/// Fonction helper de lissage exponentiel par facteur
+/// result[i] = result[i-1] + factor * (data[i] - result[i-1]), amorcé
+/// sur la première valeur valide ; une entrée NaN donne NaN sans
+/// interrompre le lissage
+fn calc_smoothing(data: &[f64], factor: f64) -> Vec<f64> {
+    let len = data.len();
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(data) {
+        Some(first) => first,
+        None => return result,
+    };
+    
+    let mut smoothed = data[first];
+    result[first] = smoothed;
+    for i in (first + 1)..len {
+        if data[i].is_nan() {
+            continue;
+        }
+        smoothed += factor * (data[i] - smoothed);
+        result[i] = smoothed;
+    }
+    
+    result
+}
+
+/// Passe stochastique incrémentale, identique à `calc_stochastic`
+#[derive(Clone)]
+struct StochasticWindow {
+    highs: RollingExtremum,
+    lows: RollingExtremum,
+    flat_range: FlatRange,
+    /// Dernière sortie définie (reconduite sur range nul)
+    previous: f64,
+    /// Première valeur valide reçue (les NaN de tête sont ignorés)
+    started: bool,
+}
+
+impl StochasticWindow {
+    fn new(period: usize, flat_range: FlatRange) -> Self {
+        StochasticWindow {
+            highs: RollingExtremum::new(period, true),
+            lows: RollingExtremum::new(period, false),
+            flat_range,
+            previous: f64::NAN,
+            started: false,
+        }
+    }
+    
+    fn push(&mut self, value: f64) -> f64 {
+        if !self.started && value.is_nan() {
+            return f64::NAN;
         }
+        self.started = true;
         
-        // Calcul MACD
-        let fast_ema = calc_ema(&closes, fast_length);
-        let slow_ema = calc_ema(&closes, slow_length);
+        let (max, min) = (self.highs.push(value), self.lows.push(value));
+        if !self.highs.is_full() {
+            return f64::NAN;
+        }
         
-        let macd: Vec<f64> = fast_ema.iter()
-            .zip(slow_ema.iter())
-            .map(|(f, s)| f - s)
-            .collect();
+        let result = if (max - min).abs() < 1e-10 {
+            self.flat_range.value(self.previous)
+        } else {
+            100.0 * (value - min) / (max - min)
+        };
+        if !result.is_nan() {
+            self.previous = result;
+        }
+        result
+    }
+}
+
+/// Lissage incrémental, identique à `calc_smoothing`
+#[derive(Clone)]
+struct Smoothing {
+    factor: f64,
+    value: Option<f64>,
+}
+
+impl Smoothing {
+    fn push(&mut self, data: f64) -> f64 {
+        if data.is_nan() {
+            return f64::NAN;
+        }
+        let value = match self.value {
+            Some(value) => value + self.factor * (data - value),
+            None => data,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// STC incrémental pour le temps réel : EMA rapide / lente puis deux
+/// passes stochastiques lissées, O(1) amorti par barre
+#[pyclass]
+#[derive(Clone)]
+pub struct STCState {
+    fast: EMACalculator,
+    slow: EMACalculator,
+    stoch1: StochasticWindow,
+    smooth1: Smoothing,
+    stoch2: StochasticWindow,
+    smooth2: Smoothing,
+}
+
+#[pymethods]
+impl STCState {
+    /// Mêmes paramètres que STCCalculator.calculate
+    #[new]
+    #[pyo3(signature = (
+        period=10, fast_length=23, slow_length=50, smoothing=0.5, flat_range="carry"
+    ))]
+    fn new(
+        period: usize,
+        fast_length: usize,
+        slow_length: usize,
+        smoothing: f64,
+        flat_range: &str,
+    ) -> PyResult<Self> {
+        if period == 0 || fast_length == 0 || slow_length == 0 {
+            return Err(HftError::InvalidPeriod(
+                "les périodes doivent être strictement positives".into()
+            )
+            .into());
+        }
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            return Err(HftError::InvalidParameter(
+                "le facteur de lissage doit être dans ]0, 1]".into()
+            )
+            .into());
+        }
         
-        // Stochastic sur MACD
-        let stoch1 = calc_stochastic(&macd, period);
+        let flat_range = FlatRange::parse(flat_range)?;
+        Ok(STCState {
+            fast: EMACalculator::new(fast_length, "sma")?,
+            slow: EMACalculator::new(slow_length, "sma")?,
+            stoch1: StochasticWindow::new(period, flat_range),
+            smooth1: Smoothing { factor: smoothing, value: None },
+            stoch2: StochasticWindow::new(period, flat_range),
+            smooth2: Smoothing { factor: smoothing, value: None },
+        })
+    }
+    
+    /// Intègre une nouvelle clôture, retourne le STC courant
+    /// Égal à la dernière valeur de STCCalculator.calculate sur toutes les
+    /// clôtures reçues ; NaN pendant le warm-up. Une clôture NaN est
+    /// ignorée (NaN retourné).
+    pub(crate) fn update(&mut self, close: f64) -> f64 {
+        if close.is_nan() {
+            return f64::NAN;
+        }
         
-        // Stochastic sur Stochastic
-        let stoch2 = calc_stochastic(&stoch1, period);
+        let (fast, slow) = (self.fast.update(close), self.slow.update(close));
+        let macd = if self.fast.is_ready() && self.slow.is_ready() {
+            fast - slow
+        } else {
+            f64::NAN
+        };
         
-        Ok(stoch2)
+        let stoch1 = self.smooth1.push(self.stoch1.push(macd));
+        self.smooth2.push(self.stoch2.push(stoch1))
+    }
+    
+    /// Vrai dès que `update` produit un STC (avec flat_range="nan", une
+    /// barre plate peut encore donner NaN)
+    fn is_ready(&self) -> bool {
+        self.smooth2.value.is_some()
     }
 }
 
+/// Index de la première valeur non-NaN (les indicateurs chaînés
+/// reçoivent des entrées déjà préfixées de NaN)
+fn first_valid(data: &[f64]) -> Option<usize> {
+    data.iter().position(|v| !v.is_nan())
+}
+
+/// Contribution d'une valeur à une somme glissante : 0 pour un NaN, compté
+/// à part pour que la fenêtre reprenne une fois le NaN sorti
+fn or_zero(x: f64) -> f64 {
+    if x.is_nan() { 0.0 } else { x }
+}
+
 /// Fonction helper pour calculer une EMA
+/// NaN tant que `period` valeurs valides n'ont pas été vues
 fn calc_ema(data: &[f64], period: usize) -> Vec<f64> {
     let len = data.len();
-    let mut result = vec![0.0; len];
+    let mut result = vec![f64::NAN; len];
     
-    if len == 0 {
-        return result;
-    }
+    let first = match first_valid(data) {
+        Some(first) if period > 0 && first + period <= len => first,
+        _ => return result,
+    };
     
     let multiplier = 2.0 / (period as f64 + 1.0);
     
     // Première valeur = SMA
-    let mut sum = 0.0;
-    for i in 0..period.min(len) {
-        sum += data[i];
-    }
-    if period <= len {
-        result[period - 1] = sum / period as f64;
-    }
+    let seed = first + period - 1;
+    let sum: f64 = data[first..=seed].iter().sum();
+    result[seed] = sum / period as f64;
     
     // EMA suivantes
-    for i in period..len {
+    for i in (seed + 1)..len {
         result[i] = (data[i] - result[i - 1]) * multiplier + result[i - 1];
     }
     
     result
 }
 
-/// Fonction helper pour calculer un oscillateur stochastique
-fn calc_stochastic(data: &[f64], period: usize) -> Vec<f64> {
+/// Amorçage d'une EMA
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EmaSeed {
+    /// SMA des `period` premières valeurs
+    Sma,
+    /// Première valeur valide
+    First,
+    /// EMA à retard réduit (ZLEMA) : entrée 2 * x[i] - x[i - (period - 1) / 2],
+    /// amorcée par SMA
+    ZeroLag,
+}
+
+impl EmaSeed {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "sma" => Ok(EmaSeed::Sma),
+            "first" => Ok(EmaSeed::First),
+            "zero_lag" => Ok(EmaSeed::ZeroLag),
+            _ => Err(HftError::InvalidParameter(format!(
+                "amorçage EMA inconnu '{}' (sma, first, zero_lag)", name
+            ))
+            .into()),
+        }
+    }
+}
+
+/// Décalage de l'entrée ZLEMA pour une période
+fn zero_lag_shift(period: usize) -> usize {
+    period.saturating_sub(1) / 2
+}
+
+/// Fonction helper pour calculer une EMA selon l'amorçage choisi
+fn calc_ema_seeded(data: &[f64], period: usize, seed: EmaSeed) -> Vec<f64> {
+    match seed {
+        EmaSeed::Sma => calc_ema(data, period),
+        EmaSeed::First => {
+            let len = data.len();
+            let mut result = vec![f64::NAN; len];
+            let first = match first_valid(data) {
+                Some(first) if period > 0 => first,
+                _ => return result,
+            };
+            
+            let multiplier = 2.0 / (period as f64 + 1.0);
+            result[first] = data[first];
+            for i in (first + 1)..len {
+                result[i] = (data[i] - result[i - 1]) * multiplier + result[i - 1];
+            }
+            result
+        }
+        EmaSeed::ZeroLag => {
+            let lag = zero_lag_shift(period);
+            let delagged: Vec<f64> = (0..data.len())
+                .map(|i| if i >= lag { 2.0 * data[i] - data[i - lag] } else { f64::NAN })
+                .collect();
+            calc_ema(&delagged, period)
+        }
+    }
+}
+
+/// Fonction helper pour calculer une moyenne mobile simple
+/// Ignore les NaN de tête ; NaN pendant le warm-up et tant qu'un NaN est
+/// dans la fenêtre
+pub fn calc_sma(data: &[f64], period: usize) -> Vec<f64> {
+    let len = data.len();
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(data) {
+        Some(first) if period > 0 && first + period <= len => first,
+        _ => return result,
+    };
+    
+    let p = period as f64;
+    let mut sum = 0.0;
+    let mut nans = 0usize;
+    for i in first..len {
+        sum += or_zero(data[i]);
+        nans += data[i].is_nan() as usize;
+        if i >= first + period {
+            let old = data[i - period];
+            sum -= or_zero(old);
+            nans -= old.is_nan() as usize;
+        }
+        
+        if i + 1 >= first + period && nans == 0 {
+            result[i] = sum / p;
+        }
+    }
+    
+    result
+}
+
+/// Fonction helper pour calculer une moyenne mobile pondérée linéairement
+/// (poids 1..period, le plus fort sur la barre courante), O(n)
+/// La fenêtre est recalculée exactement toutes les `period` barres pour
+/// borner la dérive numérique sur les prix élevés (or ~2000)
+/// Ignore les NaN de tête ; NaN pendant le warm-up et tant qu'un NaN est
+/// dans la fenêtre
+fn calc_wma(data: &[f64], period: usize) -> Vec<f64> {
     let len = data.len();
-    let mut result = vec![0.0; len];
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(data) {
+        Some(first) if period > 0 && first + period <= len => first,
+        _ => return result,
+    };
+    
+    let p = period as f64;
+    let denominator = p * (p + 1.0) / 2.0;
+    let mut weighted = 0.0;
+    let mut sum = 0.0;
+    let mut nans = 0usize;
     
-    for i in period..len {
-        let start = i - period;
-        let slice = &data[start..i];
+    // Chaque barre perd un cran de poids, la nouvelle entre avec `period`
+    for i in first..len {
+        let new = or_zero(data[i]);
+        weighted += p * new - sum;
+        sum += new;
+        nans += data[i].is_nan() as usize;
+        if i >= first + period {
+            let old = data[i - period];
+            sum -= or_zero(old);
+            nans -= old.is_nan() as usize;
+        }
         
-        let max = slice.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let min = slice.iter().cloned().fold(f64::INFINITY, f64::min);
+        if i + 1 >= first + period && nans == 0 {
+            if (i + 1) % period == 0 {
+                // Recalage exact sur la fenêtre courante
+                let window = &data[i + 1 - period..=i];
+                sum = window.iter().sum();
+                weighted = window.iter().zip(1..=period).map(|(x, w)| x * w as f64).sum();
+            }
+            result[i] = weighted / denominator;
+        }
+    }
+    
+    result
+}
+
+/// Valeur d'un stochastique sur une fenêtre sans amplitude (marché plat)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FlatRange {
+    /// Dernière valeur définie reconduite (50 s'il n'y en a pas)
+    Carry,
+    /// NaN
+    Nan,
+    /// 50, milieu de l'échelle
+    Mid,
+}
+
+impl FlatRange {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "carry" => Ok(FlatRange::Carry),
+            "nan" => Ok(FlatRange::Nan),
+            "mid" => Ok(FlatRange::Mid),
+            _ => Err(HftError::InvalidParameter(format!(
+                "traitement du range nul inconnu '{}' (carry, nan, mid)", name
+            ))
+            .into()),
+        }
+    }
+    
+    /// Valeur à émettre, `previous` étant la dernière sortie définie
+    fn value(self, previous: f64) -> f64 {
+        match self {
+            FlatRange::Carry if !previous.is_nan() => previous,
+            FlatRange::Nan => f64::NAN,
+            _ => 50.0,
+        }
+    }
+}
+
+/// Fonction helper pour calculer un oscillateur stochastique, O(n)
+/// Fenêtre de `period` valeurs incluant la courante, sortie dans [0, 100] ;
+/// range nul traité selon `flat_range`
+fn calc_stochastic(data: &[f64], period: usize, flat_range: FlatRange) -> Vec<f64> {
+    let len = data.len();
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(data) {
+        Some(first) if period > 0 => first,
+        _ => return result,
+    };
+    
+    // Fenêtres ouvertes au premier point valide : les NaN de tête sont sautés
+    let mut highest = RollingExtremum::new(period, true);
+    let mut lowest = RollingExtremum::new(period, false);
+    let mut previous = f64::NAN;
+    for (i, &x) in data.iter().enumerate().skip(first) {
+        let (max, min) = (highest.push(x), lowest.push(x));
+        if !highest.is_full() {
+            continue;
+        }
         
         if (max - min).abs() < 1e-10 {
-            result[i] = 50.0;
+            result[i] = flat_range.value(previous);
+        } else {
+            result[i] = 100.0 * (x - min) / (max - min);
+        }
+        if !result[i].is_nan() {
+            previous = result[i];
+        }
+    }
+    
+    result
+}
+
+/// Calculateur RSI (lissage de Wilder)
+#[pyclass]
+pub struct RSICalculator;
+
+#[pymethods]
+impl RSICalculator {
+    #[new]
+    fn new() -> Self {
+        RSICalculator
+    }
+    
+    /// Calcule le RSI
+    /// NaN pendant les `period` premières barres, 100.0 sans aucune perte
+    #[pyo3(signature = (closes, period=14, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let closes = closes.as_slice(nan_policy);
+        if closes.is_empty() {
+            return Err(HftError::EmptyInput("closes").into());
+        }
+        
+        check_period("period", period, closes.len())?;
+        
+        let rsi = py.allow_threads(|| calc_rsi(&closes, period));
+        check_causal("rsi", &[&rsi], |k| vec![calc_rsi(&closes[..k], period)])?;
+        Ok(to_pyarray(py, rsi))
+    }
+}
+
+/// Fonction helper pour calculer un RSI de Wilder
+fn calc_rsi(closes: &[f64], period: usize) -> Vec<f64> {
+    let len = closes.len();
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(closes) {
+        Some(first) if period > 0 && first + period < len => first,
+        _ => return result,
+    };
+    
+    let rsi_from = |avg_gain: f64, avg_loss: f64| {
+        if avg_loss == 0.0 {
+            100.0
         } else {
-            result[i] = 100.0 * (data[i] - min) / (max - min);
+            100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
         }
+    };
+    
+    // Moyennes initiales = moyennes simples des `period` premières variations
+    let mut avg_gain = 0.0;
+    let mut avg_loss = 0.0;
+    for i in (first + 1)..=(first + period) {
+        let change = closes[i] - closes[i - 1];
+        if change > 0.0 { avg_gain += change; } else { avg_loss -= change; }
+    }
+    avg_gain /= period as f64;
+    avg_loss /= period as f64;
+    result[first + period] = rsi_from(avg_gain, avg_loss);
+    
+    // Lissage de Wilder
+    let p = period as f64;
+    for i in (first + period + 1)..len {
+        let change = closes[i] - closes[i - 1];
+        let (gain, loss) = if change > 0.0 { (change, 0.0) } else { (0.0, -change) };
+        avg_gain = (avg_gain * (p - 1.0) + gain) / p;
+        avg_loss = (avg_loss * (p - 1.0) + loss) / p;
+        result[i] = rsi_from(avg_gain, avg_loss);
     }
     
     result
 }
+
+/// Calculateur EMA, batch et incrémental
+#[pyclass]
+#[derive(Clone)]
+pub struct EMACalculator {
+    period: usize,
+    seed: EmaSeed,
+    count: usize,
+    sum: f64,
+    value: f64,
+    /// Dernières entrées brutes (amorçage zero_lag)
+    recent: VecDeque<f64>,
+}
+
+#[pymethods]
+impl EMACalculator {
+    /// `seed` choisit l'amorçage de `update` (voir `calculate`)
+    #[new]
+    #[pyo3(signature = (period=20, seed="sma"))]
+    fn new(period: usize, seed: &str) -> PyResult<Self> {
+        if period == 0 {
+            return Err(HftError::InvalidPeriod(
+                "la période doit être strictement positive".into()
+            )
+            .into());
+        }
+        
+        Ok(EMACalculator {
+            period,
+            seed: EmaSeed::parse(seed)?,
+            count: 0,
+            sum: 0.0,
+            value: f64::NAN,
+            recent: VecDeque::with_capacity(zero_lag_shift(period) + 1),
+        })
+    }
+    
+    /// Calcule l'EMA sur une série complète
+    /// `period` et `seed` valent par défaut ceux du constructeur : même
+    /// résultat que des appels successifs à `update`
+    /// seed = "sma" : première valeur = SMA des `period` premières
+    /// valeurs, NaN avant. "first" : amorcée sur la première valeur, définie
+    /// dès la première barre mais biaisée vers celle-ci pendant environ
+    /// `period` barres. "zero_lag" : ZLEMA, EMA de 2 * x[i] - x[i - lag]
+    /// avec lag = (period - 1) / 2, amorcée par SMA ; NaN pendant
+    /// lag + period - 1 barres, suit le prix avec moins de retard.
+    #[pyo3(signature = (data, period=None, seed=None, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        data: PriceSeries<'_>,
+        period: Option<usize>,
+        seed: Option<&str>,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let data = data.as_slice(nan_policy);
+        if data.is_empty() {
+            return Err(HftError::EmptyInput("données").into());
+        }
+        
+        let (period, seed) = self.batch_params(period, seed)?;
+        check_period("period", period, data.len())?;
+        
+        let ema = py.allow_threads(|| calc_ema_seeded(&data, period, seed));
+        check_causal("ema", &[&ema], |k| vec![calc_ema_seeded(&data[..k], period, seed)])?;
+        Ok(to_pyarray(py, ema))
+    }
+    
+    /// Intègre une nouvelle valeur (temps réel)
+    /// Même amorçage que `calculate` avec le `seed` du constructeur
+    pub(crate) fn update(&mut self, value: f64) -> f64 {
+        let value = match self.seed {
+            EmaSeed::ZeroLag => {
+                self.recent.push_back(value);
+                if self.recent.len() <= zero_lag_shift(self.period) {
+                    return self.value;
+                }
+                let lagged = self.recent.pop_front().unwrap_or(value);
+                2.0 * value - lagged
+            }
+            _ => value,
+        };
+        
+        self.count += 1;
+        let multiplier = 2.0 / (self.period as f64 + 1.0);
+        
+        if self.seed == EmaSeed::First {
+            if self.count == 1 {
+                self.value = value;
+            } else {
+                self.value += (value - self.value) * multiplier;
+            }
+        } else if self.count < self.period {
+            self.sum += value;
+        } else if self.count == self.period {
+            self.value = (self.sum + value) / self.period as f64;
+        } else {
+            self.value += (value - self.value) * multiplier;
+        }
+        
+        self.value
+    }
+    
+    /// Dernière valeur de l'EMA incrémentale
+    #[getter]
+    fn value(&self) -> f64 {
+        self.value
+    }
+    
+    /// Vrai dès que `update` retourne une valeur définie : après `period`
+    /// valeurs ("sma"), la première ("first") ou lag + period ("zero_lag")
+    fn is_ready(&self) -> bool {
+        match self.seed {
+            EmaSeed::First => self.count >= 1,
+            _ => self.count >= self.period,
+        }
+    }
+    
+    /// Réinitialise l'état incrémental
+    fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0.0;
+        self.value = f64::NAN;
+        self.recent.clear();
+    }
+}
+
+impl EMACalculator {
+    /// Période et amorçage de `calculate`, ceux du constructeur à défaut
+    fn batch_params(
+        &self,
+        period: Option<usize>,
+        seed: Option<&str>,
+    ) -> PyResult<(usize, EmaSeed)> {
+        let seed = match seed {
+            Some(seed) => EmaSeed::parse(seed)?,
+            None => self.seed,
+        };
+        Ok((period.unwrap_or(self.period), seed))
+    }
+}
+
+/// Calculateur MACD : ligne, signal et histogramme
+#[pyclass]
+pub struct MACDCalculator;
+
+#[pymethods]
+impl MACDCalculator {
+    #[new]
+    fn new() -> Self {
+        MACDCalculator
+    }
+    
+    /// Calcule le MACD
+    /// Retourne: (macd, signal, histogramme)
+    /// Le signal (EMA du MACD) ne démarre qu'une fois le MACD valide
+    #[pyo3(signature = (closes, fast=12, slow=26, signal=9, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
+        fast: usize,
+        slow: usize,
+        signal: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries, PySeries)> {
+        
+        let closes = closes.as_slice(nan_policy);
+        if closes.is_empty() {
+            return Err(HftError::EmptyInput("closes").into());
+        }
+        
+        check_periods(&[
+            ("fast", fast),
+            ("slow", slow),
+            ("signal", signal),
+        ], closes.len())?;
+        
+        let (macd, signal_line, histogram) = py.allow_threads(|| {
+            calc_macd(&closes, fast, slow, signal)
+        });
+        check_causal("macd", &[&macd, &signal_line, &histogram], |k| {
+            let (m, s, h) = calc_macd(&closes[..k], fast, slow, signal);
+            vec![m, s, h]
+        })?;
+        
+        Ok((
+            to_pyarray(py, macd),
+            to_pyarray(py, signal_line),
+            to_pyarray(py, histogram),
+        ))
+    }
+}
+
+/// Fonction helper pour calculer la ligne MACD (EMA rapide - EMA lente)
+fn calc_macd_line(closes: &[f64], fast: usize, slow: usize) -> Vec<f64> {
+    let fast_ema = calc_ema(closes, fast);
+    let slow_ema = calc_ema(closes, slow);
+    
+    fast_ema.iter()
+        .zip(slow_ema.iter())
+        .map(|(f, s)| f - s)
+        .collect()
+}
+
+/// Fonction helper pour calculer (macd, signal, histogramme)
+fn calc_macd(
+    closes: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let macd = calc_macd_line(closes, fast, slow);
+    
+    // calc_ema ignore les NaN de tête : le signal démarre avec le MACD
+    let signal_line = calc_ema(&macd, signal);
+    
+    let histogram = macd.iter()
+        .zip(signal_line.iter())
+        .map(|(m, s)| m - s)
+        .collect();
+    
+    (macd, signal_line, histogram)
+}
+
+/// Calculateur de bandes de Bollinger
+#[pyclass]
+pub struct BollingerCalculator;
+
+#[pymethods]
+impl BollingerCalculator {
+    #[new]
+    fn new() -> Self {
+        BollingerCalculator
+    }
+    
+    /// Calcule les bandes de Bollinger
+    /// Retourne: (upper, middle, lower), écart-type de population
+    #[pyo3(signature = (closes, period=20, num_std=2.0, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
+        period: usize,
+        num_std: f64,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries, PySeries)> {
+        
+        let closes = closes.as_slice(nan_policy);
+        if closes.is_empty() {
+            return Err(HftError::EmptyInput("closes").into());
+        }
+        
+        check_period("period", period, closes.len())?;
+        
+        let (upper, middle, lower) = py.allow_threads(|| {
+            calc_bollinger(&closes, period, num_std)
+        });
+        check_causal("bollinger", &[&upper, &middle, &lower], |k| {
+            let (u, m, l) = calc_bollinger(&closes[..k], period, num_std);
+            vec![u, m, l]
+        })?;
+        
+        Ok((
+            to_pyarray(py, upper),
+            to_pyarray(py, middle),
+            to_pyarray(py, lower),
+        ))
+    }
+}
+
+/// Fonction helper pour calculer les bandes de Bollinger
+fn calc_bollinger(closes: &[f64], period: usize, num_std: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let (middle, std) = calc_rolling_mean_std(closes, period);
+    
+    let upper = middle.iter().zip(&std).map(|(m, s)| m + num_std * s).collect();
+    let lower = middle.iter().zip(&std).map(|(m, s)| m - num_std * s).collect();
+    
+    (upper, middle, lower)
+}
+
+/// Moyenne et écart-type de population glissants (Welford), O(n)
+/// La fenêtre est recalculée en deux passes toutes les `period` barres
+/// pour borner la dérive numérique sur les prix élevés (or ~2000), et
+/// dès qu'elle ne contient plus de NaN ; NaN tant qu'un NaN y est
+pub(crate) fn calc_rolling_mean_std(data: &[f64], period: usize) -> (Vec<f64>, Vec<f64>) {
+    let len = data.len();
+    let mut means = vec![f64::NAN; len];
+    let mut stds = vec![f64::NAN; len];
+    
+    if period == 0 || period > len {
+        return (means, stds);
+    }
+    
+    let p = period as f64;
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+    // NaN dans la fenêtre, et validité de (mean, m2) pour la précédente
+    let mut nans = data[..period - 1].iter().filter(|x| x.is_nan()).count();
+    let mut tracking = false;
+    
+    for i in (period - 1)..len {
+        let start = i + 1 - period;
+        nans += data[i].is_nan() as usize;
+        if start > 0 {
+            nans -= data[start - 1].is_nan() as usize;
+        }
+        if nans > 0 {
+            tracking = false;
+            continue;
+        }
+        
+        if !tracking || (i + 1) % period == 0 {
+            tracking = true;
+            // Recalage exact : moyenne puis somme des carrés des écarts
+            let window = &data[start..=i];
+            mean = window.iter().sum::<f64>() / p;
+            m2 = window.iter().map(|x| (x - mean) * (x - mean)).sum();
+        } else {
+            // Remplacement de la valeur sortante par la nouvelle
+            let (new, old) = (data[i], data[start - 1]);
+            let prev_mean = mean;
+            mean += (new - old) / p;
+            m2 += (new - old) * (new - mean + old - prev_mean);
+        }
+        
+        means[i] = mean;
+        stds[i] = (m2.max(0.0) / p).sqrt();
+    }
+    
+    (means, stds)
+}
+
+/// Calculateur d'écart-type glissant
+#[pyclass]
+pub struct StdDevCalculator;
+
+#[pymethods]
+impl StdDevCalculator {
+    #[new]
+    fn new() -> Self {
+        StdDevCalculator
+    }
+    
+    /// Calcule l'écart-type de population glissant
+    /// NaN pendant les `period - 1` premières barres
+    #[pyo3(signature = (data, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        data: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let data = data.as_slice(nan_policy);
+        if data.is_empty() {
+            return Err(HftError::EmptyInput("données").into());
+        }
+        
+        check_period("period", period, data.len())?;
+        
+        let std = py.allow_threads(|| calc_rolling_mean_std(&data, period).1);
+        check_causal("stddev", &[&std], |k| vec![calc_rolling_mean_std(&data[..k], period).1])?;
+        Ok(to_pyarray(py, std))
+    }
+}
+
+/// Calculateur ATR (Average True Range, lissage de Wilder)
+#[pyclass]
+pub struct ATRCalculator;
+
+#[pymethods]
+impl ATRCalculator {
+    #[new]
+    fn new() -> Self {
+        ATRCalculator
+    }
+    
+    /// Calcule l'ATR
+    /// NaN sur la première barre (pas de close précédent) et le warm-up
+    #[pyo3(signature = (highs, lows, closes, period=14, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        closes: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let (highs, lows, closes) = (
+            highs.as_slice(nan_policy),
+            lows.as_slice(nan_policy),
+            closes.as_slice(nan_policy),
+        );
+        check_same_len(&[&highs, &lows, &closes])?;
+        
+        check_period("period", period, closes.len())?;
+        
+        let atr = py.allow_threads(|| calc_atr(&highs, &lows, &closes, period));
+        check_causal("atr", &[&atr], |k| {
+            vec![calc_atr(&highs[..k], &lows[..k], &closes[..k], period)]
+        })?;
+        Ok(to_pyarray(py, atr))
+    }
+}
+
+/// Fonction helper pour calculer le True Range
+/// max(high - low, |high - close précédent|, |low - close précédent|),
+/// NaN sur la première barre
+fn calc_true_range(highs: &[f64], lows: &[f64], closes: &[f64]) -> Vec<f64> {
+    let len = highs.len();
+    let mut result = vec![f64::NAN; len];
+    
+    for i in 1..len {
+        let prev_close = closes[i - 1];
+        result[i] = (highs[i] - lows[i])
+            .max((highs[i] - prev_close).abs())
+            .max((lows[i] - prev_close).abs());
+    }
+    
+    result
+}
+
+/// Fonction helper de lissage de Wilder
+/// Amorce = moyenne simple des `period` premières valeurs valides,
+/// puis result[i] = (result[i-1] * (period - 1) + data[i]) / period
+fn calc_wilder(data: &[f64], period: usize) -> Vec<f64> {
+    let len = data.len();
+    let mut result = vec![f64::NAN; len];
+    
+    let first = match first_valid(data) {
+        Some(first) if period > 0 && first + period <= len => first,
+        _ => return result,
+    };
+    
+    let p = period as f64;
+    let seed = first + period - 1;
+    result[seed] = data[first..=seed].iter().sum::<f64>() / p;
+    
+    for i in (seed + 1)..len {
+        result[i] = (result[i - 1] * (p - 1.0) + data[i]) / p;
+    }
+    
+    result
+}
+
+/// Fonction helper pour calculer l'ATR
+fn calc_atr(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Vec<f64> {
+    calc_wilder(&calc_true_range(highs, lows, closes), period)
+}
+
+/// Calculateur d'oscillateur stochastique (%K / %D)
+#[pyclass]
+pub struct StochasticCalculator;
+
+#[pymethods]
+impl StochasticCalculator {
+    #[new]
+    fn new() -> Self {
+        StochasticCalculator
+    }
+    
+    /// Calcule le stochastique sur le range high/low
+    /// Retourne (%K, %D) avec %D = SMA de %K sur `d_period`
+    /// Range high/low nul (marché plat) : %K précédent reconduit
+    #[pyo3(signature = (highs, lows, closes, k_period=14, d_period=3, nan_policy=NanPolicy::Skip))]
+    #[allow(clippy::too_many_arguments)]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        closes: PriceSeries<'_>,
+        k_period: usize,
+        d_period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries)> {
+        
+        let (highs, lows, closes) = (
+            highs.as_slice(nan_policy),
+            lows.as_slice(nan_policy),
+            closes.as_slice(nan_policy),
+        );
+        check_same_len(&[&highs, &lows, &closes])?;
+        
+        check_periods(&[
+            ("k_period", k_period),
+            ("d_period", d_period),
+        ], closes.len())?;
+        
+        let stochastic = |len: usize| {
+            let k = calc_stochastic_hlc(&highs[..len], &lows[..len], &closes[..len], k_period);
+            let d = calc_sma(&k, d_period);
+            (k, d)
+        };
+        
+        let (k, d) = py.allow_threads(|| stochastic(closes.len()));
+        check_causal("stochastic", &[&k, &d], |len| {
+            let (k, d) = stochastic(len);
+            vec![k, d]
+        })?;
+        Ok((to_pyarray(py, k), to_pyarray(py, d)))
+    }
+}
+
+/// Fonction helper pour calculer le %K brut, O(n)
+/// 100 * (close - plus bas) / (plus haut - plus bas) sur la fenêtre ;
+/// range nul : %K précédent reconduit (50 sans précédent)
+fn calc_stochastic_hlc(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Vec<f64> {
+    let len = closes.len();
+    let mut result = vec![f64::NAN; len];
+    
+    if period == 0 {
+        return result;
+    }
+    
+    let mut max_high = RollingExtremum::new(period, true);
+    let mut min_low = RollingExtremum::new(period, false);
+    let mut previous = f64::NAN;
+    for i in 0..len {
+        let (max, min) = (max_high.push(highs[i]), min_low.push(lows[i]));
+        if !max_high.is_full() {
+            continue;
+        }
+        
+        if (max - min).abs() < 1e-10 {
+            result[i] = FlatRange::Carry.value(previous);
+        } else {
+            result[i] = 100.0 * (closes[i] - min) / (max - min);
+        }
+        if !result[i].is_nan() {
+            previous = result[i];
+        }
+    }
+    
+    result
+}
+
+/// Calculateur ADX / DMI (Wilder)
+#[pyclass]
+pub struct ADXCalculator;
+
+#[pymethods]
+impl ADXCalculator {
+    #[new]
+    fn new() -> Self {
+        ADXCalculator
+    }
+    
+    /// Calcule (+DI, -DI, ADX)
+    /// NaN sur la première barre et pendant le warm-up (2 * period pour l'ADX)
+    #[pyo3(signature = (highs, lows, closes, period=14, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        closes: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries, PySeries)> {
+        
+        let (highs, lows, closes) = (
+            highs.as_slice(nan_policy),
+            lows.as_slice(nan_policy),
+            closes.as_slice(nan_policy),
+        );
+        check_same_len(&[&highs, &lows, &closes])?;
+        
+        check_period("period", period, closes.len())?;
+        
+        let (plus_di, minus_di, adx) =
+            py.allow_threads(|| calc_adx(&highs, &lows, &closes, period));
+        check_causal("adx", &[&plus_di, &minus_di, &adx], |k| {
+            let (p, m, a) = calc_adx(&highs[..k], &lows[..k], &closes[..k], period);
+            vec![p, m, a]
+        })?;
+        Ok((to_pyarray(py, plus_di), to_pyarray(py, minus_di), to_pyarray(py, adx)))
+    }
+}
+
+/// Fonction helper pour calculer les mouvements directionnels (+DM, -DM)
+/// NaN sur la première barre
+fn calc_directional_movement(highs: &[f64], lows: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let len = highs.len();
+    let mut plus_dm = vec![f64::NAN; len];
+    let mut minus_dm = vec![f64::NAN; len];
+    
+    for i in 1..len {
+        let up = highs[i] - highs[i - 1];
+        let down = lows[i - 1] - lows[i];
+        plus_dm[i] = if up > down && up > 0.0 { up } else { 0.0 };
+        minus_dm[i] = if down > up && down > 0.0 { down } else { 0.0 };
+    }
+    
+    (plus_dm, minus_dm)
+}
+
+/// Fonction helper pour calculer l'ADX
+/// +DI / -DI = 100 * DM lissé / TR lissé, ADX = Wilder(DX)
+fn calc_adx(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let (plus_dm, minus_dm) = calc_directional_movement(highs, lows);
+    let tr = calc_wilder(&calc_true_range(highs, lows, closes), period);
+    let plus_dm = calc_wilder(&plus_dm, period);
+    let minus_dm = calc_wilder(&minus_dm, period);
+    
+    let di = |dm: &[f64]| -> Vec<f64> {
+        dm.iter()
+            .zip(&tr)
+            .map(|(&dm, &tr)| match tr {
+                tr if tr > 0.0 => 100.0 * dm / tr,
+                tr if tr.is_nan() => f64::NAN,
+                _ => 0.0,
+            })
+            .collect()
+    };
+    let plus_di = di(&plus_dm);
+    let minus_di = di(&minus_dm);
+    
+    let dx: Vec<f64> = plus_di
+        .iter()
+        .zip(&minus_di)
+        .map(|(&p, &m)| {
+            match p + m {
+                sum if sum > 0.0 => 100.0 * (p - m).abs() / sum,
+                sum if sum.is_nan() => f64::NAN,
+                _ => 0.0,
+            }
+        })
+        .collect();
+    
+    (plus_di, minus_di, calc_wilder(&dx, period))
+}
+
+/// Calculateur de moyenne mobile simple
+#[pyclass]
+pub struct SMACalculator;
+
+#[pymethods]
+impl SMACalculator {
+    #[new]
+    fn new() -> Self {
+        SMACalculator
+    }
+    
+    /// Calcule la SMA par somme glissante (O(n) quelle que soit la période)
+    /// NaN pendant les `period - 1` premières barres
+    #[pyo3(signature = (data, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        data: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let data = data.as_slice(nan_policy);
+        if data.is_empty() {
+            return Err(HftError::EmptyInput("données").into());
+        }
+        
+        check_period("period", period, data.len())?;
+        
+        let sma = py.allow_threads(|| calc_sma(&data, period));
+        check_causal("sma", &[&sma], |k| vec![calc_sma(&data[..k], period)])?;
+        Ok(to_pyarray(py, sma))
+    }
+}
+
+/// Calculateur de moyenne mobile pondérée linéairement
+#[pyclass]
+pub struct WMACalculator;
+
+#[pymethods]
+impl WMACalculator {
+    #[new]
+    fn new() -> Self {
+        WMACalculator
+    }
+    
+    /// Calcule la WMA : poids `period` sur la barre courante jusqu'à 1 sur
+    /// la plus ancienne, normalisés par period * (period + 1) / 2, O(n)
+    /// NaN pendant les `period - 1` premières barres
+    #[pyo3(signature = (data, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        data: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let data = data.as_slice(nan_policy);
+        if data.is_empty() {
+            return Err(HftError::EmptyInput("données").into());
+        }
+        
+        check_period("period", period, data.len())?;
+        
+        let wma = py.allow_threads(|| calc_wma(&data, period));
+        check_causal("wma", &[&wma], |k| vec![calc_wma(&data[..k], period)])?;
+        Ok(to_pyarray(py, wma))
+    }
+}
+
+/// Calculateur de moyenne mobile pondérée par le volume
+#[pyclass]
+pub struct VWMACalculator;
+
+#[pymethods]
+impl VWMACalculator {
+    #[new]
+    fn new() -> Self {
+        VWMACalculator
+    }
+    
+    /// Calcule la VWMA : somme(prix * volume) / somme(volume) par fenêtre
+    /// NaN pendant le warm-up et sur les fenêtres sans volume
+    #[pyo3(signature = (prices, volumes, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        prices: PriceSeries<'_>,
+        volumes: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let (prices, volumes) = (prices.as_slice(nan_policy), volumes.as_slice(nan_policy));
+        check_same_len(&[&prices, &volumes])?;
+        
+        check_period("period", period, prices.len())?;
+        
+        let vwma = py.allow_threads(|| calc_vwma(&prices, &volumes, period));
+        check_causal("vwma", &[&vwma], |k| {
+            vec![calc_vwma(&prices[..k], &volumes[..k], period)]
+        })?;
+        Ok(to_pyarray(py, vwma))
+    }
+}
+
+/// Fonction helper pour calculer la VWMA par sommes glissantes, O(n)
+/// NaN tant qu'un prix ou un volume NaN est dans la fenêtre
+fn calc_vwma(prices: &[f64], volumes: &[f64], period: usize) -> Vec<f64> {
+    let len = prices.len();
+    let mut result = vec![f64::NAN; len];
+    
+    if period == 0 {
+        return result;
+    }
+    
+    let missing = |i: usize| prices[i].is_nan() || volumes[i].is_nan();
+    let mut sum_pv = 0.0;
+    let mut sum_v = 0.0;
+    // Nombre de volumes non nuls dans la fenêtre : évite de diviser par
+    // un résidu d'arrondi quand la fenêtre ne contient que des zéros
+    let mut traded = 0usize;
+    let mut nans = 0usize;
+    
+    for i in 0..len {
+        if missing(i) {
+            nans += 1;
+        } else {
+            sum_pv += prices[i] * volumes[i];
+            sum_v += volumes[i];
+            traded += (volumes[i] != 0.0) as usize;
+        }
+        
+        if i >= period {
+            let old = i - period;
+            if missing(old) {
+                nans -= 1;
+            } else {
+                sum_pv -= prices[old] * volumes[old];
+                sum_v -= volumes[old];
+                traded -= (volumes[old] != 0.0) as usize;
+            }
+        }
+        
+        if i + 1 >= period && traded > 0 && nans == 0 {
+            result[i] = sum_pv / sum_v;
+        }
+    }
+    
+    result
+}
+
+/// Calculateur de canaux de Keltner
+#[pyclass]
+pub struct KeltnerCalculator;
+
+#[pymethods]
+impl KeltnerCalculator {
+    #[new]
+    fn new() -> Self {
+        KeltnerCalculator
+    }
+    
+    /// Calcule les canaux de Keltner
+    /// Retourne: (upper, middle, lower) avec middle = EMA des closes et
+    /// bandes = middle ± mult * ATR ; NaN tant que l'un des deux manque
+    #[pyo3(signature = (
+        highs, lows, closes, ema_period=20, atr_period=10, mult=2.0, nan_policy=NanPolicy::Skip
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        closes: PriceSeries<'_>,
+        ema_period: usize,
+        atr_period: usize,
+        mult: f64,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries, PySeries)> {
+        
+        let (highs, lows, closes) = (
+            highs.as_slice(nan_policy),
+            lows.as_slice(nan_policy),
+            closes.as_slice(nan_policy),
+        );
+        check_same_len(&[&highs, &lows, &closes])?;
+        
+        check_periods(&[
+            ("ema_period", ema_period),
+            ("atr_period", atr_period),
+        ], closes.len())?;
+        
+        let (upper, middle, lower) = py.allow_threads(|| {
+            calc_keltner(&highs, &lows, &closes, ema_period, atr_period, mult)
+        });
+        check_causal("keltner", &[&upper, &middle, &lower], |k| {
+            let (u, m, l) = calc_keltner(
+                &highs[..k], &lows[..k], &closes[..k], ema_period, atr_period, mult
+            );
+            vec![u, m, l]
+        })?;
+        
+        Ok((
+            to_pyarray(py, upper),
+            to_pyarray(py, middle),
+            to_pyarray(py, lower),
+        ))
+    }
+}
+
+/// Fonction helper pour calculer les canaux de Keltner
+fn calc_keltner(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    ema_period: usize,
+    atr_period: usize,
+    mult: f64,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let middle = calc_ema(closes, ema_period);
+    let atr = calc_atr(highs, lows, closes, atr_period);
+    
+    let upper = middle.iter().zip(&atr).map(|(m, a)| m + mult * a).collect();
+    let lower = middle.iter().zip(&atr).map(|(m, a)| m - mult * a).collect();
+    
+    (upper, middle, lower)
+}
+
+/// Calculateur On-Balance Volume
+#[pyclass]
+pub struct OBVCalculator;
+
+#[pymethods]
+impl OBVCalculator {
+    #[new]
+    fn new() -> Self {
+        OBVCalculator
+    }
+    
+    /// Calcule l'OBV
+    /// Démarre à 0 sur la première barre, puis ajoute le volume si le close
+    /// monte, le retranche s'il baisse, reste inchangé s'il est plat
+    #[pyo3(signature = (closes, volumes, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
+        volumes: PriceSeries<'_>,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let (closes, volumes) = (closes.as_slice(nan_policy), volumes.as_slice(nan_policy));
+        check_same_len(&[&closes, &volumes])?;
+        
+        let obv = py.allow_threads(|| calc_obv(&closes, &volumes));
+        check_causal("obv", &[&obv], |k| vec![calc_obv(&closes[..k], &volumes[..k])])?;
+        Ok(to_pyarray(py, obv))
+    }
+}
+
+/// Fonction helper pour calculer l'OBV
+fn calc_obv(closes: &[f64], volumes: &[f64]) -> Vec<f64> {
+    let mut result = Vec::with_capacity(closes.len());
+    let mut obv = 0.0;
+    
+    for i in 0..closes.len() {
+        if i > 0 {
+            if closes[i] > closes[i - 1] {
+                obv += volumes[i];
+            } else if closes[i] < closes[i - 1] {
+                obv -= volumes[i];
+            }
+        }
+        result.push(obv);
+    }
+    
+    result
+}
+
+/// Calculateur Parabolic SAR (Wilder)
+#[pyclass]
+pub struct SARCalculator;
+
+#[pymethods]
+impl SARCalculator {
+    #[new]
+    fn new() -> Self {
+        SARCalculator
+    }
+    
+    /// Calcule le Parabolic SAR
+    /// Tendance initiale haussière si la seconde barre monte (médiane
+    /// high/low), SAR amorcé sur l'extrême opposé de la première barre
+    #[pyo3(signature = (
+        highs, lows, af_start=0.02, af_step=0.02, af_max=0.2, nan_policy=NanPolicy::Skip
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        af_start: f64,
+        af_step: f64,
+        af_max: f64,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let (highs, lows) = (highs.as_slice(nan_policy), lows.as_slice(nan_policy));
+        check_same_len(&[&highs, &lows])?;
+        
+        if !(af_start > 0.0 && af_step >= 0.0 && af_max >= af_start) {
+            return Err(HftError::InvalidParameter(
+                "il faut 0 < af_start <= af_max et af_step >= 0".into()
+            )
+            .into());
+        }
+        
+        let sar = py.allow_threads(|| calc_sar(&highs, &lows, af_start, af_step, af_max));
+        check_causal("sar", &[&sar], |k| {
+            vec![calc_sar(&highs[..k], &lows[..k], af_start, af_step, af_max)]
+        })?;
+        Ok(to_pyarray(py, sar))
+    }
+}
+
+/// Fonction helper pour calculer le Parabolic SAR
+/// SAR[i] = SAR[i-1] + af * (EP - SAR[i-1]), borné par les deux plus bas
+/// (resp. hauts) précédents ; retournement sur pénétration, le SAR repart
+/// alors de l'EP et af de af_start
+fn calc_sar(highs: &[f64], lows: &[f64], af_start: f64, af_step: f64, af_max: f64) -> Vec<f64> {
+    let len = highs.len();
+    let mut result = vec![f64::NAN; len];
+    if len == 0 {
+        return result;
+    }
+    
+    let mut long = len < 2 || highs[1] + lows[1] >= highs[0] + lows[0];
+    let mut sar = if long { lows[0] } else { highs[0] };
+    let mut ep = if long { highs[0] } else { lows[0] };
+    let mut af = af_start;
+    result[0] = sar;
+    
+    for i in 1..len {
+        sar += af * (ep - sar);
+        let prev2 = i.saturating_sub(2);
+        
+        if long {
+            sar = sar.min(lows[i - 1]).min(lows[prev2]);
+            if lows[i] < sar {
+                long = false;
+                sar = ep;
+                ep = lows[i];
+                af = af_start;
+            } else if highs[i] > ep {
+                ep = highs[i];
+                af = (af + af_step).min(af_max);
+            }
+        } else {
+            sar = sar.max(highs[i - 1]).max(highs[prev2]);
+            if highs[i] > sar {
+                long = true;
+                sar = ep;
+                ep = highs[i];
+                af = af_start;
+            } else if lows[i] < ep {
+                ep = lows[i];
+                af = (af + af_step).min(af_max);
+            }
+        }
+        
+        result[i] = sar;
+    }
+    
+    result
+}
+
+/// Calculateur Hull Moving Average
+#[pyclass]
+pub struct HMACalculator;
+
+#[pymethods]
+impl HMACalculator {
+    #[new]
+    fn new() -> Self {
+        HMACalculator
+    }
+    
+    /// Calcule la HMA : WMA(2 * WMA(n/2) - WMA(n), sqrt(n))
+    /// NaN pendant les `period + floor(sqrt(period)) - 2` premières barres
+    #[pyo3(signature = (data, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        data: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<PySeries> {
+        
+        let data = data.as_slice(nan_policy);
+        if data.is_empty() {
+            return Err(HftError::EmptyInput("données").into());
+        }
+        
+        check_period("period", period, data.len())?;
+        
+        let hma = py.allow_threads(|| calc_hma(&data, period));
+        check_causal("hma", &[&hma], |k| vec![calc_hma(&data[..k], period)])?;
+        Ok(to_pyarray(py, hma))
+    }
+}
+
+/// Fonction helper pour calculer la HMA
+fn calc_hma(data: &[f64], period: usize) -> Vec<f64> {
+    let half = calc_wma(data, (period / 2).max(1));
+    let full = calc_wma(data, period);
+    let sqrt_period = ((period as f64).sqrt() as usize).max(1);
+    
+    let raw: Vec<f64> = half.iter().zip(&full).map(|(h, f)| 2.0 * h - f).collect();
+    calc_wma(&raw, sqrt_period)
+}
+
+/// Calculateur Stochastic RSI
+#[pyclass]
+pub struct StochRSICalculator;
+
+#[pymethods]
+impl StochRSICalculator {
+    #[new]
+    fn new() -> Self {
+        StochRSICalculator
+    }
+    
+    /// Calcule le stochastique du RSI
+    /// Retourne (%K, %D) : %K = SMA(stoch(RSI), k), %D = SMA(%K, d) ;
+    /// NaN pendant le warm-up cumulé des quatre étapes. RSI constant sur la
+    /// fenêtre : stochastique précédent reconduit
+    #[pyo3(signature = (
+        closes, rsi_period=14, stoch_period=14, k=3, d=3, nan_policy=NanPolicy::Skip
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        closes: PriceSeries<'_>,
+        rsi_period: usize,
+        stoch_period: usize,
+        k: usize,
+        d: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries)> {
+        
+        let closes = closes.as_slice(nan_policy);
+        if closes.is_empty() {
+            return Err(HftError::EmptyInput("closes").into());
+        }
+        
+        check_periods(&[
+            ("rsi_period", rsi_period),
+            ("stoch_period", stoch_period),
+            ("k", k),
+            ("d", d),
+        ], closes.len())?;
+        
+        let (k_period, d_period) = (k, d);
+        let (k, d) = py.allow_threads(|| {
+            calc_stoch_rsi(&closes, rsi_period, stoch_period, k_period, d_period)
+        });
+        check_causal("stoch_rsi", &[&k, &d], |len| {
+            let (k, d) = calc_stoch_rsi(
+                &closes[..len], rsi_period, stoch_period, k_period, d_period
+            );
+            vec![k, d]
+        })?;
+        Ok((to_pyarray(py, k), to_pyarray(py, d)))
+    }
+}
+
+/// Fonction helper pour calculer le Stochastic RSI : (%K, %D)
+/// Bornés à [0, 100] : les sommes glissantes des SMA laissent des résidus
+/// d'arrondi (-1e-14) après une suite de zéros
+fn calc_stoch_rsi(
+    closes: &[f64],
+    rsi_period: usize,
+    stoch_period: usize,
+    k_period: usize,
+    d_period: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let rsi = calc_rsi(closes, rsi_period);
+    let stoch = calc_stochastic(&rsi, stoch_period, FlatRange::Carry);
+    let bounded = |line: Vec<f64>| -> Vec<f64> {
+        line.into_iter().map(|v| v.clamp(0.0, 100.0)).collect()
+    };
+    let k = bounded(calc_sma(&stoch, k_period));
+    let d = bounded(calc_sma(&k, d_period));
+    (k, d)
+}
+
+/// Calculateur de canaux de Donchian
+#[pyclass]
+pub struct DonchianCalculator;
+
+#[pymethods]
+impl DonchianCalculator {
+    #[new]
+    fn new() -> Self {
+        DonchianCalculator
+    }
+    
+    /// Calcule les canaux de Donchian (fenêtre incluant la barre courante)
+    /// Retourne: (upper, lower, middle) ; NaN pendant le warm-up
+    #[pyo3(signature = (highs, lows, period=20, nan_policy=NanPolicy::Skip))]
+    fn calculate(
+        &self,
+        py: Python<'_>,
+        highs: PriceSeries<'_>,
+        lows: PriceSeries<'_>,
+        period: usize,
+        nan_policy: NanPolicy,
+    ) -> PyResult<(PySeries, PySeries, PySeries)> {
+        
+        let (highs, lows) = (highs.as_slice(nan_policy), lows.as_slice(nan_policy));
+        check_same_len(&[&highs, &lows])?;
+        check_period("period", period, highs.len())?;
+        
+        let (upper, lower, middle) = py.allow_threads(|| calc_donchian(&highs, &lows, period));
+        check_causal("donchian", &[&upper, &lower, &middle], |k| {
+            let (u, l, m) = calc_donchian(&highs[..k], &lows[..k], period);
+            vec![u, l, m]
+        })?;
+        
+        Ok((
+            to_pyarray(py, upper),
+            to_pyarray(py, lower),
+            to_pyarray(py, middle),
+        ))
+    }
+}
+
+/// Fonction helper pour calculer les canaux de Donchian, O(n)
+fn calc_donchian(highs: &[f64], lows: &[f64], period: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut max_high = RollingExtremum::new(period, true);
+    let mut min_low = RollingExtremum::new(period, false);
+    
+    let upper: Vec<f64> = highs.iter().map(|&h| max_high.push(h)).collect();
+    let lower: Vec<f64> = lows.iter().map(|&l| min_low.push(l)).collect();
+    let middle = upper.iter().zip(&lower).map(|(u, l)| (u + l) / 2.0).collect();
+    
+    (upper, lower, middle)
+}
+
+/// Nombre de barres NaN de tête d'un indicateur batch pour des paramètres
+/// donnés : la première barre où toutes ses sorties sont définies
+///
+/// `indicator` : nom du calculateur en minuscules (ichimoku, stc, rsi, ema,
+/// macd, bollinger, stddev, atr, stochastic, adx, sma, wma, vwma, keltner,
+/// obv, sar, hma, stoch_rsi, donchian). `params` : périodes nommées comme dans
+/// `calculate`, valeurs par défaut sinon. Valable pour des entrées sans
+/// NaN. Ichimoku : Chikou exclue, Senkou non décalée ; EMA : amorçage
+/// "sma".
+#[pyfunction]
+#[pyo3(signature = (indicator, params=None))]
+pub(crate) fn warmup_bars(
+    indicator: &str,
+    params: Option<HashMap<String, usize>>,
+) -> PyResult<usize> {
+    let params = params.unwrap_or_default();
+    let names: &[(&str, usize)] = match indicator {
+        "ichimoku" => &[("tenkan_period", 9), ("kijun_period", 26), ("senkou_b_period", 52)],
+        "stc" => &[("period", 10), ("fast_length", 23), ("slow_length", 50)],
+        "rsi" | "atr" | "adx" => &[("period", 14)],
+        "ema" | "bollinger" | "stddev" | "sma" | "wma" | "vwma" | "hma" | "donchian" => {
+            &[("period", 20)]
+        }
+        "macd" => &[("fast", 12), ("slow", 26), ("signal", 9)],
+        "stochastic" => &[("k_period", 14), ("d_period", 3)],
+        "keltner" => &[("ema_period", 20), ("atr_period", 10)],
+        "stoch_rsi" => &[("rsi_period", 14), ("stoch_period", 14), ("k", 3), ("d", 3)],
+        "obv" | "sar" => &[],
+        _ => {
+            return Err(HftError::InvalidParameter(format!(
+                "indicateur inconnu '{}'", indicator
+            ))
+            .into());
+        }
+    };
+    if let Some(unknown) = params.keys().find(|k| names.iter().all(|(n, _)| n != k)) {
+        return Err(HftError::InvalidParameter(format!(
+            "paramètre '{}' inconnu pour {}", unknown, indicator
+        ))
+        .into());
+    }
+    
+    let values: Vec<usize> = names
+        .iter()
+        .map(|&(name, default)| params.get(name).copied().unwrap_or(default))
+        .collect();
+    if let Some(i) = values.iter().position(|&p| p == 0) {
+        return Err(HftError::InvalidPeriod(format!("{} doit être > 0", names[i].0)).into());
+    }
+    Ok(warmup_len(indicator, &values))
+}
+
+/// Longueur de warm-up en forme close des indicateurs de `warmup_bars`
+/// `p` : périodes dans l'ordre de `warmup_bars`, toutes > 0
+fn warmup_len(indicator: &str, p: &[usize]) -> usize {
+    match indicator {
+        "ichimoku" => p[0].max(p[1]).max(p[2]) - 1,
+        // Ligne MACD, puis deux stochastiques ; les lissages n'ajoutent rien
+        "stc" => p[1].max(p[2]) + 2 * p[0] - 3,
+        // `period` variations, donc `period` + 1 clôtures
+        "rsi" | "atr" => p[0],
+        "ema" | "bollinger" | "stddev" | "sma" | "wma" | "vwma" | "donchian" => p[0] - 1,
+        "macd" => p[0].max(p[1]) + p[2] - 2,
+        "stochastic" => p[0] + p[1] - 2,
+        // DI lissés sur `period` variations, puis ADX sur `period` DX
+        "adx" => 2 * p[0] - 1,
+        "keltner" => (p[0] - 1).max(p[1]),
+        "hma" => p[0] + ((p[0] as f64).sqrt() as usize).max(1) - 2,
+        "stoch_rsi" => p[0] + p[1] + p[2] + p[3] - 3,
+        // obv, sar
+        _ => 0,
+    }
+}
+
+/// Indicateur demandé à `compute_indicators`, paramètres validés
+enum BatchIndicator {
+    Ichimoku { tenkan: usize, kijun: usize, senkou_b: usize },
+    Stc { period: usize, fast: usize, slow: usize, smoothing: f64 },
+    Rsi { period: usize },
+    Macd { fast: usize, slow: usize, signal: usize },
+    Ema { period: usize },
+    Sma { period: usize },
+    Atr { period: usize },
+    Bollinger { period: usize, num_std: f64 },
+}
+
+impl BatchIndicator {
+    /// Lit les paramètres d'un indicateur (valeurs par défaut des
+    /// calculateurs) ; lève InvalidParameter sur une clé inconnue
+    fn parse(name: &str, params: Option<&PyDict>, len: usize) -> PyResult<Self> {
+        let mut params = BatchParams {
+            values: match params {
+                Some(params) => params.extract()?,
+                None => HashMap::new(),
+            },
+            len,
+        };
+        
+        let indicator = match name {
+            "ichimoku" => BatchIndicator::Ichimoku {
+                tenkan: params.period("tenkan_period", 9)?,
+                kijun: params.period("kijun_period", 26)?,
+                senkou_b: params.period("senkou_b_period", 52)?,
+            },
+            "stc" => BatchIndicator::Stc {
+                period: params.period("period", 10)?,
+                fast: params.period("fast_length", 23)?,
+                slow: params.period("slow_length", 50)?,
+                smoothing: params.float("smoothing", 0.5)?,
+            },
+            "rsi" => BatchIndicator::Rsi { period: params.period("period", 14)? },
+            "macd" => BatchIndicator::Macd {
+                fast: params.period("fast", 12)?,
+                slow: params.period("slow", 26)?,
+                signal: params.period("signal", 9)?,
+            },
+            "ema" => BatchIndicator::Ema { period: params.period("period", 20)? },
+            "sma" => BatchIndicator::Sma { period: params.period("period", 20)? },
+            "atr" => BatchIndicator::Atr { period: params.period("period", 14)? },
+            "bollinger" => BatchIndicator::Bollinger {
+                period: params.period("period", 20)?,
+                num_std: params.float("num_std", 2.0)?,
+            },
+            _ => {
+                return Err(HftError::InvalidParameter(format!(
+                    "indicateur inconnu '{}' (ichimoku, stc, rsi, macd, ema, sma, atr, bollinger)",
+                    name
+                ))
+                .into());
+            }
+        };
+        
+        if let BatchIndicator::Stc { smoothing, .. } = indicator {
+            if !(smoothing > 0.0 && smoothing <= 1.0) {
+                return Err(HftError::InvalidParameter(
+                    "le facteur de lissage doit être dans ]0, 1]".into()
+                )
+                .into());
+            }
+        }
+        if let Some(unknown) = params.values.keys().next() {
+            return Err(HftError::InvalidParameter(format!(
+                "paramètre '{}' inconnu pour {}", unknown, name
+            ))
+            .into());
+        }
+        Ok(indicator)
+    }
+    
+    /// Clés des sorties dans le dict résultat, dans l'ordre de `compute`
+    fn keys(&self) -> &'static [&'static str] {
+        match self {
+            BatchIndicator::Ichimoku { .. } => {
+                &["tenkan", "kijun", "senkou_a", "senkou_b", "chikou"]
+            }
+            BatchIndicator::Stc { .. } => &["stc"],
+            BatchIndicator::Rsi { .. } => &["rsi"],
+            BatchIndicator::Macd { .. } => &["macd", "macd_signal", "macd_histogram"],
+            BatchIndicator::Ema { .. } => &["ema"],
+            BatchIndicator::Sma { .. } => &["sma"],
+            BatchIndicator::Atr { .. } => &["atr"],
+            BatchIndicator::Bollinger { .. } => {
+                &["bollinger_upper", "bollinger_middle", "bollinger_lower"]
+            }
+        }
+    }
+    
+    /// Nombre de sorties de tête soumises au contrôle de causalité
+    /// (Chikou exclue, comme pour IchimokuCalculator)
+    fn causal_outputs(&self) -> usize {
+        match self {
+            BatchIndicator::Ichimoku { .. } => 4,
+            _ => self.keys().len(),
+        }
+    }
+    
+    fn compute(&self, highs: &[f64], lows: &[f64], closes: &[f64]) -> Vec<Vec<f64>> {
+        match *self {
+            BatchIndicator::Ichimoku { tenkan, kijun, senkou_b } => {
+                let (t, k, a, b, c) = calc_ichimoku(highs, lows, closes, tenkan, kijun, senkou_b);
+                vec![t, k, a, b, c]
+            }
+            BatchIndicator::Stc { period, fast, slow, smoothing } => {
+                vec![calc_stc(closes, period, fast, slow, smoothing, FlatRange::Carry)]
+            }
+            BatchIndicator::Rsi { period } => vec![calc_rsi(closes, period)],
+            BatchIndicator::Macd { fast, slow, signal } => {
+                let (macd, signal_line, histogram) = calc_macd(closes, fast, slow, signal);
+                vec![macd, signal_line, histogram]
+            }
+            BatchIndicator::Ema { period } => vec![calc_ema(closes, period)],
+            BatchIndicator::Sma { period } => vec![calc_sma(closes, period)],
+            BatchIndicator::Atr { period } => vec![calc_atr(highs, lows, closes, period)],
+            BatchIndicator::Bollinger { period, num_std } => {
+                let (upper, middle, lower) = calc_bollinger(closes, period, num_std);
+                vec![upper, middle, lower]
+            }
+        }
+    }
+}
+
+/// Paramètres d'un indicateur de `compute_indicators`, consommés à la lecture
+struct BatchParams<'py> {
+    values: HashMap<String, &'py PyAny>,
+    len: usize,
+}
+
+impl BatchParams<'_> {
+    fn period(&mut self, key: &str, default: usize) -> PyResult<usize> {
+        let period = match self.values.remove(key) {
+            Some(value) => value.extract()?,
+            None => default,
+        };
+        check_period(key, period, self.len)?;
+        Ok(period)
+    }
+    
+    fn float(&mut self, key: &str, default: f64) -> PyResult<f64> {
+        match self.values.remove(key) {
+            Some(value) => value.extract(),
+            None => Ok(default),
+        }
+    }
+}
+
+/// Calcule plusieurs indicateurs en un appel sur les mêmes séries
+///
+/// `config` : {nom: paramètres ou None}, noms ichimoku, stc, rsi, macd,
+/// ema, sma, atr, bollinger, paramètres nommés comme dans `calculate`.
+/// Retourne un dict {sortie: ndarray} : tenkan, kijun, senkou_a, senkou_b,
+/// chikou, stc, rsi, macd, macd_signal, macd_histogram, ema, sma, atr,
+/// bollinger_upper / _middle / _lower. Résultats identiques aux
+/// calculateurs (Ichimoku non décalé, EMA amorcée "sma") ; les
+/// indicateurs sont calculés en parallèle, GIL relâché.
+#[pyfunction]
+#[pyo3(signature = (highs, lows, closes, config, nan_policy=NanPolicy::Skip))]
+pub(crate) fn compute_indicators<'py>(
+    py: Python<'py>,
+    highs: PriceSeries<'py>,
+    lows: PriceSeries<'py>,
+    closes: PriceSeries<'py>,
+    config: &'py PyDict,
+    nan_policy: NanPolicy,
+) -> PyResult<&'py PyDict> {
+    let (highs, lows, closes) = (
+        highs.as_slice(nan_policy),
+        lows.as_slice(nan_policy),
+        closes.as_slice(nan_policy),
+    );
+    check_same_len(&[&highs, &lows, &closes])?;
+    
+    let jobs = config
+        .iter()
+        .map(|(name, params)| {
+            let name: String = name.extract()?;
+            let params: Option<&PyDict> = params.extract()?;
+            let indicator = BatchIndicator::parse(&name, params, closes.len())?;
+            Ok((name, indicator))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    
+    let outputs: Vec<Vec<Vec<f64>>> = py.allow_threads(|| {
+        jobs.par_iter()
+            .map(|(_, indicator)| indicator.compute(&highs, &lows, &closes))
+            .collect()
+    });
+    
+    let result = PyDict::new(py);
+    for ((name, indicator), lines) in jobs.iter().zip(outputs) {
+        let checked: Vec<&[f64]> = lines[..indicator.causal_outputs()]
+            .iter()
+            .map(|line| line.as_slice())
+            .collect();
+        check_causal(name, &checked, |k| {
+            let mut lines = indicator.compute(&highs[..k], &lows[..k], &closes[..k]);
+            lines.truncate(indicator.causal_outputs());
+            lines
+        })?;
+        
+        for (key, line) in indicator.keys().iter().zip(lines) {
+            result.set_item(*key, to_pyarray(py, line))?;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Prix autour de 2000 avec un NaN à l'indice 30
+    fn series_with_hole() -> Vec<f64> {
+        let mut data: Vec<f64> = (0..80).map(|i| 2000.0 + (i as f64 / 3.0).sin() * 5.0).collect();
+        data[30] = f64::NAN;
+        data
+    }
+    
+    fn close(a: f64, b: f64) -> bool {
+        (a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-9
+    }
+    
+    /// NaN sur les fenêtres contenant un NaN, `expected(fenêtre)` sur les
+    /// autres après le warm-up
+    fn assert_resumes_after_hole(
+        name: &str,
+        result: &[f64],
+        data: &[f64],
+        period: usize,
+        expected: impl Fn(&[f64]) -> f64,
+    ) {
+        for i in period - 1..data.len() {
+            let window = &data[i + 1 - period..=i];
+            let want = if window.iter().any(|x| x.is_nan()) { f64::NAN } else { expected(window) };
+            assert!(close(result[i], want), "{} [{}] : {} au lieu de {}", name, i, result[i], want);
+        }
+    }
+    
+    #[test]
+    fn moving_averages_are_nan_only_while_the_hole_is_in_the_window() {
+        let data = series_with_hole();
+        let period = 7;
+        let mean = |w: &[f64]| w.iter().sum::<f64>() / w.len() as f64;
+        
+        assert_resumes_after_hole("sma", &calc_sma(&data, period), &data, period, mean);
+        assert_resumes_after_hole("wma", &calc_wma(&data, period), &data, period, |w| {
+            let weighted: f64 = w.iter().enumerate().map(|(k, x)| (k + 1) as f64 * x).sum();
+            weighted / (w.len() * (w.len() + 1) / 2) as f64
+        });
+        
+        let (means, stds) = calc_rolling_mean_std(&data, period);
+        assert_resumes_after_hole("mean", &means, &data, period, mean);
+        assert_resumes_after_hole("std", &stds, &data, period, |w| {
+            let m = mean(w);
+            (w.iter().map(|x| (x - m) * (x - m)).sum::<f64>() / w.len() as f64).sqrt()
+        });
+        
+        // Un volume NaN a le même effet qu'un prix NaN
+        let prices: Vec<f64> = data.iter().map(|x| if x.is_nan() { 2000.0 } else { *x }).collect();
+        let volumes: Vec<f64> = data.iter().map(|x| if x.is_nan() { *x } else { 2.0 }).collect();
+        let vwma = calc_vwma(&prices, &volumes, period);
+        assert_resumes_after_hole("vwma", &vwma, &data, period, mean);
+    }
+    
+    #[test]
+    fn rolling_extremums_skip_nan_inside_the_window() {
+        let data = series_with_hole();
+        let mut highest = RollingExtremum::new(5, true);
+        let maxima: Vec<f64> = data.iter().map(|&x| highest.push(x)).collect();
+        
+        for i in 4..data.len() {
+            let window = data[i - 4..=i].iter().copied().filter(|x| !x.is_nan());
+            let want = window.fold(f64::MIN, f64::max);
+            assert_eq!(maxima[i], want, "[{}]", i);
+        }
+        
+        let line = calc_ichimoku_line(&data, &data, 9);
+        assert!(line[30..39].iter().all(|x| !x.is_nan()));
+    }
+    
+    #[test]
+    fn ffill_policy_replaces_holes_before_the_calculation() {
+        let mut data = series_with_hole();
+        data[0] = f64::NAN;
+        let series = PriceSeries::List(data.clone());
+        
+        let skipped = series.as_slice(NanPolicy::Skip);
+        assert!(skipped[30].is_nan());
+        
+        let filled = series.as_slice(NanPolicy::Ffill);
+        assert!(filled[0].is_nan());
+        assert_eq!(filled[30], data[29]);
+        assert!(calc_sma(&filled, 7)[30..].iter().all(|x| !x.is_nan()));
+    }
+    
+    /// Warm-up mesuré : première barre où toutes les sorties de
+    /// l'indicateur sont définies sur une série synthétique sans NaN
+    fn measured_warmup(indicator: &str, values: &[usize]) -> usize {
+        let p = |i: usize| values[i];
+        
+        // Série synthétique assez longue pour que chaque sortie soit définie
+        let len = 2 * values.iter().sum::<usize>() + 64;
+        let closes: Vec<f64> = (0..len)
+            .map(|i| {
+                let x = i as f64;
+                2000.0 + 10.0 * (x / 7.0).sin() + 3.0 * (x / 3.1).sin()
+            })
+            .collect();
+        let highs: Vec<f64> = closes.iter()
+            .enumerate()
+            .map(|(i, c)| c + 1.0 + (i as f64).sin().abs())
+            .collect();
+        let lows: Vec<f64> = closes.iter()
+            .enumerate()
+            .map(|(i, c)| c - 1.0 - (i as f64).cos().abs())
+            .collect();
+        let volumes: Vec<f64> = (0..len).map(|i| 1.0 + (i % 5) as f64).collect();
+        let (h, l, c) = (&highs[..], &lows[..], &closes[..]);
+        
+        let outputs: Vec<Vec<f64>> = match indicator {
+            "ichimoku" => {
+                let (t, k, a, b, _) = calc_ichimoku(h, l, c, p(0), p(1), p(2));
+                vec![t, k, a, b]
+            }
+            "stc" => vec![calc_stc(c, p(0), p(1), p(2), 0.5, FlatRange::Carry)],
+            "rsi" => vec![calc_rsi(c, p(0))],
+            "ema" => vec![calc_ema(c, p(0))],
+            "macd" => {
+                let (m, s, hist) = calc_macd(c, p(0), p(1), p(2));
+                vec![m, s, hist]
+            }
+            "bollinger" => {
+                let (u, m, lo) = calc_bollinger(c, p(0), 2.0);
+                vec![u, m, lo]
+            }
+            "stddev" => vec![calc_rolling_mean_std(c, p(0)).1],
+            "atr" => vec![calc_atr(h, l, c, p(0))],
+            "stochastic" => {
+                let k = calc_stochastic_hlc(h, l, c, p(0));
+                let d = calc_sma(&k, p(1));
+                vec![k, d]
+            }
+            "adx" => {
+                let (plus, minus, adx) = calc_adx(h, l, c, p(0));
+                vec![plus, minus, adx]
+            }
+            "sma" => vec![calc_sma(c, p(0))],
+            "wma" => vec![calc_wma(c, p(0))],
+            "vwma" => vec![calc_vwma(c, &volumes, p(0))],
+            "keltner" => {
+                let (u, m, lo) = calc_keltner(h, l, c, p(0), p(1), 2.0);
+                vec![u, m, lo]
+            }
+            "obv" => vec![calc_obv(c, &volumes)],
+            "sar" => vec![calc_sar(h, l, 0.02, 0.02, 0.2)],
+            "hma" => vec![calc_hma(c, p(0))],
+            "stoch_rsi" => {
+                let (k, d) = calc_stoch_rsi(c, p(0), p(1), p(2), p(3));
+                vec![k, d]
+            }
+            _ => {
+                let (u, lo, m) = calc_donchian(h, l, p(0));
+                vec![u, lo, m]
+            }
+        };
+        
+        (0..len)
+            .position(|i| outputs.iter().all(|o| !o[i].is_nan()))
+            .unwrap_or(len)
+    }
+    
+    #[test]
+    fn closed_form_warmup_matches_the_measured_one() {
+        let grids: [(&str, usize); 19] = [
+            ("ichimoku", 3), ("stc", 3), ("rsi", 1), ("ema", 1), ("macd", 3), ("bollinger", 1),
+            ("stddev", 1), ("atr", 1), ("stochastic", 2), ("adx", 1), ("sma", 1), ("wma", 1),
+            ("vwma", 1), ("keltner", 2), ("obv", 0), ("sar", 0), ("hma", 1), ("stoch_rsi", 4),
+            ("donchian", 1),
+        ];
+        let periods = [1, 2, 3, 5, 9, 16, 26];
+        
+        for (indicator, arity) in grids {
+            // Toutes les combinaisons pour une période, quelques-unes sinon
+            let combos: Vec<Vec<usize>> = match arity {
+                0 => vec![vec![]],
+                1 => periods.iter().map(|&p| vec![p]).collect(),
+                _ => (0..periods.len() * 3)
+                    .map(|k| {
+                        (0..arity).map(|j| periods[(k * (j + 2) + j) % periods.len()]).collect()
+                    })
+                    .collect(),
+            };
+            for values in combos {
+                assert_eq!(
+                    warmup_len(indicator, &values),
+                    measured_warmup(indicator, &values),
+                    "{} {:?}",
+                    indicator,
+                    values
+                );
+            }
+        }
+    }
+    
+    #[test]
+    fn ichimoku_state_is_ready_exactly_when_the_batch_output_is_defined() {
+        let closes: Vec<f64> = (0..80).map(|i| 2000.0 + (i as f64 / 4.0).sin() * 6.0).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.5).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.5).collect();
+        let warmup = warmup_len("ichimoku", &[9, 26, 52]);
+        assert_eq!(warmup, 51);
+        
+        let mut state = IchimokuState::new(9, 26, 52).unwrap();
+        for i in 0..closes.len() {
+            let (tenkan, kijun, senkou_a, senkou_b, _) = state.update(highs[i], lows[i], closes[i]);
+            let defined = [tenkan, kijun, senkou_a, senkou_b].iter().all(|x| !x.is_nan());
+            assert_eq!(state.is_ready(), i >= warmup, "[{}]", i);
+            assert_eq!(defined, state.is_ready(), "[{}]", i);
+        }
+    }
+    
+    #[test]
+    fn ema_calculator_is_ready_exactly_when_the_batch_output_is_defined() {
+        let data: Vec<f64> = (0..40).map(|i| 2000.0 + (i as f64 / 3.0).cos() * 4.0).collect();
+        let warmup = warmup_len("ema", &[10]);
+        let batch = calc_ema(&data, 10);
+        
+        let mut ema = EMACalculator::new(10, "sma").unwrap();
+        assert!(!ema.is_ready());
+        for (i, &x) in data.iter().enumerate() {
+            let value = ema.update(x);
+            assert_eq!(ema.is_ready(), i >= warmup, "[{}]", i);
+            assert_eq!(!value.is_nan(), ema.is_ready());
+            assert!(close(value, batch[i]), "[{}] {} / {}", i, value, batch[i]);
+        }
+        
+        let mut first = EMACalculator::new(10, "first").unwrap();
+        first.update(data[0]);
+        assert!(first.is_ready());
+    }
+    
+    #[test]
+    fn ema_calculate_defaults_to_the_constructor_seed_like_update() {
+        let data: Vec<f64> = (0..60).map(|i| 2000.0 + (i as f64 / 3.0).cos() * 4.0).collect();
+        for seed in ["first", "zero_lag"] {
+            let mut ema = EMACalculator::new(10, seed).unwrap();
+            let (period, batch_seed) = ema.batch_params(None, None).unwrap();
+            assert_eq!((period, batch_seed), (10, EmaSeed::parse(seed).unwrap()));
+            
+            let batch = calc_ema_seeded(&data, period, batch_seed);
+            for (i, &x) in data.iter().enumerate() {
+                let value = ema.update(x);
+                assert!(close(value, batch[i]), "{} [{}] {} / {}", seed, i, value, batch[i]);
+            }
+            // Le défaut "sma" diffère bien sur les premières barres
+            assert!(!close(batch[9], calc_ema(&data, 10)[9]));
+        }
+        
+        // Valeurs explicites prioritaires
+        let ema = EMACalculator::new(10, "first").unwrap();
+        assert_eq!(ema.batch_params(Some(5), Some("sma")).unwrap(), (5, EmaSeed::Sma));
+        assert!(ema.batch_params(None, Some("ema")).is_err());
+    }
+    
+    #[test]
+    fn chikou_is_the_close_shifted_back_with_trailing_nans() {
+        let closes: Vec<f64> = (0..40).map(|i| 2000.0 + i as f64).collect();
+        let chikou = calc_chikou(&closes, 26);
+        assert_eq!(chikou.len(), closes.len());
+        for i in 0..closes.len() - 26 {
+            assert_eq!(chikou[i], closes[i + 26], "[{}]", i);
+        }
+        assert!(chikou[closes.len() - 26..].iter().all(|x| x.is_nan()));
+        
+        // Décalage >= longueur : aucune écriture hors bornes, tout NaN
+        assert!(calc_chikou(&closes[..26], 26).iter().all(|x| x.is_nan()));
+        assert!(calc_chikou(&closes[..3], 26).iter().all(|x| x.is_nan()));
+        
+        let (h, l) = (vec![2001.0; 40], vec![1999.0; 40]);
+        let (.., chikou) = calc_ichimoku(&h, &l, &closes, 9, 26, 52);
+        assert_eq!(chikou[5], closes[31]);
+    }
+    
+    #[test]
+    fn ichimoku_lines_match_a_hand_computed_example() {
+        let highs = [10.0, 12.0, 11.0, 15.0, 13.0, 14.0];
+        let lows = [8.0, 9.0, 7.0, 10.0, 11.0, 12.0];
+        let closes = [9.0, 11.0, 8.0, 14.0, 12.0, 13.0];
+        let nan = f64::NAN;
+        
+        // Fenêtre de 3 barres incluant la courante : la barre 3 voit le 15
+        let line = calc_ichimoku_line(&highs, &lows, 3);
+        let want = [nan, nan, 9.5, 11.0, 11.0, 12.5];
+        assert!(line.iter().zip(&want).all(|(&a, &b)| close(a, b)), "{:?}", line);
+        
+        let (tenkan, kijun, senkou_a, senkou_b, _) = calc_ichimoku(&highs, &lows, &closes, 2, 3, 4);
+        let lines = [
+            (tenkan, [nan, 10.0, 9.5, 11.0, 12.5, 12.5]),
+            (kijun, want),
+            (senkou_a, [nan, nan, 9.5, 11.0, 11.75, 12.5]),
+            (senkou_b, [nan, nan, nan, 11.0, 11.0, 11.0]),
+        ];
+        for (got, want) in lines {
+            assert!(got.iter().zip(&want).all(|(&a, &b)| close(a, b)), "{:?}", got);
+        }
+        
+        // Période égale à la longueur : une seule valeur, sur la dernière barre
+        let full = calc_ichimoku_line(&highs, &lows, 6);
+        assert!(full[..5].iter().all(|x| x.is_nan()));
+        assert_eq!(full[5], 11.0);
+    }
+    
+    /// Nombre de NaN de tête d'une sortie
+    fn leading_nans(line: &[f64]) -> usize {
+        line.iter().take_while(|x| x.is_nan()).count()
+    }
+    
+    #[test]
+    fn each_indicator_has_the_documented_number_of_leading_nans() {
+        let closes: Vec<f64> = (0..200).map(|i| 2000.0 + (i as f64 / 5.0).sin() * 8.0).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.0).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        let volumes = vec![3.0; closes.len()];
+        let (h, l, c) = (&highs[..], &lows[..], &closes[..]);
+        
+        let (tenkan, kijun, senkou_a, senkou_b, _) = calc_ichimoku(h, l, c, 9, 26, 52);
+        let (macd, signal, histogram) = calc_macd(c, 12, 26, 9);
+        let (upper, middle, _) = calc_bollinger(c, 20, 2.0);
+        let (plus_di, _, adx) = calc_adx(h, l, c, 14);
+        let k = calc_stochastic_hlc(h, l, c, 14);
+        let expected: [(&str, Vec<f64>, usize); 22] = [
+            ("tenkan", tenkan, 8),
+            ("kijun", kijun, 25),
+            ("senkou_a", senkou_a, 25),
+            ("senkou_b", senkou_b, 51),
+            ("stc", calc_stc(c, 10, 23, 50, 0.5, FlatRange::Carry), 67),
+            ("rsi", calc_rsi(c, 14), 14),
+            ("ema", calc_ema(c, 20), 19),
+            ("macd", macd, 25),
+            ("macd_signal", signal, 33),
+            ("macd_histogram", histogram, 33),
+            ("bollinger_upper", upper, 19),
+            ("bollinger_middle", middle, 19),
+            ("atr", calc_atr(h, l, c, 14), 14),
+            ("stochastic_k", calc_sma(&k, 1), 13),
+            ("stochastic_d", calc_sma(&k, 3), 15),
+            ("plus_di", plus_di, 14),
+            ("adx", adx, 27),
+            ("sma", calc_sma(c, 20), 19),
+            ("wma", calc_wma(c, 20), 19),
+            ("vwma", calc_vwma(c, &volumes, 20), 19),
+            ("hma", calc_hma(c, 20), 22),
+            ("donchian", calc_donchian(h, l, 20).0, 19),
+        ];
+        
+        for (name, line, nans) in expected {
+            assert_eq!(leading_nans(&line), nans, "{}", name);
+            assert!(line[nans..].iter().all(|x| !x.is_nan()), "{} : NaN après le warm-up", name);
+        }
+        assert_eq!(leading_nans(&calc_obv(c, &volumes)), 0);
+        assert_eq!(leading_nans(&calc_sar(h, l, 0.02, 0.02, 0.2)), 0);
+    }
+    
+    #[test]
+    fn streaming_ichimoku_matches_the_batch_output_bar_for_bar() {
+        let closes: Vec<f64> = (0..150).map(|i| 2000.0 + (i as f64 / 6.0).sin() * 9.0).collect();
+        let spread = |i: usize| 1.0 + (i % 3) as f64;
+        let highs: Vec<f64> = closes.iter().enumerate().map(|(i, c)| c + spread(i)).collect();
+        let lows: Vec<f64> = closes.iter().enumerate().map(|(i, c)| c - spread(i + 1)).collect();
+        let (tenkan, kijun, senkou_a, senkou_b, chikou) =
+            calc_ichimoku(&highs, &lows, &closes, 9, 26, 52);
+        
+        let mut state = IchimokuState::new(9, 26, 52).unwrap();
+        for i in 0..closes.len() {
+            let (t, k, a, b, c) = state.update(highs[i], lows[i], closes[i]);
+            let pairs = [(t, tenkan[i]), (k, kijun[i]), (a, senkou_a[i]), (b, senkou_b[i])];
+            assert!(pairs.iter().all(|&(s, b)| close(s, b)), "[{}] {:?}", i, pairs);
+            if i >= 26 {
+                assert_eq!(c, chikou[i - 26]);
+            }
+        }
+    }
+    
+    /// STC de référence écrit d'après la définition, sans les helpers :
+    /// MACD des EMA, stochastique brut, lissage 0.5, stochastique, lissage
+    fn reference_stc(closes: &[f64], period: usize, fast: usize, slow: usize) -> Vec<f64> {
+        let ema = |n: usize| -> Vec<f64> {
+            let alpha = 2.0 / (n as f64 + 1.0);
+            let mut out = vec![f64::NAN; closes.len()];
+            out[n - 1] = closes[..n].iter().sum::<f64>() / n as f64;
+            for i in n..closes.len() {
+                out[i] = out[i - 1] + alpha * (closes[i] - out[i - 1]);
+            }
+            out
+        };
+        let stoch_then_smooth = |data: &[f64]| -> Vec<f64> {
+            let mut out = vec![f64::NAN; data.len()];
+            let (mut raw_prev, mut smooth) = (f64::NAN, f64::NAN);
+            for i in 0..data.len() {
+                let window = &data[(i + 1).saturating_sub(period)..=i];
+                if i + 1 < period || window.iter().any(|x| x.is_nan()) {
+                    continue;
+                }
+                let max = window.iter().cloned().fold(f64::MIN, f64::max);
+                let min = window.iter().cloned().fold(f64::MAX, f64::min);
+                let raw = if max - min < 1e-10 {
+                    if raw_prev.is_nan() { 50.0 } else { raw_prev }
+                } else {
+                    100.0 * (data[i] - min) / (max - min)
+                };
+                raw_prev = raw;
+                smooth = if smooth.is_nan() { raw } else { smooth + 0.5 * (raw - smooth) };
+                out[i] = smooth;
+            }
+            out
+        };
+        
+        let (fast, slow) = (ema(fast), ema(slow));
+        let macd: Vec<f64> = fast.iter().zip(&slow).map(|(f, s)| f - s).collect();
+        stoch_then_smooth(&stoch_then_smooth(&macd))
+    }
+    
+    #[test]
+    fn stc_matches_the_reference_series_and_is_smoothed() {
+        let closes: Vec<f64> = (0..300)
+            .map(|i| 2000.0 + 12.0 * (i as f64 / 17.0).sin() + 2.0 * (i as f64 / 2.3).cos())
+            .collect();
+        let stc = calc_stc(&closes, 10, 23, 50, 0.5, FlatRange::Carry);
+        let reference = reference_stc(&closes, 10, 23, 50);
+        
+        assert_eq!(leading_nans(&stc), leading_nans(&reference));
+        for i in 0..closes.len() {
+            assert!(close(stc[i], reference[i]), "[{}] {} / {}", i, stc[i], reference[i]);
+        }
+        assert!(stc.iter().filter(|x| !x.is_nan()).all(|x| (0.0..=100.0).contains(x)));
+        
+        // Sans lissage (facteur 1), la série est la double passe brute,
+        // nettement plus heurtée
+        let raw = calc_stc(&closes, 10, 23, 50, 1.0, FlatRange::Carry);
+        let jumps = |line: &[f64]| -> f64 {
+            line.windows(2).filter(|w| !w[0].is_nan()).map(|w| (w[1] - w[0]).abs()).sum()
+        };
+        assert!(jumps(&stc) < jumps(&raw));
+    }
+    
+    #[test]
+    fn rsi_matches_the_wilder_reference_series() {
+        // Exemple de référence classique (RSI 14 de Wilder) sur des clôtures
+        // arrondies à 2 décimales
+        let closes = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21,
+        ];
+        let reference = [70.46, 66.25, 66.48, 69.35, 66.29, 57.92, 62.88];
+        
+        let rsi = calc_rsi(&closes, 14);
+        assert_eq!(leading_nans(&rsi), 14);
+        for (i, want) in reference.iter().enumerate() {
+            let got = rsi[14 + i];
+            assert!((got - want).abs() < 0.005, "[{}] {} au lieu de {}", 14 + i, got, want);
+        }
+        
+        // Aucune perte : 100 ; que des pertes : 0
+        let rising: Vec<f64> = (0..20).map(|i| 2000.0 + i as f64).collect();
+        assert_eq!(calc_rsi(&rising, 5)[10], 100.0);
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        assert_eq!(calc_rsi(&falling, 5)[10], 0.0);
+    }
+    
+    #[test]
+    fn macd_line_signal_and_histogram_are_consistent() {
+        // Série linéaire de pente 0.5 : une EMA amorcée par SMA vaut
+        // x[i] - 0.5 * (n - 1) / 2, donc MACD = 0.5 * (slow - fast) / 2
+        let linear: Vec<f64> = (0..40).map(|i| 2000.0 + 0.5 * i as f64).collect();
+        let (macd, signal, histogram) = calc_macd(&linear, 3, 7, 4);
+        assert_eq!(leading_nans(&macd), 6);
+        assert_eq!(leading_nans(&signal), 9);
+        assert!(macd[6..].iter().all(|&m| close(m, 1.0)));
+        assert!(signal[9..].iter().all(|&s| close(s, 1.0)));
+        assert!(histogram[9..].iter().all(|&h| close(h, 0.0)));
+        
+        // Série quelconque : signal = EMA du MACD valide, histogramme = écart
+        let closes: Vec<f64> = (0..120).map(|i| 2000.0 + (i as f64 / 4.0).sin() * 7.0).collect();
+        let (macd, signal, histogram) = calc_macd(&closes, 12, 26, 9);
+        let fast = calc_ema(&closes, 12);
+        let slow = calc_ema(&closes, 26);
+        let signal_ref = calc_ema(&macd[25..], 9);
+        for i in 0..closes.len() {
+            assert!(close(macd[i], fast[i] - slow[i]), "[{}]", i);
+            let want = if i < 25 { f64::NAN } else { signal_ref[i - 25] };
+            assert!(close(signal[i], want), "[{}]", i);
+            assert!(close(histogram[i], macd[i] - signal[i]), "[{}]", i);
+        }
+    }
+    
+    #[test]
+    fn bollinger_bands_match_a_hand_computed_five_point_example() {
+        let closes = [10.0, 12.0, 11.0, 13.0, 14.0, 16.0];
+        let (upper, middle, lower) = calc_bollinger(&closes, 5, 2.0);
+        
+        assert_eq!(leading_nans(&upper), 4);
+        assert_eq!(leading_nans(&lower), 4);
+        // 10 12 11 13 14 : moyenne 12, variance (4 + 0 + 1 + 1 + 4) / 5 = 2
+        assert!(close(middle[4], 12.0));
+        assert!(close(upper[4], 12.0 + 2.0 * 2f64.sqrt()));
+        assert!(close(lower[4], 12.0 - 2.0 * 2f64.sqrt()));
+        // 12 11 13 14 16 : moyenne 13.2, variance 14.8 / 5 = 2.96
+        assert!(close(middle[5], 13.2));
+        assert!(close(upper[5], 13.2 + 2.0 * 2.96f64.sqrt()));
+        assert!(close(lower[5], 13.2 - 2.0 * 2.96f64.sqrt()));
+    }
+    
+    #[test]
+    fn stochastic_uses_the_high_low_range_and_smooths_k_into_d() {
+        let highs = [11.0, 12.0, 13.0, 12.0, 11.0, 10.0, 10.0, 10.0];
+        let lows = [9.0, 10.0, 11.0, 10.0, 9.0, 10.0, 10.0, 10.0];
+        let closes = [10.0, 11.0, 12.5, 10.5, 9.5, 10.0, 10.0, 10.0];
+        
+        let k = calc_stochastic_hlc(&highs, &lows, &closes, 3);
+        let d = calc_sma(&k, 2);
+        assert_eq!(leading_nans(&k), 2);
+        assert_eq!(leading_nans(&d), 3);
+        // Barre 2 : (12.5 - 9) / (13 - 9) ; sur les seules clôtures, 100
+        assert!(close(k[2], 87.5));
+        assert!(close(k[3], 100.0 * 0.5 / 3.0));
+        assert!(close(k[4], 12.5));
+        assert!(close(k[5], 100.0 / 3.0));
+        assert!(close(k[6], 50.0));
+        // Fenêtre plate : %K précédent reconduit
+        assert!(close(k[7], 50.0));
+        
+        assert!(close(d[3], (87.5 + 100.0 * 0.5 / 3.0) / 2.0));
+        assert!(close(d[4], (100.0 * 0.5 / 3.0 + 12.5) / 2.0));
+        assert!(close(d[7], 50.0));
+        assert!(k.iter().chain(&d).all(|v| v.is_nan() || (0.0..=100.0).contains(v)));
+    }
+    
+    #[test]
+    fn streaming_stochastics_match_a_full_window_scan() {
+        // NaN de tête et trou en cours de série : ignorés par les extremums
+        let mut data = series_with_hole();
+        data[..3].fill(f64::NAN);
+        let highs: Vec<f64> = data.iter().map(|x| x + 1.5).collect();
+        let lows: Vec<f64> = data.iter().map(|x| x - 1.5).collect();
+        let period = 9;
+        
+        let scan = |window: &[f64], keep_max: bool| {
+            let fold = if keep_max { f64::max } else { f64::min };
+            let init = if keep_max { f64::NEG_INFINITY } else { f64::INFINITY };
+            window.iter().cloned().fold(init, fold)
+        };
+        let percent = |x: f64, max: f64, min: f64| 100.0 * (x - min) / (max - min);
+        
+        let k = calc_stochastic_hlc(&highs, &lows, &data, period);
+        let raw = calc_stochastic(&data, period, FlatRange::Nan);
+        assert_eq!(leading_nans(&raw), 3 + period - 1);
+        for i in 3 + period - 1..data.len() {
+            let start = i + 1 - period;
+            let (max, min) = (scan(&highs[start..=i], true), scan(&lows[start..=i], false));
+            assert!(close(k[i], percent(data[i], max, min)), "%K [{}] : {}", i, k[i]);
+            let (max, min) = (scan(&data[start..=i], true), scan(&data[start..=i], false));
+            assert!(close(raw[i], percent(data[i], max, min)), "brut [{}] : {}", i, raw[i]);
+        }
+    }
+    
+    #[test]
+    fn adx_matches_a_small_reference_dataset() {
+        let highs = [
+            30.20, 30.28, 30.45, 29.35, 29.35, 29.29, 28.83, 28.73, 28.67, 28.85, 28.64, 29.87,
+            30.58, 30.87,
+        ];
+        let lows = [
+            29.41, 29.32, 29.96, 28.74, 28.56, 28.41, 28.08, 27.43, 27.66, 27.83, 27.40, 28.51,
+            29.81, 29.87,
+        ];
+        let closes = [
+            29.87, 30.24, 30.10, 28.90, 28.92, 28.48, 28.56, 27.56, 28.47, 28.28, 27.49, 28.83,
+            30.18, 29.87,
+        ];
+        // Référence Wilder indépendante, période 4, arrondie à 4 décimales
+        let plus_ref = [
+            4.7222, 3.5615, 2.7838, 1.8502, 1.3390, 5.4636, 3.8750, 24.2707, 29.0781, 29.0637,
+        ];
+        let minus_ref = [
+            41.3889, 35.4050, 37.2817, 41.5471, 30.0671, 22.4625, 26.0141, 14.9155, 10.5167,
+            8.5872,
+        ];
+        let adx_ref = [84.7038, 86.3961, 80.0148, 78.5288, 64.8650, 60.3684, 58.8726];
+        
+        let (plus_di, minus_di, adx) = calc_adx(&highs, &lows, &closes, 4);
+        assert_eq!(leading_nans(&plus_di), 4);
+        assert_eq!(leading_nans(&minus_di), 4);
+        assert_eq!(leading_nans(&adx), 7);
+        let check = |line: &[f64], reference: &[f64]| {
+            let start = line.len() - reference.len();
+            for (i, want) in reference.iter().enumerate() {
+                let got = line[start + i];
+                assert!((got - want).abs() < 5e-5, "[{}] {} au lieu de {}", start + i, got, want);
+            }
+        };
+        check(&plus_di, &plus_ref);
+        check(&minus_di, &minus_ref);
+        check(&adx, &adx_ref);
+    }
+    
+    #[test]
+    fn running_sum_sma_matches_the_brute_force_average() {
+        let data: Vec<f64> = (0..500)
+            .map(|i| 2000.0 + (i as f64 * 0.37).sin() * 5.0 + (i % 7) as f64 * 0.01)
+            .collect();
+        
+        for period in [1, 2, 7, 50, 499, 500] {
+            let sma = calc_sma(&data, period);
+            assert_eq!(leading_nans(&sma), period - 1);
+            for i in period - 1..data.len() {
+                let window = &data[i + 1 - period..=i];
+                let want = window.iter().sum::<f64>() / period as f64;
+                assert!((sma[i] - want).abs() < 1e-9, "période {} [{}]", period, i);
+            }
+        }
+        assert!(calc_sma(&data, 501).iter().all(|v| v.is_nan()));
+    }
+    
+    #[test]
+    fn rolling_std_stays_accurate_on_prices_near_2000() {
+        // Écarts au pas de 2^-14 autour de 2000 : ceux-ci et les prix sont
+        // exacts en f64, la référence se calcule sur les écarts seuls. Une
+        // somme de carrés naïve y perd déjà 1e-4 en relatif
+        let offsets: Vec<f64> = (0..2_000)
+            .map(|i| ((i * 37 % 101) as f64 - 50.0) / 16_384.0)
+            .collect();
+        let prices: Vec<f64> = offsets.iter().map(|d| 2000.0 + d).collect();
+        
+        for period in [5, 20, 250] {
+            let (means, stds) = calc_rolling_mean_std(&prices, period);
+            assert_eq!(leading_nans(&stds), period - 1);
+            let mut worst = 0.0f64;
+            for i in period - 1..prices.len() {
+                let window = &offsets[i + 1 - period..=i];
+                let mean = window.iter().sum::<f64>() / period as f64;
+                let var = window.iter().map(|d| (d - mean) * (d - mean)).sum::<f64>();
+                let want = (var / period as f64).sqrt();
+                assert!((means[i] - 2000.0 - mean).abs() < 1e-9, "période {} [{}]", period, i);
+                worst = worst.max((stds[i] - want).abs() / want);
+            }
+            assert!(worst < 1e-9, "période {} : erreur relative {:e}", period, worst);
+        }
+    }
+    
+    #[test]
+    fn vwma_weights_each_window_by_volume() {
+        let prices = [10.0, 11.0, 12.0, 13.0, 14.0, 15.0];
+        let volumes = [1.0, 3.0, 0.0, 0.0, 2.0, 2.0];
+        let vwma = calc_vwma(&prices, &volumes, 2);
+        
+        assert!(vwma[0].is_nan());
+        // (10 + 33) / 4, 11 seul, fenêtre sans volume, 14 seul, (28 + 30) / 4
+        assert!(close(vwma[1], 43.0 / 4.0));
+        assert!(close(vwma[2], 11.0));
+        assert!(vwma[3].is_nan());
+        assert!(close(vwma[4], 14.0));
+        assert!(close(vwma[5], 14.5));
+        
+        // Volumes constants : moyenne simple
+        let flat = calc_vwma(&prices, &[5.0; 6], 3);
+        let sma = calc_sma(&prices, 3);
+        assert!(flat.iter().zip(&sma).all(|(&v, &s)| close(v, s)));
+    }
+    
+    #[test]
+    fn keltner_bands_wait_for_both_ema_and_atr() {
+        let closes: Vec<f64> = (0..30).map(|i| 2000.0 + (i as f64 / 3.0).sin() * 4.0).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.5).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        
+        for (ema_period, atr_period) in [(5, 3), (3, 8)] {
+            let (upper, middle, lower) =
+                calc_keltner(&highs, &lows, &closes, ema_period, atr_period, 2.0);
+            // ATR : première barre sans True Range puis `atr_period` valeurs
+            let warmup = (ema_period - 1).max(atr_period);
+            assert_eq!(leading_nans(&middle), ema_period - 1);
+            assert_eq!(leading_nans(&upper), warmup);
+            assert_eq!(leading_nans(&lower), warmup);
+            
+            let ema = calc_ema(&closes, ema_period);
+            let atr = calc_atr(&highs, &lows, &closes, atr_period);
+            for i in warmup..closes.len() {
+                assert!(close(middle[i], ema[i]));
+                assert!(close(upper[i], ema[i] + 2.0 * atr[i]));
+                assert!(close(lower[i], ema[i] - 2.0 * atr[i]));
+                assert!(lower[i] < middle[i] && middle[i] < upper[i]);
+            }
+        }
+    }
+    
+    /// Attribut `code` d'une HftException
+    fn error_code(err: PyErr) -> String {
+        Python::with_gil(|py| err.value(py).getattr("code").unwrap().extract().unwrap())
+    }
+    
+    #[test]
+    fn periods_are_checked_against_the_input_length() {
+        let closes: Vec<f64> = (0..10).map(|i| 2000.0 + i as f64).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.0).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        
+        Python::with_gil(|py| {
+            for (tenkan, kijun, senkou_b) in [(0, 5, 5), (5, 11, 5), (5, 5, 0)] {
+                let err = ichimoku_arrays(
+                    py, &highs, &lows, &closes, tenkan, kijun, senkou_b, false,
+                ).err().unwrap();
+                assert_eq!(error_code(err), "INVALID_PERIOD");
+            }
+            for (period, fast, slow) in [(0, 3, 5), (3, 3, 11), (3, 0, 5)] {
+                let err = stc_array(py, &closes, period, fast, slow, 0.5, FlatRange::Carry)
+                    .err()
+                    .unwrap();
+                assert_eq!(error_code(err), "INVALID_PERIOD");
+            }
+        });
+        assert!(check_period("period", 10, 10).is_ok());
+        assert!(check_period("period", 1, 10).is_ok());
+        assert!(check_periods(&[("a", 10), ("b", 11)], 10).is_err());
+        
+        // Période égale à la longueur : une seule valeur, sur la dernière barre
+        let single = [
+            calc_sma(&closes, 10),
+            calc_ema(&closes, 10),
+            calc_wma(&closes, 10),
+            calc_ichimoku_line(&highs, &lows, 10),
+        ];
+        for line in &single {
+            assert_eq!(leading_nans(line), 9);
+        }
+        assert!(close(single[0][9], 2004.5));
+        assert!(close(single[3][9], 2004.5));
+        
+        // Les helpers ne paniquent pas sur une période nulle
+        assert!(calc_ema(&closes, 0).iter().all(|v| v.is_nan()));
+        assert!(calc_sma(&closes, 0).iter().all(|v| v.is_nan()));
+    }
+    
+    #[test]
+    fn obv_adds_on_up_closes_subtracts_on_down_and_holds_on_flat() {
+        let closes = [10.0, 11.0, 11.0, 10.5, 12.0, 12.0, 9.0];
+        let volumes = [100.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0];
+        
+        // Première barre à 0, puis +20, =, -40, +50, =, -70
+        let obv = calc_obv(&closes, &volumes);
+        assert_eq!(obv, vec![0.0, 20.0, 20.0, -20.0, 30.0, 30.0, -40.0]);
+        assert!(calc_obv(&[], &[]).is_empty());
+        assert!(check_same_len(&[&closes, &volumes[..6]]).is_err());
+    }
+    
+    #[test]
+    fn sar_follows_the_reference_series_through_a_reversal() {
+        let highs = [10.0, 10.5, 11.0, 11.6, 12.0, 11.8, 11.2, 10.6, 10.0, 9.8, 10.4, 11.0];
+        let lows = [9.5, 10.0, 10.4, 11.0, 11.4, 11.1, 10.5, 9.9, 9.4, 9.2, 9.9, 10.5];
+        // Tendance haussière amorcée sur le plus bas 9.5, SAR borné par les
+        // deux plus bas précédents ; le plus bas 9.9 perce le SAR en barre 7,
+        // qui repart du plus haut extrême 12.0
+        let reference = [
+            9.5, 9.5, 9.5, 9.59, 9.7508, 9.97572, 10.178148, 12.0, 11.958, 11.85568, 11.696339,
+            11.546559,
+        ];
+        
+        let sar = calc_sar(&highs, &lows, 0.02, 0.02, 0.2);
+        for (i, (got, want)) in sar.iter().zip(reference).enumerate() {
+            assert!((got - want).abs() < 1e-6, "[{}] {} au lieu de {}", i, got, want);
+        }
+        for i in 0..highs.len() {
+            let below = i < 7;
+            assert_eq!(sar[i] <= lows[i], below, "[{}]", i);
+            assert_eq!(sar[i] >= highs[i], !below, "[{}]", i);
+        }
+    }
+    
+    #[test]
+    fn hma_matches_a_reference_series() {
+        let data = [10.0, 11.0, 13.0, 12.0, 14.0, 16.0, 15.0, 17.0, 18.0, 20.0];
+        // HMA 4 : WMA 2 de 2 * WMA 2 - WMA 4 ; barre 4, 2 * 13.333 - 12.9 = 13.767
+        // et 2 * 12.333 - 11.9 = 12.767 donnent (12.767 + 2 * 13.767) / 3
+        let reference = [13.433333, 15.5, 16.033333, 16.466667, 17.877778, 19.722222];
+        
+        let hma = calc_hma(&data, 4);
+        assert_eq!(leading_nans(&hma), 4);
+        for (i, want) in reference.iter().enumerate() {
+            let got = hma[4 + i];
+            assert!((got - want).abs() < 1e-6, "[{}] {} au lieu de {}", 4 + i, got, want);
+        }
+        
+        // Warm-up combiné plus long que celui d'une WMA : 9 + 3 - 2 barres
+        let long: Vec<f64> = (0..30).map(|i| 2000.0 + (i as f64 / 2.0).sin()).collect();
+        let hma = calc_hma(&long, 9);
+        assert_eq!(leading_nans(&hma), 10);
+        assert_eq!(leading_nans(&calc_wma(&long, 9)), 8);
+        
+        // Série linéaire : la HMA colle au prix, sans retard
+        let linear: Vec<f64> = (0..30).map(|i| 2000.0 + 0.5 * i as f64).collect();
+        let hma = calc_hma(&linear, 9);
+        assert!((10..30).all(|i| close(hma[i], linear[i])));
+    }
+    
+    #[test]
+    fn stoch_rsi_stays_in_range_and_matches_a_brute_force_reference() {
+        let closes: Vec<f64> = (0..300)
+            .map(|i| 2000.0 + (i as f64 / 6.0).sin() * 8.0 + (i as f64 / 1.7).cos() * 2.0)
+            .collect();
+        let (k, d) = calc_stoch_rsi(&closes, 14, 14, 3, 3);
+        assert_eq!(leading_nans(&k), 14 + 14 - 1 + 3 - 1);
+        assert_eq!(leading_nans(&d), 14 + 14 + 3 + 3 - 3);
+        assert!(k.iter().chain(&d).all(|v| v.is_nan() || (0.0..=100.0).contains(v)));
+        
+        // Référence : stochastique et moyennes recalculés fenêtre par fenêtre
+        let rsi = calc_rsi(&closes, 14);
+        let mean = |w: &[f64]| w.iter().sum::<f64>() / w.len() as f64;
+        let stoch: Vec<f64> = (0..closes.len())
+            .map(|i| {
+                if i < 27 {
+                    return f64::NAN;
+                }
+                let window = &rsi[i - 13..=i];
+                let max = window.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let min = window.iter().cloned().fold(f64::INFINITY, f64::min);
+                100.0 * (rsi[i] - min) / (max - min)
+            })
+            .collect();
+        for i in 31..closes.len() {
+            let want_k = mean(&stoch[i - 2..=i]);
+            let want_d = (0..3).map(|j| mean(&stoch[i - j - 2..=i - j])).sum::<f64>() / 3.0;
+            assert!((k[i] - want_k).abs() < 1e-9, "%K [{}]", i);
+            assert!((d[i] - want_d).abs() < 1e-9, "%D [{}]", i);
+        }
+    }
+    
+    #[test]
+    fn donchian_upper_band_extends_on_a_breakout() {
+        let highs = [10.0, 11.0, 10.5, 10.8, 10.2, 12.5, 11.0, 10.9, 10.7];
+        let lows = [9.0, 9.5, 9.2, 9.6, 9.1, 10.0, 9.8, 9.7, 9.9];
+        let (upper, lower, middle) = calc_donchian(&highs, &lows, 3);
+        
+        assert_eq!(leading_nans(&upper), 2);
+        assert_eq!(leading_nans(&lower), 2);
+        assert_eq!(&upper[2..5], &[11.0, 11.0, 10.8]);
+        // Cassure : le plus haut 12.5 devient la borne haute et y reste tant
+        // qu'il est dans la fenêtre
+        assert_eq!(&upper[5..], &[12.5, 12.5, 12.5, 11.0]);
+        assert!(upper[5] > upper[4]);
+        assert_eq!(&lower[2..], &[9.0, 9.2, 9.1, 9.1, 9.1, 9.7, 9.7]);
+        assert!((2..highs.len()).all(|i| close(middle[i], (upper[i] + lower[i]) / 2.0)));
+    }
+    
+    #[test]
+    fn ema_seeds_differ_only_in_their_early_values() {
+        // Rampe de pente 1, période 3 (multiplicateur 0.5)
+        let ramp: Vec<f64> = (1..=8).map(f64::from).collect();
+        let sma = calc_ema_seeded(&ramp, 3, EmaSeed::Sma);
+        let first = calc_ema_seeded(&ramp, 3, EmaSeed::First);
+        let zero_lag = calc_ema_seeded(&ramp, 3, EmaSeed::ZeroLag);
+        
+        // SMA : rien avant la 3e valeur, puis un retard constant d'une barre
+        assert_eq!(leading_nans(&sma), 2);
+        assert_eq!(&sma[2..], &[2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        // Première valeur : défini dès la barre 0, retard qui converge vers 1
+        assert_eq!(first, vec![1.0, 1.5, 2.25, 3.125, 4.0625, 5.03125, 6.015625, 7.0078125]);
+        // Zero-lag : un décalage de plus en warm-up, aucun retard ensuite
+        assert_eq!(leading_nans(&zero_lag), 3);
+        assert_eq!(&zero_lag[3..], &ramp[3..]);
+        
+        // Les trois se rejoignent une fois l'amorçage oublié, sur un plateau
+        let mut plateau = ramp.clone();
+        plateau.extend([8.0; 60]);
+        let last = |seed| *calc_ema_seeded(&plateau, 3, seed).last().unwrap();
+        for seed in [EmaSeed::Sma, EmaSeed::First, EmaSeed::ZeroLag] {
+            assert!(close(last(seed), 8.0), "{:?} : {}", seed, last(seed));
+        }
+        
+        assert_eq!(EmaSeed::parse("sma").unwrap(), EmaSeed::Sma);
+        assert_eq!(error_code(EmaSeed::parse("wilder").unwrap_err()), "INVALID_PARAMETER");
+    }
+    
+    #[test]
+    fn displaced_senkou_spans_are_shifted_forward_by_kijun_period() {
+        let highs: Vec<f64> = (0..120).map(|i| 2001.0 + (i as f64 / 6.0).sin() * 5.0).collect();
+        let lows: Vec<f64> = highs.iter().map(|h| h - 2.0).collect();
+        let closes: Vec<f64> = highs.iter().map(|h| h - 1.0).collect();
+        let (_, _, senkou_a, senkou_b, _) = calc_ichimoku(&highs, &lows, &closes, 9, 26, 52);
+        
+        for line in [senkou_a, senkou_b] {
+            let shifted = displace_forward(line.clone(), 26);
+            assert_eq!(shifted.len(), line.len() + 26);
+            assert!(shifted[..26].iter().all(|v| v.is_nan()));
+            // Le nuage tracé en i + 26 est celui calculé en i
+            assert!(line.iter().enumerate().all(|(i, &v)| close(shifted[i + 26], v)));
+        }
+        assert_eq!(displace_forward(vec![1.0, 2.0], 0), vec![1.0, 2.0]);
+        
+        // Les tableaux exigent le module numpy côté Python
+        Python::with_gil(|py| {
+            if py.import("numpy").is_err() {
+                return;
+            }
+            let values = |s: &PySeries| s.as_ref(py).to_vec().unwrap();
+            let flat = ichimoku_arrays(py, &highs, &lows, &closes, 9, 26, 52, false).unwrap();
+            let ahead = ichimoku_arrays(py, &highs, &lows, &closes, 9, 26, 52, true).unwrap();
+            // Kijun inchangée
+            let (kijun, kijun_ahead) = (values(&flat.1), values(&ahead.1));
+            assert!(kijun.iter().zip(&kijun_ahead).all(|(&x, &y)| close(x, y)));
+            let (a, b) = (values(&flat.2), values(&ahead.2));
+            assert_eq!(b.len(), a.len() + 26);
+            assert!(a.iter().enumerate().all(|(i, &v)| close(b[i + 26], v)));
+        });
+    }
+    
+    #[test]
+    fn wma_matches_a_hand_computed_four_point_average() {
+        // Poids 1, 2, 3, 4 du plus ancien au plus récent, somme 10
+        let data = [10.0, 12.0, 11.0, 15.0, 14.0, 18.0];
+        let wma = calc_wma(&data, 4);
+        assert_eq!(leading_nans(&wma), 3);
+        for (got, want) in wma[3..].iter().zip([12.7, 13.5, 15.5]) {
+            assert!(close(*got, want), "{:?}", wma);
+        }
+        
+        // Mise à jour O(1) contre la somme pondérée directe, prix proches de 2000
+        let prices: Vec<f64> = (0..500).map(|i| 2000.0 + (i as f64 / 11.0).sin() * 3.0).collect();
+        let wma = calc_wma(&prices, 4);
+        for (i, window) in prices.windows(4).enumerate() {
+            let direct: f64 = window.iter().zip(1..=4).map(|(x, w)| x * w as f64).sum::<f64>() / 10.0;
+            assert!((wma[i + 3] - direct).abs() < 1e-9, "barre {} : {}", i + 3, wma[i + 3]);
+        }
+    }
+    
+    #[test]
+    fn batch_outputs_match_the_standalone_calculators() {
+        use pyo3::types::IntoPyDict;
+        
+        let closes: Vec<f64> = (0..300)
+            .map(|i| 2000.0 + 10.0 * (i as f64 / 9.0).sin() + (i as f64 / 2.3).cos())
+            .collect();
+        let highs: Vec<f64> =
+            closes.iter().enumerate().map(|(i, c)| c + 1.0 + (i % 3) as f64 * 0.4).collect();
+        let lows: Vec<f64> =
+            closes.iter().enumerate().map(|(i, c)| c - 1.0 - (i % 4) as f64 * 0.3).collect();
+        let (h, l, c) = (&highs[..], &lows[..], &closes[..]);
+        
+        // Sorties des calculateurs, paramètres par défaut sauf les périodes
+        // de stc, rsi et ema
+        let (t, k, a, b, chikou) = calc_ichimoku(h, l, c, 9, 26, 52);
+        let (macd, signal, histogram) = calc_macd(c, 12, 26, 9);
+        let (upper, middle, lower) = calc_bollinger(c, 20, 2.0);
+        let expected = [
+            ("ichimoku", None, vec![t, k, a, b, chikou]),
+            ("stc", Some(12), vec![calc_stc(c, 12, 23, 50, 0.5, FlatRange::Carry)]),
+            ("rsi", Some(10), vec![calc_rsi(c, 10)]),
+            ("macd", None, vec![macd, signal, histogram]),
+            ("ema", Some(8), vec![calc_ema_seeded(c, 8, EmaSeed::Sma)]),
+            ("sma", None, vec![calc_sma(c, 20)]),
+            ("atr", None, vec![calc_atr(h, l, c, 14)]),
+            ("bollinger", None, vec![upper, middle, lower]),
+        ];
+        
+        Python::with_gil(|py| {
+            let jobs: Vec<BatchIndicator> = expected
+                .iter()
+                .map(|(name, period, _)| {
+                    let params = period.map(|p| [("period", p)].into_py_dict(py));
+                    BatchIndicator::parse(name, params, c.len()).unwrap()
+                })
+                .collect();
+            let outputs: Vec<Vec<Vec<f64>>> =
+                jobs.par_iter().map(|job| job.compute(h, l, c)).collect();
+            
+            for ((job, lines), (name, _, want)) in jobs.iter().zip(&outputs).zip(&expected) {
+                assert_eq!(job.keys().len(), lines.len(), "{}", name);
+                assert_eq!(lines.len(), want.len(), "{}", name);
+                for (line, want) in lines.iter().zip(want) {
+                    assert_eq!(line.len(), want.len(), "{}", name);
+                    assert!(line.iter().zip(want).all(|(&x, &y)| close(x, y)), "{}", name);
+                    assert!(line.iter().any(|x| !x.is_nan()), "{}", name);
+                }
+            }
+            
+            let typo = [("periode", 10)].into_py_dict(py);
+            assert!(BatchIndicator::parse("rsi", Some(typo), c.len()).is_err());
+            assert!(BatchIndicator::parse("vwap", None, c.len()).is_err());
+            let long = [("period", 301)].into_py_dict(py);
+            assert!(BatchIndicator::parse("sma", Some(long), c.len()).is_err());
+        });
+    }
+    
+    fn range_of_window(window: &[f64]) -> f64 {
+        let max = window.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        max - window.iter().cloned().fold(f64::INFINITY, f64::min)
+    }
+    
+    #[test]
+    fn flat_segment_creates_no_spurious_stc_crosses() {
+        // Oscillation, 1500 barres parfaitement plates, puis reprise
+        let wave = |i: usize| 2000.0 + 8.0 * (i as f64 / 6.0).sin();
+        let mut closes: Vec<f64> = (0..200).map(wave).collect();
+        closes.extend([closes[199]; 1500]);
+        closes.extend((200..300).map(wave));
+        
+        // La MACD s'éteint : première barre dont la fenêtre stochastique
+        // n'a plus d'amplitude
+        let macd = calc_macd_line(&closes, 23, 50);
+        let dead = (210..1700)
+            .find(|&i| range_of_window(&macd[i - 9..=i]) < 1e-10)
+            .unwrap();
+        
+        // Franchissements des niveaux 25 / 75 entre deux barres définies
+        let crosses = |stc: &[f64], bars: std::ops::Range<usize>| {
+            bars.filter(|&i| !stc[i - 1].is_nan() && !stc[i].is_nan())
+                .filter(|&i| {
+                    [25.0, 75.0].iter().any(|&level| (stc[i - 1] < level) != (stc[i] < level))
+                })
+                .count()
+        };
+        
+        // Reconduction : aucun franchissement tant que le marché est plat,
+        // les croisements reprennent avec le mouvement
+        let carry = calc_stc(&closes, 10, 23, 50, 0.5, FlatRange::Carry);
+        assert_eq!(crosses(&carry, dead..1700), 0);
+        assert!(crosses(&carry, 1700..closes.len()) > 0);
+        
+        // 50 forcé : remontée artificielle à travers les niveaux
+        let mid = calc_stc(&closes, 10, 23, 50, 0.5, FlatRange::Mid);
+        assert!(crosses(&mid, dead..1700) > 0);
+        
+        // NaN : trou sur le segment mort, sans franchissement
+        let nan = calc_stc(&closes, 10, 23, 50, 0.5, FlatRange::Nan);
+        assert!(nan[dead + 20..1700].iter().all(|x| x.is_nan()));
+        assert_eq!(crosses(&nan, dead..1700), 0);
+    }
+    
+    #[test]
+    fn streaming_stc_matches_the_batch_output_bar_for_bar() {
+        // Oscillation avec un segment plat assez long pour éteindre la MACD
+        // et exercer chaque flat_range
+        let mut closes: Vec<f64> = (0..1400)
+            .map(|i| 2000.0 + 12.0 * (i as f64 / 17.0).sin() + 2.0 * (i as f64 / 2.3).cos())
+            .collect();
+        let plateau = closes[249];
+        closes[250..1150].fill(plateau);
+        
+        let configs = [
+            (10, 23, 50, 0.5, "carry"),
+            (10, 23, 50, 0.5, "nan"),
+            (10, 23, 50, 0.5, "mid"),
+            (5, 12, 26, 1.0, "carry"),
+            (14, 8, 30, 0.25, "carry"),
+        ];
+        for (period, fast, slow, smoothing, flat_range) in configs {
+            let batch = calc_stc(
+                &closes, period, fast, slow, smoothing, FlatRange::parse(flat_range).unwrap(),
+            );
+            let mut state = STCState::new(period, fast, slow, smoothing, flat_range).unwrap();
+            let mut ready_at = None;
+            for (i, &close) in closes.iter().enumerate() {
+                let value = state.update(close);
+                assert!(
+                    (value - batch[i]).abs() < 1e-9 || (value.is_nan() && batch[i].is_nan()),
+                    "{} {} [{}] {} / {}", period, flat_range, i, value, batch[i]
+                );
+                if state.is_ready() && ready_at.is_none() {
+                    ready_at = Some(i);
+                }
+            }
+            assert_eq!(ready_at, Some(leading_nans(&batch)), "{} {}", period, flat_range);
+            if flat_range == "nan" {
+                assert!(batch[1000..1150].iter().all(|x| x.is_nan()));
+            }
+        }
+        
+        assert!(STCState::new(0, 23, 50, 0.5, "carry").is_err());
+        assert!(STCState::new(10, 23, 50, 0.0, "carry").is_err());
+        assert!(STCState::new(10, 23, 50, 0.5, "zero").is_err());
+    }
+}
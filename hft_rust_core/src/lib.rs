@@ -1,22 +1,84 @@
 //! HFT Trading Core - Rust Backend
 //! Composants haute performance pour trading HFT
 
+// Les macros pyo3 0.20 génèrent des impl non locaux
+#![allow(non_local_definitions)]
+
 use pyo3::prelude::*;
 
+mod errors;
 mod tick_processor;
 mod indicators;
 mod signal_detector;
+mod statistics;
+
+pub use errors::{HftError, HftException};
+pub use tick_processor::{
+    resample_ohlc, BufferSnapshot, MultiSymbolBuffer, OHLC, OHLCBidAsk, PriceSource, Tick,
+    TickBuffer, TickBufferF32,
+};
+pub use indicators::{
+    ADXCalculator, ATRCalculator, BollingerCalculator, DonchianCalculator, EMACalculator,
+    HMACalculator, IchimokuCalculator, IchimokuState, KeltnerCalculator, MACDCalculator,
+    NanPolicy, OBVCalculator, RSICalculator, SARCalculator, SMACalculator, STCCalculator, STCState,
+    StdDevCalculator, StochRSICalculator, StochasticCalculator, VWMACalculator, WMACalculator,
+};
+pub use signal_detector::{CombinePolicy, ConfluenceInput, SignalDetector, SignalType};
+use indicators::{compute_indicators, no_lookahead_enabled, set_no_lookahead, warmup_bars};
+use statistics::{rolling_apply, rolling_beta, rolling_correlation, spread};
 
-pub use tick_processor::TickBuffer;
-pub use indicators::{IchimokuCalculator, STCCalculator};
-pub use signal_detector::SignalDetector;
+/// Helpers de calcul exposés aux benchmarks (hors API Python)
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::indicators::{calc_chikou, calc_ichimoku, calc_ichimoku_line, calc_sma};
+}
 
 /// Module Python exposé
 #[pymodule]
-fn hft_rust_core(_py: Python, m: &PyModule) -> PyResult<()> {
+fn hft_rust_core(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("HftException", py.get_type::<HftException>())?;
+    m.add_class::<Tick>()?;
+    m.add_class::<OHLC>()?;
+    m.add_class::<OHLCBidAsk>()?;
+    m.add_class::<PriceSource>()?;
     m.add_class::<TickBuffer>()?;
+    m.add_class::<TickBufferF32>()?;
+    m.add_class::<MultiSymbolBuffer>()?;
+    m.add_class::<BufferSnapshot>()?;
     m.add_class::<IchimokuCalculator>()?;
+    m.add_class::<IchimokuState>()?;
     m.add_class::<STCCalculator>()?;
+    m.add_class::<STCState>()?;
+    m.add_class::<RSICalculator>()?;
+    m.add_class::<EMACalculator>()?;
+    m.add_class::<MACDCalculator>()?;
+    m.add_class::<BollingerCalculator>()?;
+    m.add_class::<ATRCalculator>()?;
+    m.add_class::<StochasticCalculator>()?;
+    m.add_class::<ADXCalculator>()?;
+    m.add_class::<SMACalculator>()?;
+    m.add_class::<WMACalculator>()?;
+    m.add_class::<StdDevCalculator>()?;
+    m.add_class::<VWMACalculator>()?;
+    m.add_class::<KeltnerCalculator>()?;
+    m.add_class::<OBVCalculator>()?;
+    m.add_class::<SARCalculator>()?;
+    m.add_class::<HMACalculator>()?;
+    m.add_class::<StochRSICalculator>()?;
+    m.add_class::<DonchianCalculator>()?;
+    m.add_class::<SignalType>()?;
     m.add_class::<SignalDetector>()?;
+    m.add_class::<ConfluenceInput>()?;
+    m.add_class::<CombinePolicy>()?;
+    m.add_class::<NanPolicy>()?;
+    m.add_function(wrap_pyfunction!(resample_ohlc, m)?)?;
+    m.add_function(wrap_pyfunction!(rolling_correlation, m)?)?;
+    m.add_function(wrap_pyfunction!(rolling_beta, m)?)?;
+    m.add_function(wrap_pyfunction!(spread, m)?)?;
+    m.add_function(wrap_pyfunction!(rolling_apply, m)?)?;
+    m.add_function(wrap_pyfunction!(set_no_lookahead, m)?)?;
+    m.add_function(wrap_pyfunction!(no_lookahead_enabled, m)?)?;
+    m.add_function(wrap_pyfunction!(warmup_bars, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_indicators, m)?)?;
     Ok(())
 }
@@ -2,112 +2,1536 @@
 //! Analyse rapide des conditions de marché
 
 use pyo3::prelude::*;
+use crate::errors::HftError;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Direction d'un signal, exposée à Python (SignalType.Long, ...)
 #[pyclass]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SignalType {
     Long,
     Short,
     Neutral,
 }
 
+impl SignalType {
+    /// Direction signée : +1 Long, -1 Short, 0 Neutral
+    fn direction(self) -> f64 {
+        match self {
+            SignalType::Long => 1.0,
+            SignalType::Short => -1.0,
+            SignalType::Neutral => 0.0,
+        }
+    }
+}
+
+/// Règle de combinaison de signaux (signal, confiance, poids)
+#[pyclass]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CombinePolicy {
+    /// Tous les signaux dans la même direction, aucun neutre ; confiance =
+    /// moyenne pondérée des confiances
+    Unanimous,
+    /// La direction au poids de votes le plus élevé l'emporte (neutres
+    /// abstentionnistes, égalité → Neutral) ; confiance = moyenne pondérée
+    /// des confiances des votes majoritaires
+    Majority,
+    /// Score net = somme(direction * confiance * poids) / somme(poids)
+    WeightedSum,
+    /// Le signal le plus confiant l'emporte, poids ignorés ; Neutral si
+    /// deux signaux opposés ont la même confiance maximale
+    AnyStrong,
+}
+
+impl CombinePolicy {
+    /// Combine des signaux (signal, confiance, poids) ; None → Neutral
+    fn combine(self, signals: &[(SignalType, f64, f64)]) -> Option<(SignalType, f64)> {
+        let votes = || signals.iter().filter(|&&(signal, _, _)| signal != SignalType::Neutral);
+        match self {
+            CombinePolicy::Unanimous => {
+                let (first, _, _) = *signals.first()?;
+                if first == SignalType::Neutral || signals.iter().any(|s| s.0 != first) {
+                    return None;
+                }
+                weighted_confidence(signals).map(|conf| (first, conf))
+            }
+            CombinePolicy::Majority => {
+                let weight_of = |side: SignalType| -> f64 {
+                    votes().filter(|s| s.0 == side).map(|s| s.2).sum()
+                };
+                let (long, short) = (weight_of(SignalType::Long), weight_of(SignalType::Short));
+                let side = if long > short {
+                    SignalType::Long
+                } else if short > long {
+                    SignalType::Short
+                } else {
+                    return None;
+                };
+                let majority: Vec<_> = votes().filter(|s| s.0 == side).copied().collect();
+                weighted_confidence(&majority).map(|conf| (side, conf))
+            }
+            CombinePolicy::WeightedSum => net_score(signals),
+            CombinePolicy::AnyStrong => {
+                let best = votes().map(|s| s.1).fold(f64::NEG_INFINITY, f64::max);
+                let mut strongest = votes().filter(|s| s.1 == best).map(|s| s.0);
+                let side = strongest.next()?;
+                if strongest.any(|other| other != side) {
+                    return None;
+                }
+                Some((side, best))
+            }
+        }
+    }
+}
+
+/// Moyenne des confiances pondérée par les poids, None sans poids positif
+fn weighted_confidence(signals: &[(SignalType, f64, f64)]) -> Option<f64> {
+    let total: f64 = signals.iter().map(|s| s.2).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    Some(signals.iter().map(|s| s.1 * s.2).sum::<f64>() / total)
+}
+
+/// Entrées du score de confluence : signal, confiance et poids par indicateur
+#[pyclass]
+#[derive(Clone)]
+pub struct ConfluenceInput {
+    #[pyo3(get, set)]
+    ichimoku_signal: SignalType,
+    #[pyo3(get, set)]
+    ichimoku_conf: f64,
+    #[pyo3(get, set)]
+    ichimoku_weight: f64,
+    #[pyo3(get, set)]
+    stc_signal: SignalType,
+    #[pyo3(get, set)]
+    stc_conf: f64,
+    #[pyo3(get, set)]
+    stc_weight: f64,
+    #[pyo3(get, set)]
+    rsi_signal: SignalType,
+    #[pyo3(get, set)]
+    rsi_conf: f64,
+    #[pyo3(get, set)]
+    rsi_weight: f64,
+    #[pyo3(get, set)]
+    macd_signal: SignalType,
+    #[pyo3(get, set)]
+    macd_conf: f64,
+    #[pyo3(get, set)]
+    macd_weight: f64,
+}
+
+#[pymethods]
+impl ConfluenceInput {
+    #[new]
+    #[pyo3(signature = (
+        ichimoku_signal=SignalType::Neutral,
+        ichimoku_conf=0.0,
+        stc_signal=SignalType::Neutral,
+        stc_conf=0.0,
+        rsi_signal=SignalType::Neutral,
+        rsi_conf=0.0,
+        macd_signal=SignalType::Neutral,
+        macd_conf=0.0,
+        ichimoku_weight=1.0,
+        stc_weight=1.0,
+        rsi_weight=1.0,
+        macd_weight=1.0
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        ichimoku_signal: SignalType,
+        ichimoku_conf: f64,
+        stc_signal: SignalType,
+        stc_conf: f64,
+        rsi_signal: SignalType,
+        rsi_conf: f64,
+        macd_signal: SignalType,
+        macd_conf: f64,
+        ichimoku_weight: f64,
+        stc_weight: f64,
+        rsi_weight: f64,
+        macd_weight: f64,
+    ) -> Self {
+        ConfluenceInput {
+            ichimoku_signal,
+            ichimoku_conf,
+            ichimoku_weight,
+            stc_signal,
+            stc_conf,
+            stc_weight,
+            rsi_signal,
+            rsi_conf,
+            rsi_weight,
+            macd_signal,
+            macd_conf,
+            macd_weight,
+        }
+    }
+}
+
+impl ConfluenceInput {
+    /// Triplets (signal, confiance, poids) dans l'ordre Ichimoku, STC, RSI, MACD
+    fn entries(&self) -> [(SignalType, f64, f64); 4] {
+        [
+            (self.ichimoku_signal, self.ichimoku_conf, self.ichimoku_weight),
+            (self.stc_signal, self.stc_conf, self.stc_weight),
+            (self.rsi_signal, self.rsi_conf, self.rsi_weight),
+            (self.macd_signal, self.macd_conf, self.macd_weight),
+        ]
+    }
+}
+
 #[pyclass]
 pub struct SignalDetector {
+    /// Seuil sur le score brut 0-100, avant calibrage, pour toutes les
+    /// méthodes ; en dessous le résultat devient (Neutral, 0.0)
+    #[pyo3(get)]
     min_confidence: f64,
+    /// Poids Ichimoku : prix hors du nuage
+    #[pyo3(get)]
+    cloud_weight: f64,
+    /// Poids Ichimoku : croisement Tenkan/Kijun
+    #[pyo3(get)]
+    tk_cross_weight: f64,
+    /// Poids Ichimoku : Tenkan et Kijun du même côté du nuage
+    #[pyo3(get)]
+    confirmation_weight: f64,
+    /// Confiance attribuée à un croisement STC
+    #[pyo3(get)]
+    stc_confidence: f64,
+    /// Poids Ichimoku : confirmation par la Chikou
+    #[pyo3(get)]
+    chikou_weight: f64,
+    /// Niveau de survente STC (croisement haussier)
+    #[pyo3(get)]
+    stc_oversold: f64,
+    /// Niveau de surachat STC (croisement baissier)
+    #[pyo3(get)]
+    stc_overbought: f64,
+    /// Hystérésis STC : un nouveau signal de même sens exige que le STC ait
+    /// d'abord traversé toute la bande jusqu'au niveau opposé
+    #[pyo3(get)]
+    stc_hysteresis: bool,
+    /// Signaux STC Long / Short autorisés (hystérésis)
+    stc_long_armed: bool,
+    stc_short_armed: bool,
+    /// Dernier signal émis (anti-répétition)
+    last_signal: SignalType,
+    /// Timestamp du dernier signal émis, en microsecondes
+    last_signal_ts: i64,
+    /// Historique borné des signaux détectés (timestamp, signal, confiance)
+    history: VecDeque<(i64, SignalType, f64)>,
+    history_capacity: usize,
+    /// Horloge imposée (backtest), sinon horloge système
+    clock_us: Option<i64>,
+    /// Règle imposée à combine_signals / combine_weighted ; None conserve
+    /// leurs règles historiques (Unanimous / WeightedSum)
+    #[pyo3(get)]
+    combine_policy: Option<CombinePolicy>,
+    /// Courbe de calibrage (score brut, probabilité), triée par score brut ;
+    /// vide : confiances brutes
+    #[pyo3(get)]
+    calibration: Vec<(f64, f64)>,
 }
 
 #[pymethods]
 impl SignalDetector {
     #[new]
-    fn new(min_confidence: f64) -> Self {
-        SignalDetector { min_confidence }
+    #[pyo3(signature = (
+        min_confidence,
+        cloud_weight=30.0,
+        tk_cross_weight=40.0,
+        confirmation_weight=30.0,
+        stc_confidence=70.0,
+        chikou_weight=20.0,
+        stc_oversold=25.0,
+        stc_overbought=75.0,
+        stc_hysteresis=false,
+        history_capacity=256,
+        combine_policy=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        min_confidence: f64,
+        cloud_weight: f64,
+        tk_cross_weight: f64,
+        confirmation_weight: f64,
+        stc_confidence: f64,
+        chikou_weight: f64,
+        stc_oversold: f64,
+        stc_overbought: f64,
+        stc_hysteresis: bool,
+        history_capacity: usize,
+        combine_policy: Option<CombinePolicy>,
+    ) -> PyResult<Self> {
+        check_stc_levels(stc_oversold, stc_overbought)?;
+        
+        Ok(SignalDetector {
+            min_confidence,
+            cloud_weight,
+            tk_cross_weight,
+            confirmation_weight,
+            stc_confidence,
+            chikou_weight,
+            stc_oversold,
+            stc_overbought,
+            stc_hysteresis,
+            stc_long_armed: true,
+            stc_short_armed: true,
+            last_signal: SignalType::Neutral,
+            last_signal_ts: 0,
+            history: VecDeque::with_capacity(history_capacity),
+            history_capacity,
+            clock_us: None,
+            combine_policy,
+            calibration: Vec::new(),
+        })
+    }
+    
+    /// Modifie les poids de scoring (calibrage par instrument)
+    #[pyo3(signature = (
+        cloud_weight,
+        tk_cross_weight,
+        confirmation_weight,
+        stc_confidence,
+        chikou_weight=20.0
+    ))]
+    fn set_weights(
+        &mut self,
+        cloud_weight: f64,
+        tk_cross_weight: f64,
+        confirmation_weight: f64,
+        stc_confidence: f64,
+        chikou_weight: f64,
+    ) {
+        self.cloud_weight = cloud_weight;
+        self.tk_cross_weight = tk_cross_weight;
+        self.confirmation_weight = confirmation_weight;
+        self.stc_confidence = stc_confidence;
+        self.chikou_weight = chikou_weight;
+    }
+    
+    /// Définit la courbe de calibrage des confiances retournées
+    /// `points` : (score brut, probabilité), dans un ordre quelconque. Tout
+    /// score brut retenu (après min_confidence) est interpolé linéairement
+    /// entre points, prolongé à plat hors de la courbe, borné à [0, 1] :
+    /// detect_*, combinaisons, confluence, classify_trend et
+    /// confirm_with_higher_tf. Neutral garde une confiance nulle.
+    /// Les combinaisons attendent des scores bruts en entrée et calibrent
+    /// leur seul résultat.
+    fn set_calibration(&mut self, mut points: Vec<(f64, f64)>) -> PyResult<()> {
+        if points.is_empty() || points.iter().any(|(x, y)| !x.is_finite() || !y.is_finite()) {
+            return Err(HftError::InvalidParameter(
+                "la courbe de calibrage exige au moins un point fini".into()
+            )
+            .into());
+        }
+        
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if points.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(HftError::InvalidParameter(
+                "scores bruts en double dans la courbe de calibrage".into()
+            )
+            .into());
+        }
+        
+        self.calibration = points;
+        Ok(())
+    }
+    
+    /// Supprime la courbe : confiances brutes
+    fn clear_calibration(&mut self) {
+        self.calibration.clear();
+    }
+    
+    /// Probabilité calibrée d'un score brut (score inchangé sans courbe)
+    fn calibrate(&self, confidence: f64) -> f64 {
+        let points = &self.calibration;
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return confidence,
+        };
+        
+        let probability = if confidence <= first.0 {
+            first.1
+        } else if confidence >= last.0 {
+            last.1
+        } else {
+            let i = points.partition_point(|p| p.0 <= confidence);
+            let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+            y0 + (y1 - y0) * (confidence - x0) / (x1 - x0)
+        };
+        probability.clamp(0.0, 1.0)
     }
     
     /// Détecte un signal Ichimoku
+    ///
+    /// Si `chikou` et `price_26_ago` sont fournis, la Chikou sert de filtre :
+    /// du même côté que le prix par rapport au nuage, elle ajoute
+    /// chikou_weight (score plafonné à 100) ; en désaccord, un signal
+    /// Long/Short est rétrogradé en Neutral.
+    #[pyo3(signature = (price, tenkan, kijun, senkou_a, senkou_b, chikou=None, price_26_ago=None))]
+    #[allow(clippy::too_many_arguments)]
     fn detect_ichimoku_signal(
-        &self,
+        &mut self,
         price: f64,
         tenkan: f64,
         kijun: f64,
         senkou_a: f64,
         senkou_b: f64,
-    ) -> (String, f64) {
+        chikou: Option<f64>,
+        price_26_ago: Option<f64>,
+    ) -> (SignalType, f64) {
         
         let cloud_top = senkou_a.max(senkou_b);
         let cloud_bottom = senkou_a.min(senkou_b);
         
         let mut score = 0.0;
-        let mut signal = "NEUTRAL".to_string();
+        let mut signal = SignalType::Neutral;
         
         // Prix au-dessus du nuage
         if price > cloud_top {
-            score += 30.0;
+            score += self.cloud_weight;
             
             // TK Cross haussier
             if tenkan > kijun {
-                score += 40.0;
+                score += self.tk_cross_weight;
                 
                 // Confirmation forte
                 if tenkan > cloud_top && kijun > cloud_top {
-                    score += 30.0;
-                    signal = "LONG".to_string();
+                    score += self.confirmation_weight;
+                    signal = SignalType::Long;
                 }
             }
         }
         // Prix en-dessous du nuage
         else if price < cloud_bottom {
-            score += 30.0;
+            score += self.cloud_weight;
             
             // TK Cross baissier
             if tenkan < kijun {
-                score += 40.0;
+                score += self.tk_cross_weight;
                 
                 // Confirmation forte
                 if tenkan < cloud_bottom && kijun < cloud_bottom {
-                    score += 30.0;
-                    signal = "SHORT".to_string();
+                    score += self.confirmation_weight;
+                    signal = SignalType::Short;
                 }
             }
         }
         
-        (signal, score)
+        // Confirmation Chikou vs prix d'il y a 26 barres
+        if let (Some(chikou), Some(past_price)) = (chikou, price_26_ago) {
+            let side = if price > cloud_top {
+                1.0
+            } else if price < cloud_bottom {
+                -1.0
+            } else {
+                0.0
+            };
+            let chikou_side = (chikou - past_price).signum();
+            
+            if side != 0.0 && chikou != past_price && chikou_side == side {
+                score = (score + self.chikou_weight).min(100.0);
+            } else if signal != SignalType::Neutral {
+                signal = SignalType::Neutral;
+            }
+        }
+        
+        self.finish(signal, score)
+    }
+    
+    /// Modifie les niveaux STC ; réarme les deux sens
+    #[pyo3(signature = (oversold, overbought, hysteresis=false))]
+    fn set_stc_levels(&mut self, oversold: f64, overbought: f64, hysteresis: bool) -> PyResult<()> {
+        check_stc_levels(oversold, overbought)?;
+        
+        self.stc_oversold = oversold;
+        self.stc_overbought = overbought;
+        self.stc_hysteresis = hysteresis;
+        self.stc_long_armed = true;
+        self.stc_short_armed = true;
+        Ok(())
     }
     
     /// Détecte un signal STC
-    fn detect_stc_signal(&self, stc: f64, prev_stc: f64) -> (String, f64) {
-        let mut signal = "NEUTRAL".to_string();
+    /// Long quand le STC repasse au-dessus de `stc_oversold`, Short quand il
+    /// repasse sous `stc_overbought`. Avec `stc_hysteresis`, un Long émis
+    /// n'est réarmé qu'une fois `stc_overbought` dépassé (et inversement).
+    /// Les appels doivent alors suivre l'ordre des barres.
+    fn detect_stc_signal(&mut self, stc: f64, prev_stc: f64) -> (SignalType, f64) {
+        let mut signal = SignalType::Neutral;
         let mut confidence = 0.0;
         
+        if self.stc_hysteresis {
+            if stc > self.stc_overbought {
+                self.stc_long_armed = true;
+            }
+            if stc < self.stc_oversold {
+                self.stc_short_armed = true;
+            }
+        }
+        
+        let crossed_up = prev_stc < self.stc_oversold && stc > self.stc_oversold;
+        let crossed_down = prev_stc > self.stc_overbought && stc < self.stc_overbought;
+        
         // Survente → Achat
-        if prev_stc < 25.0 && stc > 25.0 {
-            signal = "LONG".to_string();
-            confidence = 70.0;
+        if crossed_up && self.stc_long_armed {
+            signal = SignalType::Long;
+            confidence = self.stc_confidence;
+            self.stc_long_armed = !self.stc_hysteresis;
         }
         // Surachat → Vente
-        else if prev_stc > 75.0 && stc < 75.0 {
-            signal = "SHORT".to_string();
-            confidence = 70.0;
+        else if crossed_down && self.stc_short_armed {
+            signal = SignalType::Short;
+            confidence = self.stc_confidence;
+            self.stc_short_armed = !self.stc_hysteresis;
         }
         
-        (signal, confidence)
+        self.finish(signal, confidence)
     }
     
-    /// Combine plusieurs signaux
+    /// `detect_stc_signal` sur toute une série STC, barre par barre
+    /// Retourne un résultat par barre : la première barre et celles dont
+    /// la valeur courante ou précédente est NaN (warm-up) valent Neutral
+    /// sans être évaluées. Même état (hystérésis, historique) que des
+    /// appels successifs.
+    fn detect_stc_signals(&mut self, stc: Vec<f64>) -> Vec<(SignalType, f64)> {
+        let mut signals = Vec::with_capacity(stc.len());
+        if !stc.is_empty() {
+            signals.push((SignalType::Neutral, 0.0));
+        }
+        
+        for w in stc.windows(2) {
+            let (prev, current) = (w[0], w[1]);
+            if prev.is_nan() || current.is_nan() {
+                signals.push((SignalType::Neutral, 0.0));
+            } else {
+                signals.push(self.detect_stc_signal(current, prev));
+            }
+        }
+        signals
+    }
+    
+    /// Détecte une pression du carnet à partir des tailles au meilleur prix
+    /// Long si bid_size / (bid_size + ask_size) dépasse `threshold`, Short
+    /// si ask_size / (bid_size + ask_size) le dépasse, Neutral sinon ou si
+    /// la taille totale est nulle. Confiance : 100 * |déséquilibre|, avec
+    /// déséquilibre = (bid_size - ask_size) / (bid_size + ask_size).
+    #[pyo3(signature = (bid_size, ask_size, threshold=0.6))]
+    fn detect_book_pressure(
+        &mut self,
+        bid_size: f64,
+        ask_size: f64,
+        threshold: f64,
+    ) -> PyResult<(SignalType, f64)> {
+        if !(0.5..1.0).contains(&threshold) {
+            return Err(HftError::InvalidParameter(format!(
+                "threshold = {} hors de [0.5, 1)", threshold
+            ))
+            .into());
+        }
+        
+        let total = bid_size + ask_size;
+        let valid = bid_size >= 0.0 && ask_size >= 0.0 && total > 0.0;
+        if !valid {
+            return Ok((SignalType::Neutral, 0.0));
+        }
+        
+        let bid_share = bid_size / total;
+        let signal = if bid_share > threshold {
+            SignalType::Long
+        } else if 1.0 - bid_share > threshold {
+            SignalType::Short
+        } else {
+            return Ok((SignalType::Neutral, 0.0));
+        };
+        
+        let imbalance = (bid_size - ask_size) / total;
+        Ok(self.finish(signal, 100.0 * imbalance.abs()))
+    }
+    
+    /// Détecte un signal de retour à la moyenne sur le z-score du prix
+    /// z = (price - mean) / std : Short au-delà de +entry_z, Long sous
+    /// -entry_z. Confiance de 50 au seuil, 100 à deux fois le seuil.
+    /// Neutral si std <= 0 ou entry_z <= 0.
+    #[pyo3(signature = (price, mean, std, entry_z=2.0))]
+    fn detect_zscore_signal(
+        &mut self,
+        price: f64,
+        mean: f64,
+        std: f64,
+        entry_z: f64,
+    ) -> (SignalType, f64) {
+        if !(std > 0.0 && entry_z > 0.0) {
+            return (SignalType::Neutral, 0.0);
+        }
+        
+        let z = (price - mean) / std;
+        let signal = if z > entry_z {
+            SignalType::Short
+        } else if z < -entry_z {
+            SignalType::Long
+        } else {
+            return (SignalType::Neutral, 0.0);
+        };
+        
+        let confidence = (50.0 * z.abs() / entry_z).min(100.0);
+        self.finish(signal, confidence)
+    }
+    
+    /// Filtre CUSUM symétrique sur une série de rendements
+    /// s+ = max(0, s+ + r - drift), s- = min(0, s- + r + drift) ; un
+    /// franchissement s+ > threshold donne (i, Long), s- < -threshold donne
+    /// (i, Short), puis les deux sommes repartent de zéro. `drift` est la
+    /// dérive tolérée par barre : plus il est grand, plus une tendance lente
+    /// est absorbée sans déclencher. Les rendements NaN sont ignorés.
+    #[pyo3(signature = (returns, threshold, drift=0.0))]
+    fn detect_cusum(
+        &self,
+        returns: Vec<f64>,
+        threshold: f64,
+        drift: f64,
+    ) -> PyResult<Vec<(usize, SignalType)>> {
+        if threshold.is_nan() || threshold <= 0.0 || drift.is_nan() || drift < 0.0 {
+            return Err(HftError::InvalidParameter(
+                "CUSUM : threshold doit être > 0 et drift >= 0".into()
+            )
+            .into());
+        }
+        
+        let mut events = Vec::new();
+        let (mut s_pos, mut s_neg) = (0.0f64, 0.0f64);
+        for (i, &r) in returns.iter().enumerate() {
+            if r.is_nan() {
+                continue;
+            }
+            s_pos = (s_pos + r - drift).max(0.0);
+            s_neg = (s_neg + r + drift).min(0.0);
+            
+            let signal = if s_pos > threshold {
+                SignalType::Long
+            } else if s_neg < -threshold {
+                SignalType::Short
+            } else {
+                continue;
+            };
+            events.push((i, signal));
+            s_pos = 0.0;
+            s_neg = 0.0;
+        }
+        
+        Ok(events)
+    }
+    
+    /// Croisement d'un niveau entre deux prix consécutifs
+    /// +1 si le prix passe strictement du dessous au dessus, -1 dans
+    /// l'autre sens, 0 sinon. Toucher le niveau n'est pas un croisement :
+    /// un prix égal au niveau (avant ou après) retourne 0 ; voir
+    /// `detect_crosses` pour suivre une série qui traverse par contact.
+    fn detect_cross(&self, prev_price: f64, price: f64, level: f64) -> i8 {
+        cross_of(side_of(prev_price, level), side_of(price, level))
+    }
+    
+    /// Croisements d'un niveau sur une série de prix, un résultat par prix
+    /// Le côté retenu est le dernier côté strict : un passage 99 → 100 → 101
+    /// autour de 100 est un croisement haussier signalé à 101, un contact
+    /// suivi d'un retour (99 → 100 → 99) n'est pas un croisement.
+    /// Le premier prix vaut toujours 0 ; les NaN sont ignorés.
+    fn detect_crosses(&self, prices: Vec<f64>, level: f64) -> Vec<i8> {
+        let mut last_side = 0;
+        prices
+            .iter()
+            .map(|&price| {
+                let side = side_of(price, level);
+                let cross = cross_of(last_side, side);
+                if side != 0 {
+                    last_side = side;
+                }
+                cross
+            })
+            .collect()
+    }
+    
+    /// Détecte une divergence régulière prix / oscillateur sur les
+    /// `lookback` dernières barres
+    ///
+    /// Les deux derniers creux (resp. sommets) du prix sont comparés à
+    /// l'oscillateur aux mêmes barres : plus bas plus bas du prix avec creux
+    /// montant de l'oscillateur → Long, plus haut plus haut avec sommet
+    /// descendant → Short. La divergence la plus récente l'emporte.
+    /// Confiance = 50 * (écart prix + écart oscillateur), chacun normalisé
+    /// par son range sur la fenêtre. Neutral si les séries sont trop courtes
+    /// ou de longueurs différentes.
+    fn detect_divergence(
+        &mut self,
+        prices: Vec<f64>,
+        osc: Vec<f64>,
+        lookback: usize,
+    ) -> (SignalType, f64) {
+        if lookback < 3 || prices.len() < lookback || prices.len() != osc.len() {
+            return (SignalType::Neutral, 0.0);
+        }
+        
+        let start = prices.len() - lookback;
+        let prices = &prices[start..];
+        let osc = &osc[start..];
+        
+        let price_range = range_of(prices);
+        let osc_range = range_of(osc);
+        if !(price_range > 0.0 && osc_range > 0.0) {
+            return (SignalType::Neutral, 0.0);
+        }
+        
+        // (barre du second pivot, signal, confiance)
+        let mut best: Option<(usize, SignalType, f64)> = None;
+        
+        for (signal, pivots) in [
+            (SignalType::Long, swing_points(prices, osc, false)),
+            (SignalType::Short, swing_points(prices, osc, true)),
+        ] {
+            let [.., first, second] = pivots[..] else { continue };
+            let price_move = (prices[second] - prices[first]) * signal.direction();
+            let osc_move = (osc[second] - osc[first]) * signal.direction();
+            
+            // Régulière : le prix prolonge la tendance, l'oscillateur non
+            let more_recent = best.is_none_or(|(bar, _, _)| second > bar);
+            if price_move < 0.0 && osc_move > 0.0 && more_recent {
+                let mismatch = -price_move / price_range + osc_move / osc_range;
+                best = Some((second, signal, (50.0 * mismatch).min(100.0)));
+            }
+        }
+        
+        match best {
+            Some((_, signal, confidence)) => self.finish(signal, confidence),
+            None => (SignalType::Neutral, 0.0),
+        }
+    }
+    
+    /// Combine les signaux Ichimoku et STC (poids égaux)
+    /// Selon `combine_policy`, Unanimous par défaut : accord des deux
+    /// signaux requis, confiance moyenne
     fn combine_signals(
         &self,
-        ichimoku_signal: String,
+        ichimoku_signal: SignalType,
         ichimoku_conf: f64,
-        stc_signal: String,
+        stc_signal: SignalType,
         stc_conf: f64,
-    ) -> (String, f64) {
+    ) -> (SignalType, f64) {
+        let policy = self.combine_policy.unwrap_or(CombinePolicy::Unanimous);
+        let signals = [(ichimoku_signal, ichimoku_conf, 1.0), (stc_signal, stc_conf, 1.0)];
+        self.apply_combined(policy.combine(&signals))
+    }
+    
+    /// Tendance d'après deux EMA et la pente de l'EMA rapide (variation
+    /// par barre) : Long si rapide > lente et pente > 0, Short si rapide <
+    /// lente et pente < 0, Neutral sinon (range)
+    /// Confiance : 50 pour un écart |rapide - lente| de `full_gap` (relatif
+    /// à la lente) plus 50 pour une pente de `full_slope` (relative aussi),
+    /// chaque part plafonnée ; soumise à min_confidence
+    #[pyo3(signature = (fast_ema, slow_ema, slope, full_gap=0.001, full_slope=0.0002))]
+    fn classify_trend(
+        &self,
+        fast_ema: f64,
+        slow_ema: f64,
+        slope: f64,
+        full_gap: f64,
+        full_slope: f64,
+    ) -> PyResult<(SignalType, f64)> {
+        if full_gap <= 0.0 || full_slope <= 0.0 {
+            return Err(HftError::InvalidParameter(
+                "full_gap et full_slope doivent être strictement positifs".into()
+            )
+            .into());
+        }
+        
+        let gap = fast_ema - slow_ema;
+        let signal = if gap > 0.0 && slope > 0.0 {
+            SignalType::Long
+        } else if gap < 0.0 && slope < 0.0 {
+            SignalType::Short
+        } else {
+            // Désaccord, égalité ou entrée NaN
+            return Ok((SignalType::Neutral, 0.0));
+        };
+        
+        let scale = slow_ema.abs();
+        let gap_part = (gap.abs() / scale / full_gap).min(1.0);
+        let slope_part = (slope.abs() / scale / full_slope).min(1.0);
+        let confidence = 50.0 * gap_part + 50.0 * slope_part;
+        if !confidence.is_finite() {
+            return Ok((SignalType::Neutral, 0.0));
+        }
+        Ok(self.apply_threshold(signal, confidence))
+    }
+    
+    /// Confirme un signal de timeframe court par la tendance d'un timeframe
+    /// supérieur (ex. M1 par M5)
+    /// Accord : confiance `ltf_conf + boost` (plafonnée à 100) ; désaccord ou
+    /// tendance supérieure neutre : Neutral
+    #[pyo3(signature = (ltf_signal, htf_trend, ltf_conf=50.0, boost=20.0))]
+    fn confirm_with_higher_tf(
+        &self,
+        ltf_signal: SignalType,
+        htf_trend: SignalType,
+        ltf_conf: f64,
+        boost: f64,
+    ) -> (SignalType, f64) {
+        if ltf_signal == SignalType::Neutral || ltf_signal != htf_trend {
+            return (SignalType::Neutral, 0.0);
+        }
+        
+        self.apply_threshold(ltf_signal, (ltf_conf + boost).min(100.0))
+    }
+    
+    /// Combine un ensemble de signaux pondérés (signal, confiance, poids)
+    /// Selon `combine_policy`, WeightedSum par défaut : score net =
+    /// somme(direction * confiance * poids) / somme(poids) ; le signe donne
+    /// la direction, la valeur absolue la confiance. Un score net nul
+    /// (égalité) donne Neutral.
+    fn combine_weighted(&self, signals: Vec<(SignalType, f64, f64)>) -> (SignalType, f64) {
+        let policy = self.combine_policy.unwrap_or(CombinePolicy::WeightedSum);
+        self.apply_combined(policy.combine(&signals))
+    }
+    
+    /// Score de confluence 0-100 sur Ichimoku, STC, RSI et MACD
+    /// Même règle que `combine_weighted` ; avec WeightedSum, un indicateur
+    /// neutre dilue le score, un indicateur opposé le réduit d'autant
+    fn score_confluence(&self, inputs: ConfluenceInput) -> (SignalType, f64) {
+        self.combine_weighted(inputs.entries().to_vec())
+    }
+    
+    /// Indique si un signal doit être émis (anti-répétition)
+    /// Un signal de même direction que le précédent est supprimé tant que
+    /// `cooldown_us` n'est pas écoulé ; un changement de direction est
+    /// toujours émis et relance la fenêtre. Neutral n'est jamais émis.
+    fn should_emit(&mut self, signal: SignalType, timestamp: i64, cooldown_us: i64) -> bool {
+        if signal == SignalType::Neutral {
+            return false;
+        }
+        
+        if signal == self.last_signal && timestamp - self.last_signal_ts < cooldown_us {
+            return false;
+        }
+        
+        self.last_signal = signal;
+        self.last_signal_ts = timestamp;
+        true
+    }
+    
+    /// Impose l'horodatage (µs) des signaux enregistrés, pour le backtest
+    /// None revient à l'horloge système
+    fn set_clock(&mut self, timestamp: Option<i64>) {
+        self.clock_us = timestamp;
+    }
+    
+    /// N derniers signaux détectés (timestamp, signal, confiance), du plus
+    /// ancien au plus récent. Seuls les signaux non neutres des méthodes
+    /// detect_* sont enregistrés.
+    fn recent_signals(&self, n: usize) -> Vec<(i64, SignalType, f64)> {
+        let start = self.history.len().saturating_sub(n);
+        self.history.range(start..).cloned().collect()
+    }
+    
+    /// Nombre de changements de direction parmi les `window` derniers
+    /// signaux enregistrés
+    fn signal_flip_count(&self, window: usize) -> usize {
+        let start = self.history.len().saturating_sub(window);
+        self.history
+            .range(start..)
+            .zip(self.history.range(start..).skip(1))
+            .filter(|((_, prev, _), (_, cur, _))| prev != cur)
+            .count()
+    }
+    
+    /// Suggère (stop-loss, take-profit) à partir de l'ATR
+    /// Long : SL = entry - sl_mult * atr, TP = entry + tp_mult * atr
+    /// Short : symétrique ; Neutral : (NaN, NaN)
+    #[pyo3(signature = (entry, direction, atr, sl_mult=1.5, tp_mult=2.0))]
+    fn suggest_levels(
+        &self,
+        entry: f64,
+        direction: SignalType,
+        atr: f64,
+        sl_mult: f64,
+        tp_mult: f64,
+    ) -> (f64, f64) {
+        let sign = direction.direction();
+        if sign == 0.0 {
+            return (f64::NAN, f64::NAN);
+        }
+        
+        (entry - sign * sl_mult * atr, entry + sign * tp_mult * atr)
+    }
+    
+    /// Taille de position telle que la perte au stop vaille `account_risk`
+    /// unités = account_risk / (|entry - stop| * contract_value), arrondi
+    /// au multiple inférieur de `lot_step` ; valable en Long comme en Short
+    /// 0.0 si entry == stop ou si le risque tient sous un lot
+    #[pyo3(signature = (account_risk, entry, stop, contract_value, lot_step=0.01))]
+    fn position_size(
+        &self,
+        account_risk: f64,
+        entry: f64,
+        stop: f64,
+        contract_value: f64,
+        lot_step: f64,
+    ) -> PyResult<f64> {
+        let valid = account_risk >= 0.0 && contract_value > 0.0 && lot_step > 0.0;
+        if !valid || !entry.is_finite() || !stop.is_finite() {
+            return Err(HftError::InvalidParameter(
+                "account_risk >= 0, contract_value > 0, lot_step > 0 et prix finis requis".into()
+            )
+            .into());
+        }
+        
+        let risk_per_unit = (entry - stop).abs() * contract_value;
+        if risk_per_unit == 0.0 {
+            return Ok(0.0);
+        }
+        
+        // Tolérance pour ne pas perdre un lot sur l'arrondi binaire (0.29 / 0.01)
+        let lots = (account_risk / risk_per_unit / lot_step + 1e-9).floor();
+        Ok(lots * lot_step)
+    }
+}
+
+impl SignalDetector {
+    /// Issue commune de toutes les méthodes : seuil min_confidence sur le
+    /// score brut, puis calibrage ; Neutral sort toujours avec 0.0
+    fn apply_threshold(&self, signal: SignalType, confidence: f64) -> (SignalType, f64) {
+        let below = confidence.is_nan() || confidence < self.min_confidence;
+        if signal == SignalType::Neutral || below {
+            (SignalType::Neutral, 0.0)
+        } else {
+            (signal, self.calibrate(confidence))
+        }
+    }
+    
+    /// Seuil et calibrage appliqués au résultat d'une combinaison
+    fn apply_combined(&self, combined: Option<(SignalType, f64)>) -> (SignalType, f64) {
+        match combined {
+            Some((signal, confidence)) => self.apply_threshold(signal, confidence),
+            None => (SignalType::Neutral, 0.0),
+        }
+    }
+    
+    /// Seuil, calibrage puis enregistrement dans l'historique des signaux
+    /// non neutres
+    fn finish(&mut self, signal: SignalType, confidence: f64) -> (SignalType, f64) {
+        let result = self.apply_threshold(signal, confidence);
+        
+        if result.0 != SignalType::Neutral && self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            let timestamp = self.clock_us.unwrap_or_else(now_us);
+            self.history.push_back((timestamp, result.0, result.1));
+        }
+        
+        result
+    }
+}
+
+/// Horloge système en microsecondes depuis l'epoch
+fn now_us() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as i64)
+}
+
+/// Vérifie 0 <= oversold < overbought <= 100
+fn check_stc_levels(oversold: f64, overbought: f64) -> PyResult<()> {
+    if !(0.0..=100.0).contains(&oversold)
+        || !(0.0..=100.0).contains(&overbought)
+        || oversold >= overbought
+    {
+        return Err(HftError::InvalidParameter(format!(
+            "niveaux STC {} / {} : il faut 0 <= survente < surachat <= 100",
+            oversold, overbought
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Score net pondéré (direction, |score|), None si nul ou sans poids
+fn net_score(signals: &[(SignalType, f64, f64)]) -> Option<(SignalType, f64)> {
+    let total_weight: f64 = signals.iter().map(|&(_, _, w)| w).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    
+    let net = signals.iter()
+        .map(|&(signal, conf, w)| signal.direction() * conf * w)
+        .sum::<f64>() / total_weight;
+    
+    if net > 0.0 {
+        Some((SignalType::Long, net))
+    } else if net < 0.0 {
+        Some((SignalType::Short, -net))
+    } else {
+        None
+    }
+}
+
+/// Côté d'un prix par rapport à un niveau : 1 au-dessus, -1 en dessous,
+/// 0 au contact (ou NaN)
+fn side_of(price: f64, level: f64) -> i8 {
+    if price > level {
+        1
+    } else if price < level {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Croisement entre deux côtés stricts, 0 si l'un est au contact
+fn cross_of(prev_side: i8, side: i8) -> i8 {
+    if prev_side != 0 && side == -prev_side { side } else { 0 }
+}
+
+/// Amplitude (max - min) d'une série, NaN ignorés
+fn range_of(data: &[f64]) -> f64 {
+    let max = data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let min = data.iter().cloned().fold(f64::INFINITY, f64::min);
+    max - min
+}
+
+/// Indices des pivots du prix (sommets si `highs`, sinon creux)
+/// Un pivot est strict à gauche et large à droite ; les extrémités de la
+/// série et les barres où l'oscillateur est NaN sont exclues
+fn swing_points(prices: &[f64], osc: &[f64], highs: bool) -> Vec<usize> {
+    (1..prices.len() - 1)
+        .filter(|&i| {
+            let (prev, cur, next) = (prices[i - 1], prices[i], prices[i + 1]);
+            let is_pivot = if highs {
+                cur > prev && cur >= next
+            } else {
+                cur < prev && cur <= next
+            };
+            is_pivot && !osc[i].is_nan()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Seuil 60 sur le score brut, courbe (0, 0) (50, 0.4) (100, 0.9) :
+    /// un score brut de 70 vaut 0.6
+    fn calibrated_detector() -> SignalDetector {
+        let mut detector = SignalDetector::new(
+            60.0, 30.0, 40.0, 30.0, 70.0, 20.0, 25.0, 75.0, false, 16, None,
+        )
+        .unwrap();
+        detector.set_calibration(vec![(100.0, 0.9), (0.0, 0.0), (50.0, 0.4)]).unwrap();
+        detector
+    }
+    
+    fn assert_result(result: (SignalType, f64), signal: SignalType, confidence: f64) {
+        assert_eq!(result.0, signal);
+        assert!((result.1 - confidence).abs() < 1e-12, "{} != {}", result.1, confidence);
+    }
+    
+    #[test]
+    fn raw_seventy_maps_to_the_interpolated_probability() {
+        let mut detector = calibrated_detector();
+        assert!((detector.calibrate(70.0) - 0.6).abs() < 1e-12);
+        assert_eq!(detector.calibrate(-10.0), 0.0);
+        assert_eq!(detector.calibrate(150.0), 0.9);
+        
+        assert_result(detector.detect_stc_signal(30.0, 20.0), SignalType::Long, 0.6);
+        assert!((detector.recent_signals(1)[0].2 - 0.6).abs() < 1e-12);
+        
+        detector.clear_calibration();
+        assert_result(detector.detect_stc_signal(70.0, 80.0), SignalType::Short, 70.0);
+    }
+    
+    #[test]
+    fn every_path_thresholds_the_raw_score_then_calibrates() {
+        let mut detector = calibrated_detector();
+        let (long, neutral) = (SignalType::Long, SignalType::Neutral);
+        
+        assert_result(detector.combine_signals(long, 70.0, long, 70.0), long, 0.6);
+        assert_result(detector.combine_weighted(vec![(long, 70.0, 2.0)]), long, 0.6);
+        let all_long = ConfluenceInput::new(
+            long, 70.0, long, 70.0, long, 70.0, long, 70.0, 1.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(detector.score_confluence(all_long), long, 0.6);
+        assert_result(detector.confirm_with_higher_tf(long, long, 50.0, 20.0), long, 0.6);
+        let trend = detector.classify_trend(2002.0, 2000.0, 1.0, 0.001, 0.0002).unwrap();
+        assert_result(trend, long, 0.9);
+        
+        // 55 brut (0.45 calibré) reste sous le seuil de 60 : Neutral, 0.0
+        assert_result(detector.combine_signals(long, 55.0, long, 55.0), neutral, 0.0);
+        let diluted = ConfluenceInput::new(
+            long, 70.0, neutral, 0.0, neutral, 0.0, neutral, 0.0, 1.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(detector.score_confluence(diluted), neutral, 0.0);
+        
+        // Neutral n'est jamais calibré, même si la courbe part au-dessus de 0
+        detector.set_calibration(vec![(0.0, 0.2), (100.0, 0.9)]).unwrap();
+        assert_result(detector.detect_stc_signal(50.0, 50.0), neutral, 0.0);
+        assert_result(detector.confirm_with_higher_tf(long, neutral, 90.0, 0.0), neutral, 0.0);
+        assert!(detector.recent_signals(16).is_empty());
+    }
+    
+    /// Détecteur aux poids par défaut, sans calibrage
+    fn detector(min_confidence: f64) -> SignalDetector {
+        SignalDetector::new(
+            min_confidence, 30.0, 40.0, 30.0, 70.0, 20.0, 25.0, 75.0, false, 16, None,
+        )
+        .unwrap()
+    }
+    
+    #[test]
+    fn weak_scores_are_suppressed_below_min_confidence() {
+        let (long, neutral) = (SignalType::Long, SignalType::Neutral);
+        
+        // Poids 10 / 10 / 10 : un signal Long complet ne pèse que 30
+        let mut strict = detector(50.0);
+        strict.set_weights(10.0, 10.0, 10.0, 70.0, 20.0);
+        let mut lenient = detector(20.0);
+        lenient.set_weights(10.0, 10.0, 10.0, 70.0, 20.0);
+        let weak = |d: &mut SignalDetector| {
+            d.detect_ichimoku_signal(2010.0, 2008.0, 2006.0, 2000.0, 2002.0, None, None)
+        };
+        assert_result(weak(&mut strict), neutral, 0.0);
+        assert_result(weak(&mut lenient), long, 30.0);
+        assert!(strict.recent_signals(16).is_empty());
+        
+        // STC à 70 et combinaison à 45 de moyenne face à un seuil de 80
+        let mut high = detector(80.0);
+        assert_result(high.detect_stc_signal(30.0, 20.0), neutral, 0.0);
+        assert_result(high.combine_signals(long, 40.0, long, 50.0), neutral, 0.0);
+        assert_result(detector(45.0).combine_signals(long, 40.0, long, 50.0), long, 45.0);
+    }
+    
+    #[test]
+    fn weighted_combination_handles_unanimous_majority_and_tie() {
+        let detector = detector(0.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        
+        let unanimous = vec![(long, 60.0, 1.0), (long, 80.0, 1.0), (long, 100.0, 2.0)];
+        assert_result(detector.combine_weighted(unanimous), long, 85.0);
+        
+        // Un Short fort l'emporte sur deux Long faibles : (90 * 3 - 20 - 20) / 5
+        let majority = vec![(long, 20.0, 1.0), (long, 20.0, 1.0), (short, 90.0, 3.0)];
+        assert_result(detector.combine_weighted(majority), short, 46.0);
+        
+        let tie = vec![(long, 50.0, 2.0), (short, 100.0, 1.0), (neutral, 0.0, 1.0)];
+        assert_result(detector.combine_weighted(tie), neutral, 0.0);
+        assert_result(detector.combine_weighted(Vec::new()), neutral, 0.0);
+    }
+    
+    #[test]
+    fn cooldown_suppresses_repeats_until_the_direction_flips() {
+        const COOLDOWN: i64 = 60_000_000;
+        let mut detector = detector(0.0);
+        let (long, short) = (SignalType::Long, SignalType::Short);
+        
+        // Répétitions rapprochées : seule la première passe
+        assert!(detector.should_emit(long, 0, COOLDOWN));
+        assert!(!detector.should_emit(long, 1_000_000, COOLDOWN));
+        assert!(!detector.should_emit(long, COOLDOWN - 1, COOLDOWN));
+        assert!(detector.should_emit(long, COOLDOWN, COOLDOWN));
+        assert!(!detector.should_emit(SignalType::Neutral, 10 * COOLDOWN, COOLDOWN));
+        
+        // Un retournement dans la fenêtre est émis et la relance
+        assert!(detector.should_emit(short, COOLDOWN + 5_000_000, COOLDOWN));
+        assert!(!detector.should_emit(short, COOLDOWN + 6_000_000, COOLDOWN));
+        assert!(detector.should_emit(long, COOLDOWN + 7_000_000, COOLDOWN));
+        assert!(!detector.should_emit(long, 2 * COOLDOWN + 6_999_999, COOLDOWN));
+        assert!(detector.should_emit(long, 2 * COOLDOWN + 7_000_000, COOLDOWN));
+    }
+    
+    #[test]
+    fn zscore_fires_only_beyond_the_entry_threshold() {
+        let mut detector = detector(0.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        
+        // z = ±2 exactement : pas encore de signal
+        assert_result(detector.detect_zscore_signal(2004.0, 2000.0, 2.0, 2.0), neutral, 0.0);
+        assert_result(detector.detect_zscore_signal(1996.0, 2000.0, 2.0, 2.0), neutral, 0.0);
+        assert_result(detector.detect_zscore_signal(2001.0, 2000.0, 2.0, 2.0), neutral, 0.0);
+        
+        // Au-delà : 50 juste après le seuil, 75 à 1.5 fois, 100 à deux fois
+        assert_result(detector.detect_zscore_signal(2006.0, 2000.0, 2.0, 2.0), short, 75.0);
+        assert_result(detector.detect_zscore_signal(1994.0, 2000.0, 2.0, 2.0), long, 75.0);
+        assert_result(detector.detect_zscore_signal(2012.0, 2000.0, 2.0, 2.0), short, 100.0);
+        let (signal, confidence) = detector.detect_zscore_signal(2004.01, 2000.0, 2.0, 2.0);
+        assert_eq!(signal, short);
+        assert!((confidence - 50.0).abs() < 0.2);
+        
+        // Écart-type ou seuil non positif
+        assert_result(detector.detect_zscore_signal(2010.0, 2000.0, 0.0, 2.0), neutral, 0.0);
+        assert_result(detector.detect_zscore_signal(2010.0, 2000.0, -1.0, 2.0), neutral, 0.0);
+        assert_result(detector.detect_zscore_signal(2010.0, 2000.0, 2.0, 0.0), neutral, 0.0);
+    }
+    
+    #[test]
+    fn divergence_flags_constructed_bullish_and_bearish_setups() {
+        let mut detector = detector(0.0);
+        
+        // Creux du prix 8 → 7, creux de l'oscillateur 20 → 30 ; sommets
+        // 10 → 11 accompagnés par l'oscillateur (60 → 70)
+        let prices = vec![10.0, 8.0, 9.0, 10.0, 7.0, 9.0, 10.0, 11.0, 10.0];
+        let osc = vec![50.0, 20.0, 40.0, 60.0, 30.0, 50.0, 60.0, 70.0, 60.0];
+        // 50 * (1 / 4 + 10 / 50)
+        let bullish = detector.detect_divergence(prices.clone(), osc.clone(), 9);
+        assert_result(bullish, SignalType::Long, 22.5);
+        
+        // Miroir : plus haut plus haut du prix, sommet descendant
+        let mirrored = |series: &[f64], axis: f64| -> Vec<f64> {
+            series.iter().map(|v| axis - v).collect()
+        };
+        let bearish = detector.detect_divergence(mirrored(&prices, 20.0), mirrored(&osc, 100.0), 9);
+        assert_result(bearish, SignalType::Short, 22.5);
+        
+        // Prix et oscillateur d'accord, séries trop courtes ou désalignées
+        let aligned = detector.detect_divergence(prices.clone(), prices.clone(), 9);
+        assert_result(aligned, SignalType::Neutral, 0.0);
+        let short = detector.detect_divergence(prices[..5].to_vec(), osc[..5].to_vec(), 9);
+        assert_result(short, SignalType::Neutral, 0.0);
+        let mismatched = detector.detect_divergence(prices, osc[1..].to_vec(), 8);
+        assert_result(mismatched, SignalType::Neutral, 0.0);
+    }
+    
+    #[test]
+    fn confluence_reflects_agreement_and_respects_min_confidence() {
+        let strict = detector(50.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        
+        let full = ConfluenceInput::new(
+            long, 80.0, long, 80.0, long, 80.0, long, 80.0, 1.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(strict.score_confluence(full), long, 80.0);
+        
+        // Accord partiel : les neutres diluent, (90 * 2 + 80) / 5
+        let partial = ConfluenceInput::new(
+            long, 90.0, long, 80.0, neutral, 0.0, neutral, 0.0, 2.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(strict.score_confluence(partial), long, 52.0);
+        let diluted = ConfluenceInput::new(
+            long, 80.0, long, 80.0, neutral, 0.0, neutral, 0.0, 1.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(strict.score_confluence(diluted), neutral, 0.0);
+        
+        // Conflit : équilibre parfait, ou majorité pondérée sous le seuil
+        let balanced = ConfluenceInput::new(
+            long, 80.0, long, 80.0, short, 80.0, short, 80.0, 1.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(strict.score_confluence(balanced), neutral, 0.0);
+        let opposed = || ConfluenceInput::new(
+            short, 90.0, long, 60.0, neutral, 0.0, neutral, 0.0, 3.0, 1.0, 1.0, 1.0,
+        );
+        assert_result(strict.score_confluence(opposed()), neutral, 0.0);
+        assert_result(detector(0.0).score_confluence(opposed()), short, 35.0);
+    }
+    
+    #[test]
+    fn stc_levels_are_configurable_and_hysteresis_waits_for_the_opposite_band() {
+        let (long, neutral) = (SignalType::Long, SignalType::Neutral);
+        let mut default_levels = detector(0.0);
+        let mut custom = detector(0.0);
+        custom.set_stc_levels(20.0, 80.0, false).unwrap();
+        
+        // 15 → 22 ne franchit que 20, 22 → 30 que 25
+        assert_result(custom.detect_stc_signal(22.0, 15.0), long, 70.0);
+        assert_result(custom.detect_stc_signal(30.0, 22.0), neutral, 0.0);
+        assert_result(default_levels.detect_stc_signal(22.0, 15.0), neutral, 0.0);
+        assert_result(default_levels.detect_stc_signal(30.0, 22.0), long, 70.0);
+        
+        let series = vec![10.0, 25.0, 15.0, 25.0, 85.0, 70.0, 90.0, 75.0, 10.0, 30.0];
+        let directions = |d: &mut SignalDetector| -> Vec<i8> {
+            d.detect_stc_signals(series.clone())
+                .iter()
+                .map(|&(signal, _)| signal.direction() as i8)
+                .collect()
+        };
+        assert_eq!(directions(&mut custom), vec![0, 1, 0, 1, 0, -1, 0, -1, 0, 1]);
+        
+        // Hystérésis : le second Long attend un passage au-dessus de 80, le
+        // second Short un passage sous 20
+        let mut hysteresis = detector(0.0);
+        hysteresis.set_stc_levels(20.0, 80.0, true).unwrap();
+        assert_eq!(directions(&mut hysteresis), vec![0, 1, 0, 0, 0, -1, 0, 0, 0, 1]);
+        
+        assert!(custom.set_stc_levels(80.0, 20.0, false).is_err());
+        assert!(custom.set_stc_levels(-5.0, 80.0, false).is_err());
+    }
+    
+    #[test]
+    fn history_records_fired_signals_and_counts_flips() {
+        let mut detector = SignalDetector::new(
+            0.0, 30.0, 40.0, 30.0, 70.0, 20.0, 25.0, 75.0, false, 4, None,
+        )
+        .unwrap();
+        
+        // Long, Long, Short, Long, Short ; les Neutral ne sont pas enregistrés
+        for (ts, &(prev, stc)) in [
+            (20.0, 30.0),
+            (20.0, 30.0),
+            (50.0, 50.0),
+            (80.0, 70.0),
+            (20.0, 30.0),
+            (80.0, 70.0),
+        ]
+        .iter()
+        .enumerate()
+        {
+            detector.set_clock(Some(ts as i64 * 1_000));
+            detector.detect_stc_signal(stc, prev);
+        }
+        
+        // Capacité 4 : le premier Long est sorti de l'historique
+        let recent = detector.recent_signals(10);
+        let signals: Vec<SignalType> = recent.iter().map(|&(_, signal, _)| signal).collect();
+        let (long, short) = (SignalType::Long, SignalType::Short);
+        assert_eq!(signals, vec![long, short, long, short]);
+        assert_eq!(recent[0].0, 1_000);
+        assert_eq!(recent[3], (5_000, short, 70.0));
+        
+        assert_eq!(detector.signal_flip_count(10), 3);
+        assert_eq!(detector.signal_flip_count(2), 1);
+        assert_eq!(detector.signal_flip_count(1), 0);
+        assert_eq!(detector.recent_signals(2).len(), 2);
+    }
+    
+    #[test]
+    fn level_crosses_cover_touch_up_down_and_none() {
+        let signals = detector(0.0);
+        
+        assert_eq!(signals.detect_cross(99.0, 101.0, 100.0), 1);
+        assert_eq!(signals.detect_cross(101.0, 99.0, 100.0), -1);
+        assert_eq!(signals.detect_cross(99.0, 99.5, 100.0), 0);
+        assert_eq!(signals.detect_cross(101.0, 102.0, 100.0), 0);
+        // Contact exact, avant ou après : pas de croisement
+        assert_eq!(signals.detect_cross(99.0, 100.0, 100.0), 0);
+        assert_eq!(signals.detect_cross(100.0, 101.0, 100.0), 0);
+        assert_eq!(signals.detect_cross(f64::NAN, 101.0, 100.0), 0);
+        
+        let prices = vec![
+            99.0, 100.0, 99.0, 100.0, 101.0, 101.5, 100.0, 99.0, f64::NAN, 98.0, 102.0,
+        ];
+        let crosses = signals.detect_crosses(prices, 100.0);
+        assert_eq!(crosses, vec![0, 0, 0, 0, 1, 0, 0, -1, 0, 0, 1]);
+        assert!(signals.detect_crosses(Vec::new(), 100.0).is_empty());
+    }
+    
+    #[test]
+    fn combine_policies_diverge_on_the_same_inputs() {
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        let with_policy = |policy| {
+            SignalDetector::new(0.0, 30.0, 40.0, 30.0, 70.0, 20.0, 25.0, 75.0, false, 16, policy)
+                .unwrap()
+        };
+        let inputs = vec![
+            (long, 40.0, 1.0), (long, 30.0, 1.0), (short, 90.0, 1.0), (neutral, 0.0, 1.0),
+        ];
+        
+        let combined = |policy| with_policy(Some(policy)).combine_weighted(inputs.clone());
+        assert_result(combined(CombinePolicy::Unanimous), neutral, 0.0);
+        // Deux votes Long contre un Short : moyenne des confiances Long
+        assert_result(combined(CombinePolicy::Majority), long, 35.0);
+        // (40 + 30 - 90 + 0) / 4
+        assert_result(combined(CombinePolicy::WeightedSum), short, 5.0);
+        assert_result(combined(CombinePolicy::AnyStrong), short, 90.0);
+        
+        // combine_signals : Unanimous par défaut, un neutre bloque
+        let pair = |policy| with_policy(policy).combine_signals(long, 60.0, neutral, 0.0);
+        assert_result(pair(None), neutral, 0.0);
+        assert_result(pair(Some(CombinePolicy::Majority)), long, 60.0);
+        assert_result(pair(Some(CombinePolicy::WeightedSum)), long, 30.0);
+        assert_result(pair(Some(CombinePolicy::AnyStrong)), long, 60.0);
+        // combine_weighted : WeightedSum par défaut
+        assert_result(with_policy(None).combine_weighted(inputs.clone()), short, 5.0);
+        
+        // Égalité de confiance maximale entre sens opposés
+        let tie = vec![(long, 80.0, 1.0), (short, 80.0, 5.0)];
+        let strongest = with_policy(Some(CombinePolicy::AnyStrong)).combine_weighted(tie);
+        assert_result(strongest, neutral, 0.0);
+    }
+    
+    #[test]
+    fn cusum_fires_at_the_expected_index_after_a_step() {
+        let detector = detector(0.0);
+        let (long, short) = (SignalType::Long, SignalType::Short);
+        
+        // Bruit ±1e-4 absorbé par la dérive, puis marche à +1e-3 dès la
+        // barre 50 : la somme gagne 8e-4 par barre et franchit 2.5e-3 à la
+        // quatrième, repart de zéro, puis marche à -1e-3 dès la barre 60
+        let mut returns: Vec<f64> =
+            (0..50).map(|i| if i % 2 == 0 { 1e-4 } else { -1e-4 }).collect();
+        returns.extend([1e-3; 10]);
+        returns.extend([-1e-3; 4]);
+        let events = detector.detect_cusum(returns.clone(), 2.5e-3, 2e-4).unwrap();
+        assert_eq!(events, vec![(53, long), (57, long), (63, short)]);
+        
+        // Un NaN ne fait ni avancer ni remettre à zéro les sommes
+        returns.insert(52, f64::NAN);
+        let events = detector.detect_cusum(returns, 2.5e-3, 2e-4).unwrap();
+        assert_eq!(events[0], (54, long));
+        
+        // Tendance lente : absorbée par la dérive, détectée sans elle
+        let slow = vec![1e-4; 200];
+        assert!(detector.detect_cusum(slow.clone(), 2.55e-3, 2e-4).unwrap().is_empty());
+        assert_eq!(detector.detect_cusum(slow, 2.55e-3, 0.0).unwrap()[0], (25, long));
+        
+        for (threshold, drift) in [(0.0, 0.0), (f64::NAN, 0.0), (1e-3, -1e-4)] {
+            assert!(detector.detect_cusum(vec![0.0], threshold, drift).is_err());
+        }
+    }
+    
+    #[test]
+    fn trend_is_labelled_from_the_ema_gap_and_slope() {
+        let strict = detector(60.0);
+        let detector = detector(0.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        let trend = |fast, slow, slope| detector.classify_trend(fast, slow, slope, 0.001, 0.0002).unwrap();
+        
+        // Hausse : écart de 0.1 % (part pleine) et pente de 1e-4 (demi-part)
+        assert_result(trend(2002.0, 2000.0, 0.2), long, 75.0);
+        // Baisse : écart de 0.05 % et pente de 1e-4, une demi-part chacun
+        assert_result(trend(1999.0, 2000.0, -0.2), short, 50.0);
+        // Parts plafonnées
+        assert_result(trend(2100.0, 2000.0, 5.0), long, 100.0);
+        
+        // Range : écart et pente en désaccord, ou nuls
+        assert_result(trend(2002.0, 2000.0, -0.2), neutral, 0.0);
+        assert_result(trend(1998.0, 2000.0, 0.2), neutral, 0.0);
+        assert_result(trend(2000.0, 2000.0, 0.2), neutral, 0.0);
+        assert_result(trend(2002.0, 2000.0, 0.0), neutral, 0.0);
+        assert_result(trend(f64::NAN, 2000.0, 0.2), neutral, 0.0);
+        
+        // Tendance faible sous min_confidence, paramètres non positifs refusés
+        let weak = strict.classify_trend(1999.0, 2000.0, -0.2, 0.001, 0.0002).unwrap();
+        assert_result(weak, neutral, 0.0);
+        assert!(strict.classify_trend(2002.0, 2000.0, 0.2, 0.0, 0.0002).is_err());
+        assert!(strict.classify_trend(2002.0, 2000.0, 0.2, 0.001, -1.0).is_err());
+    }
+    
+    #[test]
+    fn series_scan_matches_repeated_single_bar_calls() {
+        // Warm-up NaN, oscillation qui traverse 25 / 75, puis un trou
+        let mut stc = vec![f64::NAN; 12];
+        stc.extend((0..120).map(|i| 50.0 + 48.0 * (i as f64 / 7.0).sin()));
+        stc[60] = f64::NAN;
+        
+        for hysteresis in [false, true] {
+            let mut batch = detector(0.0);
+            let mut single = detector(0.0);
+            batch.set_stc_levels(25.0, 75.0, hysteresis).unwrap();
+            single.set_stc_levels(25.0, 75.0, hysteresis).unwrap();
+            batch.set_clock(Some(0));
+            single.set_clock(Some(0));
+            
+            let scanned = batch.detect_stc_signals(stc.clone());
+            assert_eq!(scanned.len(), stc.len());
+            assert_result(scanned[0], SignalType::Neutral, 0.0);
+            for i in 1..stc.len() {
+                if stc[i].is_nan() || stc[i - 1].is_nan() {
+                    assert_result(scanned[i], SignalType::Neutral, 0.0);
+                } else {
+                    let (signal, confidence) = single.detect_stc_signal(stc[i], stc[i - 1]);
+                    assert_result(scanned[i], signal, confidence);
+                }
+            }
+            
+            // Même historique, et des signaux effectivement émis
+            assert_eq!(batch.recent_signals(16), single.recent_signals(16));
+            let fired = scanned.iter().filter(|(signal, _)| *signal != SignalType::Neutral);
+            assert!(fired.count() >= 2);
+        }
+        assert!(detector(0.0).detect_stc_signals(Vec::new()).is_empty());
+    }
+    
+    #[test]
+    fn book_pressure_fires_only_beyond_the_threshold() {
+        let mut strict = detector(50.0);
+        let mut detector = detector(0.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        let mut pressure = |bid, ask| detector.detect_book_pressure(bid, ask, 0.6).unwrap();
+        
+        // Part de 60 % exactement, d'un côté comme de l'autre : pas de signal
+        assert_result(pressure(6.0, 4.0), neutral, 0.0);
+        assert_result(pressure(4.0, 6.0), neutral, 0.0);
+        // En deçà
+        assert_result(pressure(5.5, 4.5), neutral, 0.0);
+        // Au-delà : confiance 100 * |bid - ask| / total
+        assert_result(pressure(7.0, 3.0), long, 40.0);
+        assert_result(pressure(2.0, 8.0), short, 60.0);
+        assert_result(pressure(10.0, 0.0), long, 100.0);
+        
+        // Carnet vide ou tailles négatives
+        assert_result(pressure(0.0, 0.0), neutral, 0.0);
+        assert_result(pressure(-1.0, 3.0), neutral, 0.0);
+        
+        // Confiance de 40 sous un min_confidence de 50
+        assert_result(strict.detect_book_pressure(7.0, 3.0, 0.6).unwrap(), neutral, 0.0);
+        for threshold in [0.4, 1.0, f64::NAN] {
+            assert!(detector.detect_book_pressure(7.0, 3.0, threshold).is_err());
+        }
+    }
+    
+    #[test]
+    fn position_size_puts_the_account_risk_at_the_stop() {
+        let detector = detector(0.0);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        
+        // Long 2000 / stop 1995, 100 onces par lot : 500 de risque par lot
+        let long = detector.position_size(500.0, 2000.0, 1995.0, 100.0, 0.01).unwrap();
+        assert!(close(long, 1.0));
+        assert!(close(long * (2000.0 - 1995.0) * 100.0, 500.0));
+        
+        // Short 2000 / stop 2010 : 1000 par lot, 290 de risque → 0.29 lot,
+        // sans perdre un pas sur l'arrondi binaire ; arrondi inférieur au pas
+        let short = detector.position_size(290.0, 2000.0, 2010.0, 100.0, 0.01).unwrap();
+        assert!(close(short, 0.29));
+        assert!(close(short * (2010.0 - 2000.0) * 100.0, 290.0));
+        let coarse = detector.position_size(290.0, 2000.0, 2010.0, 100.0, 0.1).unwrap();
+        assert!(close(coarse, 0.2));
+        
+        // Stop sur l'entrée, risque sous un pas
+        assert_eq!(detector.position_size(500.0, 2000.0, 2000.0, 100.0, 0.01).unwrap(), 0.0);
+        assert_eq!(detector.position_size(5.0, 2000.0, 1990.0, 100.0, 0.01).unwrap(), 0.0);
+        
+        for (risk, stop, contract_value, lot_step) in [
+            (-1.0, 1995.0, 100.0, 0.01),
+            (500.0, f64::NAN, 100.0, 0.01),
+            (500.0, 1995.0, 0.0, 0.01),
+            (500.0, 1995.0, 100.0, 0.0),
+        ] {
+            assert!(detector.position_size(risk, 2000.0, stop, contract_value, lot_step).is_err());
+        }
+    }
+    
+    #[test]
+    fn higher_tf_confirms_agreement_and_vetoes_conflict_symmetrically() {
+        let strict = detector(80.0);
+        let detector = detector(0.0);
+        let (long, short, neutral) = (SignalType::Long, SignalType::Short, SignalType::Neutral);
+        
+        // Accord : confiance relevée du boost, plafonnée à 100
+        assert_result(detector.confirm_with_higher_tf(long, long, 50.0, 20.0), long, 70.0);
+        assert_result(detector.confirm_with_higher_tf(short, short, 50.0, 20.0), short, 70.0);
+        assert_result(detector.confirm_with_higher_tf(short, short, 90.0, 20.0), short, 100.0);
         
-        // Accord parfait
-        if ichimoku_signal == stc_signal && ichimoku_signal != "NEUTRAL" {
-            let combined_conf = (ichimoku_conf + stc_conf) / 2.0;
-            return (ichimoku_signal, combined_conf);
+        // Désaccord, tendance supérieure neutre ou signal court neutre
+        for (ltf, htf) in [(long, short), (short, long), (long, neutral), (short, neutral)] {
+            assert_result(detector.confirm_with_higher_tf(ltf, htf, 90.0, 20.0), neutral, 0.0);
         }
+        assert_result(detector.confirm_with_higher_tf(neutral, neutral, 90.0, 20.0), neutral, 0.0);
         
-        // Désaccord ou neutralité
-        ("NEUTRAL".to_string(), 0.0)
+        // Le score relevé reste soumis à min_confidence
+        assert_result(strict.confirm_with_higher_tf(long, long, 50.0, 20.0), neutral, 0.0);
+        assert_result(strict.confirm_with_higher_tf(long, long, 60.0, 20.0), long, 80.0);
     }
 }
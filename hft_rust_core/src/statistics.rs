@@ -0,0 +1,488 @@
+//! Statistiques glissantes multi-séries (pairs trading, couverture)
+
+use pyo3::prelude::*;
+use crate::errors::HftError;
+use crate::indicators::{
+    calc_rolling_mean_std, check_causal, check_period, check_same_len, to_pyarray, NanPolicy,
+    PriceSeries, PySeries, RollingExtremum,
+};
+
+/// Corrélation de Pearson glissante entre deux séries
+/// NaN pendant le warm-up et sur les fenêtres de variance nulle ;
+/// résultat borné à [-1, 1]
+#[pyfunction]
+#[pyo3(signature = (a, b, period=20, nan_policy=NanPolicy::Skip))]
+pub(crate) fn rolling_correlation(
+    py: Python<'_>,
+    a: PriceSeries<'_>,
+    b: PriceSeries<'_>,
+    period: usize,
+    nan_policy: NanPolicy,
+) -> PyResult<PySeries> {
+    let (a, b) = (a.as_slice(nan_policy), b.as_slice(nan_policy));
+    check_same_len(&[&a, &b])?;
+    check_period("period", period, a.len())?;
+    if period < 2 {
+        return Err(HftError::InvalidPeriod(
+            "la corrélation exige period >= 2".into()
+        )
+        .into());
+    }
+    
+    let corr = py.allow_threads(|| {
+        calc_rolling_moments(&a, &b, period, RollingCoMoments::correlation)
+    });
+    check_causal("rolling_correlation", &[&corr], |k| {
+        vec![calc_rolling_moments(&a[..k], &b[..k], period, RollingCoMoments::correlation)]
+    })?;
+    Ok(to_pyarray(py, corr))
+}
+
+/// Pente OLS glissante de y sur x : cov(x, y) / var(x)
+/// NaN pendant le warm-up et sur les fenêtres où x est constant
+#[pyfunction]
+#[pyo3(signature = (y, x, period=20, nan_policy=NanPolicy::Skip))]
+pub(crate) fn rolling_beta(
+    py: Python<'_>,
+    y: PriceSeries<'_>,
+    x: PriceSeries<'_>,
+    period: usize,
+    nan_policy: NanPolicy,
+) -> PyResult<PySeries> {
+    let (y, x) = (y.as_slice(nan_policy), x.as_slice(nan_policy));
+    check_same_len(&[&y, &x])?;
+    check_period("period", period, x.len())?;
+    if period < 2 {
+        return Err(HftError::InvalidPeriod(
+            "le beta exige period >= 2".into()
+        )
+        .into());
+    }
+    
+    let beta = py.allow_threads(|| {
+        calc_rolling_moments(&x, &y, period, RollingCoMoments::beta)
+    });
+    check_causal("rolling_beta", &[&beta], |k| {
+        vec![calc_rolling_moments(&x[..k], &y[..k], period, RollingCoMoments::beta)]
+    })?;
+    Ok(to_pyarray(py, beta))
+}
+
+/// Spread d'une paire : y - beta * x, élément par élément
+/// `beta` est typiquement la sortie de `rolling_beta` (NaN propagés)
+#[pyfunction]
+#[pyo3(signature = (y, x, beta, nan_policy=NanPolicy::Skip))]
+pub(crate) fn spread(
+    py: Python<'_>,
+    y: PriceSeries<'_>,
+    x: PriceSeries<'_>,
+    beta: PriceSeries<'_>,
+    nan_policy: NanPolicy,
+) -> PyResult<PySeries> {
+    let (y, x, beta) = (y.as_slice(nan_policy), x.as_slice(nan_policy), beta.as_slice(nan_policy));
+    check_same_len(&[&y, &x, &beta])?;
+    
+    let spread = y.iter()
+        .zip(x.iter())
+        .zip(beta.iter())
+        .map(|((y, x), b)| y - b * x)
+        .collect();
+    Ok(to_pyarray(py, spread))
+}
+
+/// Réduction glissante nommée sur chaque fenêtre de `period` valeurs
+/// op : "mean", "sum", "std" (population), "max", "min" ou "median"
+/// NaN pendant les `period - 1` premières valeurs ; max et min ignorent
+/// les NaN de la fenêtre, les autres valent NaN tant qu'un NaN y est
+#[pyfunction]
+#[pyo3(signature = (data, period, op, nan_policy=NanPolicy::Skip))]
+pub(crate) fn rolling_apply(
+    py: Python<'_>,
+    data: PriceSeries<'_>,
+    period: usize,
+    op: &str,
+    nan_policy: NanPolicy,
+) -> PyResult<PySeries> {
+    let data = data.as_slice(nan_policy);
+    if data.is_empty() {
+        return Err(HftError::EmptyInput("data").into());
+    }
+    check_period("period", period, data.len())?;
+    
+    if !ROLLING_OPS.contains(&op) {
+        return Err(HftError::InvalidParameter(format!(
+            "opération inconnue '{}' ({})", op, ROLLING_OPS.join(", ")
+        ))
+        .into());
+    }
+    
+    let result = py.allow_threads(|| calc_rolling_op(&data, period, op));
+    check_causal("rolling_apply", &[&result], |k| {
+        vec![calc_rolling_op(&data[..k], period, op)]
+    })?;
+    Ok(to_pyarray(py, result))
+}
+
+/// Opérations acceptées par `rolling_apply`
+const ROLLING_OPS: [&str; 6] = ["mean", "sum", "std", "max", "min", "median"];
+
+/// Réduction glissante `op` (parmi `ROLLING_OPS`) sur `data`
+fn calc_rolling_op(data: &[f64], period: usize, op: &str) -> Vec<f64> {
+    match op {
+        "mean" => calc_rolling_mean_std(data, period).0,
+        "std" => calc_rolling_mean_std(data, period).1,
+        "sum" => {
+            let (means, _) = calc_rolling_mean_std(data, period);
+            means.into_iter().map(|m| m * period as f64).collect()
+        }
+        "max" | "min" => {
+            let mut window = RollingExtremum::new(period, op == "max");
+            data.iter().map(|&x| window.push(x)).collect()
+        }
+        "median" => calc_rolling_median(data, period),
+        _ => unreachable!("opération validée par rolling_apply"),
+    }
+}
+
+/// Médiane glissante sur une fenêtre triée maintenue par insertion
+/// binaire, O(n * period) dans le pire cas
+/// NaN tant qu'un NaN est dans la fenêtre, comme les moyennes glissantes :
+/// les NaN sont comptés à part et jamais insérés dans la fenêtre triée
+fn calc_rolling_median(data: &[f64], period: usize) -> Vec<f64> {
+    let mut result = vec![f64::NAN; data.len()];
+    let mut sorted: Vec<f64> = Vec::with_capacity(period + 1);
+    let mut nans = 0;
+    
+    for (i, &x) in data.iter().enumerate() {
+        if x.is_nan() {
+            nans += 1;
+        } else {
+            let pos = sorted.partition_point(|v| v.total_cmp(&x).is_lt());
+            sorted.insert(pos, x);
+        }
+        if i >= period {
+            let old = data[i - period];
+            if old.is_nan() {
+                nans -= 1;
+            } else {
+                let pos = sorted.partition_point(|v| v.total_cmp(&old).is_lt());
+                sorted.remove(pos);
+            }
+        }
+        
+        if i + 1 >= period && nans == 0 {
+            let mid = period / 2;
+            result[i] = if period % 2 == 1 {
+                sorted[mid]
+            } else {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            };
+        }
+    }
+    
+    result
+}
+
+/// Seuil relatif sous lequel une somme de carrés des écarts est tenue pour
+/// nulle, rapporté à n * moyenne²
+const VARIANCE_REL_EPS: f64 = 1e-12;
+
+/// Moments glissants d'une paire de séries sur une fenêtre fixe
+/// Mise à jour de Welford (retrait puis ajout), O(1) par barre, sans
+/// l'annulation catastrophique des sommes de carrés aux prix de l'or
+struct RollingCoMoments {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    /// Sommes des produits des écarts à la moyenne
+    cxx: f64,
+    cyy: f64,
+    cxy: f64,
+}
+
+impl RollingCoMoments {
+    /// Calcul exact en deux passes sur une fenêtre
+    fn exact(xs: &[f64], ys: &[f64]) -> Self {
+        let n = xs.len() as f64;
+        let mean_x = xs.iter().sum::<f64>() / n;
+        let mean_y = ys.iter().sum::<f64>() / n;
+        
+        let (mut cxx, mut cyy, mut cxy) = (0.0, 0.0, 0.0);
+        for (x, y) in xs.iter().zip(ys) {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            cxx += dx * dx;
+            cyy += dy * dy;
+            cxy += dx * dy;
+        }
+        
+        RollingCoMoments { n, mean_x, mean_y, cxx, cyy, cxy }
+    }
+    
+    /// Remplace la paire la plus ancienne (x0, y0) par (x1, y1)
+    fn replace(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) {
+        let n = self.n;
+        
+        // Retrait
+        let mean_x = (n * self.mean_x - x0) / (n - 1.0);
+        let mean_y = (n * self.mean_y - y0) / (n - 1.0);
+        self.cxx -= (x0 - self.mean_x) * (x0 - mean_x);
+        self.cyy -= (y0 - self.mean_y) * (y0 - mean_y);
+        self.cxy -= (x0 - self.mean_x) * (y0 - mean_y);
+        
+        // Ajout
+        self.mean_x = mean_x + (x1 - mean_x) / n;
+        self.mean_y = mean_y + (y1 - mean_y) / n;
+        self.cxx += (x1 - mean_x) * (x1 - self.mean_x);
+        self.cyy += (y1 - mean_y) * (y1 - self.mean_y);
+        self.cxy += (x1 - self.mean_x) * (y1 - mean_y);
+    }
+    
+    /// Somme de carrés `c` indiscernable de l'arrondi pour une série de
+    /// moyenne `mean` : une série constante laisse alors un résidu de
+    /// l'ordre de n * mean² * ε après les mises à jour glissantes
+    fn negligible(&self, c: f64, mean: f64) -> bool {
+        c <= VARIANCE_REL_EPS * self.n * mean * mean
+    }
+    
+    /// Corrélation de Pearson, NaN si l'une des séries est constante
+    fn correlation(&self) -> f64 {
+        if self.negligible(self.cxx, self.mean_x) || self.negligible(self.cyy, self.mean_y) {
+            return f64::NAN;
+        }
+        (self.cxy / (self.cxx * self.cyy).sqrt()).clamp(-1.0, 1.0)
+    }
+    
+    /// Pente de y (seconde série) sur x (première série), NaN si x est
+    /// constant
+    fn beta(&self) -> f64 {
+        if self.negligible(self.cxx, self.mean_x) {
+            return f64::NAN;
+        }
+        self.cxy / self.cxx
+    }
+}
+
+/// Fonction helper appliquant `stat` aux moments de chaque fenêtre
+/// La fenêtre est recalculée exactement toutes les `period` barres pour
+/// borner la dérive numérique. NaN tant qu'une paire de la fenêtre
+/// contient un NaN, puis recalcul exact dès que le dernier en sort
+fn calc_rolling_moments(
+    a: &[f64],
+    b: &[f64],
+    period: usize,
+    stat: fn(&RollingCoMoments) -> f64,
+) -> Vec<f64> {
+    let len = a.len();
+    let mut result = vec![f64::NAN; len];
+    
+    if period < 2 || period > len {
+        return result;
+    }
+    
+    let is_hole = |j: usize| (a[j].is_nan() || b[j].is_nan()) as usize;
+    let mut moments = RollingCoMoments::exact(&a[..period], &b[..period]);
+    // Paires NaN dans la fenêtre, et validité de `moments` pour la précédente
+    let mut holes: usize = (0..period - 1).map(is_hole).sum();
+    let mut tracking = false;
+    
+    for i in (period - 1)..len {
+        let start = i + 1 - period;
+        holes += is_hole(i);
+        if start > 0 {
+            holes -= is_hole(start - 1);
+        }
+        if holes > 0 {
+            tracking = false;
+            continue;
+        }
+        
+        if !tracking || (i + 1) % period == 0 {
+            tracking = true;
+            moments = RollingCoMoments::exact(&a[start..=i], &b[start..=i]);
+        } else {
+            moments.replace(a[start - 1], b[start - 1], a[i], b[i]);
+        }
+        result[i] = stat(&moments);
+    }
+    
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn correlation(a: &[f64], b: &[f64], period: usize) -> Vec<f64> {
+        calc_rolling_moments(a, b, period, RollingCoMoments::correlation)
+    }
+    
+    #[test]
+    fn correlation_is_one_for_affine_series_and_minus_one_when_mirrored() {
+        let a: Vec<f64> = (0..200).map(|i| 2000.0 + (i as f64 / 9.0).sin() * 5.0).collect();
+        let up: Vec<f64> = a.iter().map(|x| 3.0 * x - 100.0).collect();
+        let down: Vec<f64> = a.iter().map(|x| 4000.0 - 0.5 * x).collect();
+        
+        let corr = correlation(&a, &up, 20);
+        assert!(corr[..19].iter().all(|c| c.is_nan()));
+        assert!(corr[19..].iter().all(|c| (c - 1.0).abs() < 1e-9), "{:?}", corr);
+        
+        let anti = correlation(&a, &down, 20);
+        assert!(anti[19..].iter().all(|c| (c + 1.0).abs() < 1e-9), "{:?}", anti);
+    }
+    
+    #[test]
+    fn correlation_of_a_flat_window_is_nan_despite_rounding() {
+        // Prix élevés et plats après un mouvement : les retraits glissants
+        // laissent un résidu positif qui ne doit pas produire ±1
+        let mut a: Vec<f64> = (0..30).map(|i| 2000.0 + i as f64 * 0.37).collect();
+        a.extend([2011.1; 50]);
+        let b: Vec<f64> = (0..80).map(|i| 1500.0 + (i as f64).cos()).collect();
+        
+        let corr = correlation(&a, &b, 20);
+        assert!(corr[55..].iter().all(|c| c.is_nan()), "{:?}", &corr[55..]);
+    }
+    
+    #[test]
+    fn beta_recovers_the_slope_of_a_linear_relationship() {
+        let x: Vec<f64> = (0..150).map(|i| 1800.0 + (i as f64 / 5.0).sin() * 12.0).collect();
+        let y: Vec<f64> = x.iter().map(|x| 1.75 * x + 42.0).collect();
+        
+        let beta = calc_rolling_moments(&x, &y, 30, RollingCoMoments::beta);
+        assert!(beta[..29].iter().all(|b| b.is_nan()));
+        assert!(beta[29..].iter().all(|b| (b - 1.75).abs() < 1e-9), "{:?}", beta);
+        
+        // Spread nul avec la pente exacte, hors ordonnée à l'origine
+        let residual: Vec<f64> = y.iter().zip(&x).map(|(y, x)| y - beta[149] * x).collect();
+        assert!(residual[29..].iter().all(|r| (r - 42.0).abs() < 1e-6));
+    }
+    
+    #[test]
+    fn beta_on_constant_x_is_nan() {
+        let mut x: Vec<f64> = (0..25).map(|i| 1900.0 + i as f64 * 0.73).collect();
+        x.extend([1917.52; 60]);
+        let y: Vec<f64> = (0..85).map(|i| 2000.0 + (i as f64).sin()).collect();
+        
+        let beta = calc_rolling_moments(&x, &y, 20, RollingCoMoments::beta);
+        assert!(beta[45..].iter().all(|b| b.is_nan()), "{:?}", &beta[45..]);
+    }
+    
+    #[test]
+    fn moments_are_nan_only_while_the_hole_is_in_the_window() {
+        let period = 12;
+        let x: Vec<f64> = (0..90).map(|i| 2000.0 + (i as f64 / 4.0).sin() * 6.0).collect();
+        let mut y: Vec<f64> = (0..90).map(|i| 1500.0 + (i as f64 / 7.0).cos() * 3.0).collect();
+        // Trou au milieu d'un cycle de recalage : 35 % 12 != 0
+        y[35] = f64::NAN;
+        
+        for stat in [RollingCoMoments::correlation, RollingCoMoments::beta] {
+            let result = calc_rolling_moments(&x, &y, period, stat);
+            for i in period - 1..x.len() {
+                let start = i + 1 - period;
+                let (xs, ys) = (&x[start..=i], &y[start..=i]);
+                if ys.iter().any(|v| v.is_nan()) {
+                    assert!(result[i].is_nan(), "[{}] : {}", i, result[i]);
+                } else {
+                    let want = stat(&RollingCoMoments::exact(xs, ys));
+                    assert!(
+                        (result[i] - want).abs() < 1e-9,
+                        "[{}] : {} au lieu de {}", i, result[i], want
+                    );
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn rolling_statistics_pass_the_no_lookahead_check() {
+        use crate::indicators::set_no_lookahead;
+        
+        let a: Vec<f64> = (0..120).map(|i| 2000.0 + (i as f64 / 4.0).sin() * 3.0).collect();
+        let b: Vec<f64> = (0..120).map(|i| 1500.0 + (i as f64 / 7.0).cos() * 2.0).collect();
+        set_no_lookahead(true);
+        
+        for stat in [RollingCoMoments::correlation, RollingCoMoments::beta] {
+            let full = calc_rolling_moments(&a, &b, 20, stat);
+            check_causal("moments", &[&full], |k| {
+                vec![calc_rolling_moments(&a[..k], &b[..k], 20, stat)]
+            })
+            .unwrap();
+        }
+        for op in ROLLING_OPS {
+            let full = calc_rolling_op(&a, 15, op);
+            check_causal(op, &[&full], |k| vec![calc_rolling_op(&a[..k], 15, op)]).unwrap();
+        }
+        
+        // Moyenne centrée : dépend de la valeur suivante
+        let centered = |data: &[f64]| -> Vec<f64> {
+            (0..data.len())
+                .map(|i| data.get(i + 1).map_or(f64::NAN, |next| (data[i] + next) / 2.0))
+                .collect()
+        };
+        let err = check_causal("centered", &[&centered(&a)], |k| vec![centered(&a[..k])])
+            .unwrap_err();
+        set_no_lookahead(false);
+        let code: String = Python::with_gil(|py| {
+            err.value(py).getattr("code").unwrap().extract().unwrap()
+        });
+        assert_eq!(code, "LOOK_AHEAD");
+    }
+    
+    /// Réduction naïve d'une fenêtre, référence des tests rolling_apply
+    /// max et min ignorent les NaN, les autres les propagent
+    fn brute_force(window: &[f64], op: &str) -> f64 {
+        let n = window.len() as f64;
+        let mean = window.iter().sum::<f64>() / n;
+        let mut sorted: Vec<f64> = window.iter().copied().filter(|x| !x.is_nan()).collect();
+        sorted.sort_by(f64::total_cmp);
+        match op {
+            "mean" => mean,
+            "sum" => window.iter().sum(),
+            "std" => (window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt(),
+            _ if sorted.is_empty() => f64::NAN,
+            "max" => sorted[sorted.len() - 1],
+            "min" => sorted[0],
+            "median" if sorted.len() < window.len() => f64::NAN,
+            "median" if sorted.len() % 2 == 1 => sorted[sorted.len() / 2],
+            "median" => (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0,
+            _ => unreachable!(),
+        }
+    }
+    
+    #[test]
+    fn rolling_ops_match_a_brute_force_window_scan() {
+        // Valeurs répétées pour exercer les égalités de la médiane
+        let mut data: Vec<f64> = (0..300)
+            .map(|i| 2000.0 + ((i * 7 % 13) as f64 - 6.0) * 0.25 + (i as f64 / 40.0).sin())
+            .collect();
+        // Trous isolés : les fenêtres qui les contiennent sont vérifiées aussi
+        data[120] = f64::NAN;
+        data[201] = f64::NAN;
+        
+        for op in ROLLING_OPS {
+            for period in [1, 4, 7, 50] {
+                let result = calc_rolling_op(&data, period, op);
+                assert_eq!(result.len(), data.len());
+                assert!(result[..period - 1].iter().all(|v| v.is_nan()), "{} {}", op, period);
+                for (i, window) in data.windows(period).enumerate() {
+                    let (got, want) = (result[i + period - 1], brute_force(window, op));
+                    assert!(
+                        (got.is_nan() && want.is_nan())
+                            || (got - want).abs() <= 1e-9 * want.abs().max(1.0),
+                        "{} période {} barre {} : {} contre {}", op, period, i, got, want
+                    );
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn rolling_apply_rejects_an_unknown_op() {
+        Python::with_gil(|py| {
+            let data = PriceSeries::List(vec![1.0, 2.0, 3.0]);
+            let err = rolling_apply(py, data, 2, "variance", NanPolicy::Skip).unwrap_err();
+            let code: String = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, "INVALID_PARAMETER");
+            assert!(err.to_string().contains("variance"), "{}", err);
+        });
+    }
+}
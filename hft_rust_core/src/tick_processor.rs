@@ -1,10 +1,21 @@
 //! Buffer circulaire haute performance pour ticks
 //! Utilise parking_lot pour thread-safety optimale
 
+use numpy::{Element, IntoPyArray, PyArray1};
 use pyo3::prelude::*;
-use parking_lot::RwLock;
+use pyo3::types::{IntoPyDict, PyBytes, PyDict};
+use crate::errors::HftError;
+use crate::indicators::{
+    ichimoku_arrays, stc_array, EMACalculator, FlatRange, IchimokuArrays, IchimokuState, PySeries,
+    STCState,
+};
+use chrono::{DateTime, Datelike, DurationRound, TimeDelta, Timelike, Utc};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use chrono::{DateTime, Utc};
 
 /// Tick de marché
 #[pyclass]
@@ -20,13 +31,27 @@ pub struct Tick {
     pub timestamp: i64, // Unix timestamp en microsecondes
     #[pyo3(get)]
     pub volume: i64,
+    /// Tailles au meilleur bid / ask (0 si non fournies)
+    #[pyo3(get)]
+    pub bid_size: f64,
+    #[pyo3(get)]
+    pub ask_size: f64,
 }
 
 #[pymethods]
 impl Tick {
     #[new]
-    fn new(symbol: String, bid: f64, ask: f64, timestamp: i64, volume: i64) -> Self {
-        Tick { symbol, bid, ask, timestamp, volume }
+    #[pyo3(signature = (symbol, bid, ask, timestamp, volume, bid_size=0.0, ask_size=0.0))]
+    fn new(
+        symbol: String,
+        bid: f64,
+        ask: f64,
+        timestamp: i64,
+        volume: i64,
+        bid_size: f64,
+        ask_size: f64,
+    ) -> Self {
+        Tick { symbol, bid, ask, timestamp, volume, bid_size, ask_size }
     }
     
     #[getter]
@@ -34,15 +59,96 @@ impl Tick {
         (self.bid + self.ask) / 2.0
     }
     
+    /// Microprix : (bid * ask_size + ask * bid_size) / (bid_size + ask_size)
+    /// Se rapproche de l'ask quand le carnet penche côté bid ; mid si les
+    /// tailles sont absentes
+    #[getter]
+    fn microprice(&self) -> f64 {
+        let total = self.bid_size + self.ask_size;
+        if total > 0.0 {
+            (self.bid * self.ask_size + self.ask * self.bid_size) / total
+        } else {
+            self.mid_price()
+        }
+    }
+    
+    /// Déséquilibre des tailles (bid_size - ask_size) / (bid_size + ask_size),
+    /// dans [-1, 1] ; 0 si les tailles sont absentes
+    #[getter]
+    fn size_imbalance(&self) -> f64 {
+        let total = self.bid_size + self.ask_size;
+        if total > 0.0 {
+            (self.bid_size - self.ask_size) / total
+        } else {
+            0.0
+        }
+    }
+    
     #[getter]
     fn spread(&self) -> f64 {
         self.ask - self.bid
     }
+    
+    /// Horodatage en datetime Python (UTC)
+    #[getter(datetime)]
+    fn py_datetime(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py_datetime(py, self.datetime())
+    }
+}
+
+impl Tick {
+    /// Horodatage en DateTime UTC
+    pub fn datetime(&self) -> DateTime<Utc> {
+        datetime_from_us(self.timestamp)
+    }
+    
+    /// Vérifie la cohérence des données du flux
+    fn validate(&self) -> PyResult<()> {
+        let reason = if !(self.bid > 0.0 && self.bid.is_finite()) {
+            "bid doit être fini et strictement positif"
+        } else if !self.ask.is_finite() || self.ask < self.bid {
+            "ask doit être fini et supérieur ou égal au bid"
+        } else if self.timestamp <= 0 {
+            "timestamp doit être strictement positif"
+        } else if self.volume < 0 {
+            "le volume doit être positif ou nul"
+        } else if !(self.bid_size >= 0.0 && self.ask_size >= 0.0) {
+            "les tailles bid / ask doivent être positives"
+        } else {
+            return Ok(());
+        };
+        
+        Err(HftError::InvalidTick(format!(
+            "({} bid={} ask={} timestamp={}) : {}",
+            self.symbol, self.bid, self.ask, self.timestamp, reason
+        )).into())
+    }
+}
+
+/// Prix d'un tick utilisé pour construire les bougies
+#[pyclass]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    Mid,
+    Bid,
+    Ask,
+}
+
+impl PriceSource {
+    /// Prix du tick selon la source
+    fn of(self, tick: &Tick) -> f64 {
+        match self {
+            PriceSource::Mid => tick.mid_price(),
+            PriceSource::Bid => tick.bid,
+            PriceSource::Ask => tick.ask,
+        }
+    }
 }
 
 /// OHLC Bar
 #[pyclass]
 #[derive(Clone, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct OHLC {
     #[pyo3(get)]
     pub timestamp: i64,
@@ -58,91 +164,3846 @@ pub struct OHLC {
     pub volume: i64,
 }
 
-/// Buffer circulaire thread-safe pour ticks
+#[pymethods]
+impl OHLC {
+    /// Début de la bougie en datetime Python (UTC)
+    #[getter(datetime)]
+    fn py_datetime(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py_datetime(py, self.datetime())
+    }
+}
+
+impl OHLC {
+    /// Début de la bougie en DateTime UTC
+    pub fn datetime(&self) -> DateTime<Utc> {
+        datetime_from_us(self.timestamp)
+    }
+    
+    /// Ouvre une bougie à partir d'un premier tick
+    fn from_tick(timestamp: i64, tick: &Tick, source: PriceSource) -> Self {
+        let price = source.of(tick);
+        OHLC {
+            timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: tick.volume,
+        }
+    }
+    
+    /// Intègre un tick dans la bougie en cours
+    fn update(&mut self, tick: &Tick, source: PriceSource) {
+        let price = source.of(tick);
+        if price > self.high { self.high = price; }
+        if price < self.low { self.low = price; }
+        self.close = price;
+        self.volume += tick.volume;
+    }
+    
+    /// Intègre un tick arrivé en retard : la clôture reste inchangée
+    fn absorb_late(&mut self, tick: &Tick, source: PriceSource) {
+        let price = source.of(tick);
+        if price > self.high { self.high = price; }
+        if price < self.low { self.low = price; }
+        self.volume += tick.volume;
+    }
+}
+
+/// Bougie OHLC séparée bid / ask
 #[pyclass]
-pub struct TickBuffer {
-    ticks: Arc<RwLock<Vec<Tick>>>,
-    m1_candles: Arc<RwLock<Vec<OHLC>>>,
-    m5_candles: Arc<RwLock<Vec<OHLC>>>,
-    capacity: usize,
-    symbol: String,
+#[derive(Clone, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct OHLCBidAsk {
+    #[pyo3(get)]
+    pub timestamp: i64,
+    #[pyo3(get)]
+    pub bid_open: f64,
+    #[pyo3(get)]
+    pub bid_high: f64,
+    #[pyo3(get)]
+    pub bid_low: f64,
+    #[pyo3(get)]
+    pub bid_close: f64,
+    #[pyo3(get)]
+    pub ask_open: f64,
+    #[pyo3(get)]
+    pub ask_high: f64,
+    #[pyo3(get)]
+    pub ask_low: f64,
+    #[pyo3(get)]
+    pub ask_close: f64,
+    #[pyo3(get)]
+    pub volume: i64,
 }
 
 #[pymethods]
-impl TickBuffer {
-    #[new]
-    fn new(capacity: usize, symbol: String) -> Self {
-        TickBuffer {
-            ticks: Arc::new(RwLock::new(Vec::with_capacity(capacity))),
-            m1_candles: Arc::new(RwLock::new(Vec::with_capacity(60))),
-            m5_candles: Arc::new(RwLock::new(Vec::with_capacity(60))),
-            capacity,
-            symbol,
+impl OHLCBidAsk {
+    /// Borne haute pessimiste : plus haut des asks (prix payé à l'achat)
+    #[getter]
+    fn worst_case_high(&self) -> f64 {
+        self.ask_high
+    }
+    
+    /// Borne basse pessimiste : plus bas des bids (prix reçu à la vente)
+    #[getter]
+    fn worst_case_low(&self) -> f64 {
+        self.bid_low
+    }
+}
+
+/// Calculateur incrémental alimenté par les bougies clôturées
+#[derive(Clone)]
+enum StreamingIndicator {
+    Ichimoku(Box<IchimokuState>),
+    Ema(EMACalculator),
+    Stc(Box<STCState>),
+}
+
+impl StreamingIndicator {
+    /// Copie un IchimokuState, un EMACalculator ou un STCState Python
+    fn extract(indicator: &PyAny) -> PyResult<Self> {
+        if let Ok(state) = indicator.extract::<IchimokuState>() {
+            return Ok(StreamingIndicator::Ichimoku(Box::new(state)));
+        }
+        if let Ok(ema) = indicator.extract::<EMACalculator>() {
+            return Ok(StreamingIndicator::Ema(ema));
+        }
+        if let Ok(state) = indicator.extract::<STCState>() {
+            return Ok(StreamingIndicator::Stc(Box::new(state)));
         }
+        Err(HftError::InvalidParameter(
+            "indicateur incrémental attendu (IchimokuState, EMACalculator, STCState)".into()
+        )
+        .into())
     }
     
-    /// Ajoute un tick au buffer (thread-safe)
-    fn add_tick(&self, tick: Tick) {
-        let mut ticks = self.ticks.write();
+    /// Sorties de `update` : (tenkan, kijun, senkou_a, senkou_b, chikou)
+    /// pour Ichimoku, (ema,) et (stc,) pour l'EMA et le STC sur la clôture
+    fn update(&mut self, bar: &OHLC) -> Vec<f64> {
+        match self {
+            StreamingIndicator::Ichimoku(state) => {
+                let (tenkan, kijun, senkou_a, senkou_b, chikou) =
+                    state.update(bar.high, bar.low, bar.close);
+                vec![tenkan, kijun, senkou_a, senkou_b, chikou]
+            }
+            StreamingIndicator::Ema(ema) => vec![ema.update(bar.close)],
+            StreamingIndicator::Stc(state) => vec![state.update(bar.close)],
+        }
+    }
+}
+
+/// Indicateur enregistré sur une série, avec son état initial pour
+/// les remises à zéro
+struct RegisteredIndicator {
+    name: String,
+    initial: StreamingIndicator,
+    state: StreamingIndicator,
+    latest: Option<Vec<f64>>,
+}
+
+impl RegisteredIndicator {
+    fn reset(&mut self) {
+        self.state = self.initial.clone();
+        self.latest = None;
+    }
+}
+
+/// Série de bougies agrégées en continu pour un intervalle donné
+struct CandleSeries {
+    interval_us: i64,
+    current: Option<OHLC>,
+    /// Bougies clôturées, de la plus ancienne à la plus récente
+    candles: VecDeque<OHLC>,
+    /// Nombre maximal de bougies clôturées conservées (FIFO)
+    max_candles: Option<usize>,
+    /// Prix des ticks qui forme open / high / low / close
+    source: PriceSource,
+    /// Indicateurs mis à jour à chaque clôture, sous le verrou des bougies
+    indicators: Vec<RegisteredIndicator>,
+}
+
+impl CandleSeries {
+    fn new(interval_seconds: u64, max_candles: Option<usize>, source: PriceSource) -> Self {
+        CandleSeries {
+            interval_us: interval_seconds as i64 * 1_000_000,
+            current: None,
+            candles: VecDeque::with_capacity(60),
+            max_candles,
+            source,
+            indicators: Vec::new(),
+        }
+    }
+    
+    /// Clôture une bougie, en évinçant les plus anciennes au-delà du plafond
+    /// (O(1) par bougie, comme les ticks)
+    fn close(&mut self, bar: OHLC) {
+        for indicator in &mut self.indicators {
+            indicator.latest = Some(indicator.state.update(&bar));
+        }
+        self.candles.push_back(bar);
+        self.evict();
+    }
+    
+    fn evict(&mut self) {
+        if let Some(max) = self.max_candles {
+            while self.candles.len() > max {
+                self.candles.pop_front();
+            }
+        }
+    }
+    
+    /// Intègre un tick ; clôture la bougie en cours au changement d'intervalle.
+    /// Un saut de plusieurs intervalles ne crée pas de bougies vides.
+    /// Un tick en retard (`late`) ne modifie pas la clôture.
+    /// Avec une session, les bougies sont alignées sur le début du segment
+    /// (ouverture ou fermeture) et ne débordent jamais sur le suivant ; un
+    /// tick ignoré hors session clôture la bougie de la session terminée.
+    /// Retourne une copie de la bougie clôturée par ce tick, le cas échéant.
+    fn push_tick(
+        &mut self,
+        tick: &Tick,
+        late: bool,
+        session: Option<&TradingSession>,
+    ) -> Option<OHLC> {
+        let bar_start = match session {
+            None => bar_start(tick.timestamp, self.interval_us),
+            Some(session) => {
+                let (anchor, in_session) = session.segment(tick.timestamp);
+                if !in_session && session.drop_outside {
+                    if self.current.as_ref().is_some_and(|bar| bar.timestamp < anchor) {
+                        let closed = self.current.take()?;
+                        self.close(closed.clone());
+                        return Some(closed);
+                    }
+                    return None;
+                }
+                let elapsed = tick.timestamp - anchor;
+                anchor + elapsed - elapsed.rem_euclid(self.interval_us)
+            }
+        };
         
-        // Buffer circulaire : supprimer le plus ancien si plein
-        if ticks.len() >= self.capacity {
-            ticks.remove(0);
+        match self.current.as_mut() {
+            Some(bar) if bar.timestamp == bar_start && late => bar.absorb_late(tick, self.source),
+            Some(bar) if bar.timestamp == bar_start => bar.update(tick, self.source),
+            // Tick plus ancien que la bougie en cours : ignoré
+            Some(bar) if bar.timestamp > bar_start => {}
+            _ => {
+                let closed = self.current.take();
+                if let Some(bar) = closed.clone() {
+                    self.close(bar);
+                }
+                self.current = Some(OHLC::from_tick(bar_start, tick, self.source));
+                return closed;
+            }
         }
+        None
+    }
+    
+    /// Reconstruit la série depuis des ticks triés avec la règle des ticks
+    /// en retard de `push_tick` : chaque tick marqué d'un horizon dans
+    /// `horizons` (timestamp du dernier tick à son arrivée) est intégré dès
+    /// que la série atteint cet horizon, comme lors de la réception
+    fn rebuild(
+        &mut self,
+        ticks: &VecDeque<Tick>,
+        horizons: &VecDeque<Option<i64>>,
+        session: Option<&TradingSession>,
+    ) {
+        self.clear();
         
-        ticks.push(tick);
+        let mut late: Vec<(i64, &Tick)> = ticks
+            .iter()
+            .zip(horizons)
+            .filter_map(|(tick, horizon)| horizon.map(|h| (h, tick)))
+            .collect();
+        late.sort_by_key(|&(horizon, _)| horizon);
+        let mut pending = late.into_iter().peekable();
+        
+        for (tick, horizon) in ticks.iter().zip(horizons) {
+            if horizon.is_some() {
+                continue;
+            }
+            while let Some((_, late_tick)) = pending.next_if(|&(h, _)| h < tick.timestamp) {
+                self.push_tick(late_tick, true, session);
+            }
+            self.push_tick(tick, false, session);
+        }
+        for (_, late_tick) in pending {
+            self.push_tick(late_tick, true, session);
+        }
     }
     
-    /// Récupère les N derniers ticks
-    fn get_recent_ticks(&self, n: usize) -> Vec<Tick> {
-        let ticks = self.ticks.read();
-        let start = if ticks.len() > n { ticks.len() - n } else { 0 };
-        ticks[start..].to_vec()
+    /// Dernières N bougies clôturées
+    fn recent(&self, n: usize) -> Vec<OHLC> {
+        let (older, newer) = self.recent_slices(n);
+        [older, newer].concat()
     }
     
-    /// Construit une bougie OHLC à partir des ticks
-    fn build_ohlc_from_ticks(&self, ticks: Vec<Tick>) -> Option<OHLC> {
-        if ticks.is_empty() {
-            return None;
+    /// Vide la série en conservant l'allocation ; les indicateurs restent
+    /// enregistrés mais repartent de leur état initial
+    fn clear(&mut self) {
+        self.current = None;
+        self.candles.clear();
+        for indicator in &mut self.indicators {
+            indicator.reset();
+        }
+    }
+    
+    /// Trous entre bougies consécutives (bougie en cours incluse)
+    /// Chaque trou est (début de la première bougie manquante, début de la
+    /// dernière bougie manquante)
+    fn gaps(&self) -> Vec<(i64, i64)> {
+        let timestamps: Vec<i64> = self.candles.iter()
+            .chain(self.current.iter())
+            .map(|c| c.timestamp)
+            .collect();
+        
+        timestamps.windows(2)
+            .filter(|w| w[1] - w[0] > self.interval_us)
+            .map(|w| (w[0] + self.interval_us, w[1] - self.interval_us))
+            .collect()
+    }
+    
+    /// Dernières N bougies clôturées, en deux tranches contiguës de
+    /// l'anneau (la plus ancienne d'abord, la seconde éventuellement vide)
+    fn recent_slices(&self, n: usize) -> (&[OHLC], &[OHLC]) {
+        let start = self.candles.len().saturating_sub(n);
+        let (front, back) = self.candles.as_slices();
+        if start >= front.len() {
+            (&back[start - front.len()..], &[])
+        } else {
+            (&front[start..], back)
+        }
+    }
+}
+
+/// Transformation Heikin-Ashi d'une suite de bougies
+/// La première bougie est amorcée sur l'open brut
+fn heikin_ashi<'a>(candles: impl ExactSizeIterator<Item = &'a OHLC>) -> Vec<OHLC> {
+    let mut result: Vec<OHLC> = Vec::with_capacity(candles.len());
+    
+    for bar in candles {
+        let close = (bar.open + bar.high + bar.low + bar.close) / 4.0;
+        let open = match result.last() {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => bar.open,
+        };
+        result.push(OHLC {
+            timestamp: bar.timestamp,
+            open,
+            high: bar.high.max(open).max(close),
+            low: bar.low.min(open).min(close),
+            close,
+            volume: bar.volume,
+        });
+    }
+    
+    result
+}
+
+/// Convertit un timestamp Unix en microsecondes en DateTime UTC
+/// (epoch si hors de la plage représentable)
+fn datetime_from_us(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros(timestamp).unwrap_or_default()
+}
+
+/// Convertit un DateTime UTC en datetime.datetime Python avec tzinfo UTC
+fn to_py_datetime(py: Python<'_>, dt: DateTime<Utc>) -> PyResult<PyObject> {
+    let datetime = py.import("datetime")?;
+    let utc = datetime.getattr("timezone")?.getattr("utc")?;
+    let args = (
+        dt.year(),
+        dt.month(),
+        dt.day(),
+        dt.hour(),
+        dt.minute(),
+        dt.second(),
+        dt.timestamp_subsec_micros(),
+    );
+    let kwargs = [("tzinfo", utc)].into_py_dict(py);
+    Ok(datetime.getattr("datetime")?.call(args, Some(kwargs))?.into())
+}
+
+/// Début de la bougie contenant `timestamp`, aligné sur l'horloge UTC
+/// (minutes, heures pleines...) ; repli arithmétique si chrono refuse
+/// la troncature (intervalle plus grand que le timestamp)
+fn bar_start(timestamp: i64, interval_us: i64) -> i64 {
+    DateTime::from_timestamp_micros(timestamp)
+        .and_then(|dt| dt.duration_trunc(TimeDelta::microseconds(interval_us)).ok())
+        .map_or_else(
+            || timestamp - timestamp.rem_euclid(interval_us),
+            |dt| dt.timestamp_micros(),
+        )
+}
+
+/// Vérifie qu'une capacité de buffer est strictement positive
+fn check_capacity(capacity: usize) -> PyResult<()> {
+    if capacity == 0 {
+        return Err(HftError::InvalidParameter(
+            "la capacité doit être strictement positive".into()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Erreur pour un timeframe non enregistré
+fn unknown_timeframe(timeframe_seconds: u64) -> PyErr {
+    HftError::UnknownTimeframe(timeframe_seconds).into()
+}
+
+/// Extrait une colonne sous forme de numpy.ndarray
+fn column<'py, S, T: Element>(
+    py: Python<'py>,
+    items: impl Iterator<Item = S>,
+    field: impl Fn(S) -> T,
+) -> &'py PyArray1<T> {
+    items.map(field).collect::<Vec<T>>().into_pyarray(py)
+}
+
+/// Convertit des bougies en dict de numpy.ndarray
+/// (timestamp, open, high, low, close, volume)
+fn candles_to_dict<'py>(py: Python<'py>, candles: &[OHLC]) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("timestamp", column(py, candles.iter(), |c| c.timestamp))?;
+    dict.set_item("open", column(py, candles.iter(), |c| c.open))?;
+    dict.set_item("high", column(py, candles.iter(), |c| c.high))?;
+    dict.set_item("low", column(py, candles.iter(), |c| c.low))?;
+    dict.set_item("close", column(py, candles.iter(), |c| c.close))?;
+    dict.set_item("volume", column(py, candles.iter(), |c| c.volume))?;
+    Ok(dict)
+}
+
+/// Durée d'une journée en microsecondes
+const DAY_US: i64 = 86_400 * 1_000_000;
+
+/// Session de trading quotidienne (heures UTC, fermeture possiblement le
+/// lendemain de l'ouverture)
+#[derive(Clone, Copy, Debug)]
+struct TradingSession {
+    /// Ouverture en microsecondes depuis minuit UTC
+    open_us: i64,
+    /// Durée de la session (une journée si ouverture == fermeture)
+    duration_us: i64,
+    /// Ignore les ticks hors session au lieu de les agréger à part
+    drop_outside: bool,
+}
+
+impl TradingSession {
+    /// Début du segment contenant `timestamp` et appartenance à la session
+    /// Hors session, le segment commence à la dernière fermeture
+    fn segment(&self, timestamp: i64) -> (i64, bool) {
+        let open = timestamp - (timestamp - self.open_us).rem_euclid(DAY_US);
+        if timestamp - open < self.duration_us {
+            (open, true)
+        } else {
+            (open + self.duration_us, false)
+        }
+    }
+}
+
+/// VWAP de paires (prix mid, volume), NaN si le volume total est nul
+/// Partagé par `TickBuffer` et `TickBufferF32`
+fn vwap_of(ticks: impl Iterator<Item = (f64, i64)>) -> f64 {
+    let mut pv = 0.0;
+    let mut total_volume = 0.0;
+    for (mid, volume) in ticks {
+        pv += mid * volume as f64;
+        total_volume += volume as f64;
+    }
+    
+    if total_volume > 0.0 { pv / total_volume } else { f64::NAN }
+}
+
+/// Percentile `p` (0-100) par interpolation linéaire entre rangs
+/// Sélection (select_nth_unstable) plutôt que tri complet ; NaN si vide
+fn percentile_of(values: &mut [f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    
+    let rank = p / 100.0 * (values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let frac = rank - lower as f64;
+    
+    let (_, &mut low, above) = values.select_nth_unstable_by(lower, f64::total_cmp);
+    if frac == 0.0 {
+        return low;
+    }
+    let high = above.iter().copied().fold(f64::INFINITY, f64::min);
+    low + (high - low) * frac
+}
+
+/// Histogramme volume / prix mid (centres, volumes), `bins` > 0
+fn volume_histogram(ticks: &VecDeque<Tick>, bins: usize) -> (Vec<f64>, Vec<f64>) {
+    let (min, max) = ticks.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), t| {
+        let mid = t.mid_price();
+        (lo.min(mid), hi.max(mid))
+    });
+    if ticks.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    if max <= min {
+        let total = ticks.iter().map(|t| t.volume as f64).sum();
+        return (vec![min], vec![total]);
+    }
+    
+    let width = (max - min) / bins as f64;
+    let centers = (0..bins).map(|i| min + (i as f64 + 0.5) * width).collect();
+    let mut volumes = vec![0.0; bins];
+    for tick in ticks {
+        let bin = (((tick.mid_price() - min) / width) as usize).min(bins - 1);
+        volumes[bin] += tick.volume as f64;
+    }
+    
+    (centers, volumes)
+}
+
+/// En-tête du format binaire de checkpoint
+/// Version 2 : tailles bid / ask ajoutées ; version 3 : options du buffer
+/// Les versions 1 et 2 restent lisibles
+const CHECKPOINT_MAGIC: &[u8; 4] = b"HFTB";
+const CHECKPOINT_VERSION: u8 = 3;
+
+/// Plafond absent (bougies illimitées) dans un checkpoint
+const NO_MAX_CANDLES: u64 = u64::MAX;
+
+/// Comportement d'un buffer conservé par un checkpoint (version 3)
+/// Les callbacks, le filtre de pics et les indicateurs ne sont pas
+/// sérialisés
+#[derive(Clone, Debug)]
+struct BufferOptions {
+    strict_ordering: bool,
+    validate_ticks: bool,
+    lazy_candles: bool,
+    max_candles: Option<usize>,
+    price_source: PriceSource,
+    session: Option<TradingSession>,
+    /// Timeframes enregistrés et leur plafond de bougies
+    timeframes: Vec<(u64, Option<usize>)>,
+}
+
+impl Default for BufferOptions {
+    /// Valeurs par défaut du constructeur (checkpoints versions 1 et 2)
+    fn default() -> Self {
+        BufferOptions {
+            strict_ordering: false,
+            validate_ticks: true,
+            lazy_candles: false,
+            max_candles: None,
+            price_source: PriceSource::Mid,
+            session: None,
+            timeframes: Vec::new(),
+        }
+    }
+}
+
+impl BufferOptions {
+    /// Drapeaux u8 (bit 0 strict_ordering, bit 1 validate_ticks, bit 2
+    /// lazy_candles, bits 3-4 price_source : 0 mid, 1 bid, 2 ask),
+    /// max_candles u64, session u8 (0 aucune, 1 agrège hors
+    /// session, 2 ignore hors session) suivie de l'ouverture et de la durée
+    /// i64 en microsecondes, nombre de timeframes u32, puis pour chacun
+    /// intervalle u64 et plafond u64 (u64::MAX : illimité)
+    fn write(&self, buf: &mut Vec<u8>) {
+        let max = |m: Option<usize>| m.map_or(NO_MAX_CANDLES, |m| m as u64);
+        
+        buf.push(
+            self.strict_ordering as u8
+                | (self.validate_ticks as u8) << 1
+                | (self.lazy_candles as u8) << 2
+                | (self.price_source as u8) << 3
+        );
+        buf.extend_from_slice(&max(self.max_candles).to_le_bytes());
+        match self.session {
+            Some(session) => {
+                buf.push(1 + session.drop_outside as u8);
+                buf.extend_from_slice(&session.open_us.to_le_bytes());
+                buf.extend_from_slice(&session.duration_us.to_le_bytes());
+            }
+            None => {
+                buf.push(0);
+                buf.extend_from_slice(&[0; 16]);
+            }
+        }
+        buf.extend_from_slice(&(self.timeframes.len() as u32).to_le_bytes());
+        for &(seconds, max_candles) in &self.timeframes {
+            buf.extend_from_slice(&seconds.to_le_bytes());
+            buf.extend_from_slice(&max(max_candles).to_le_bytes());
+        }
+    }
+}
+
+/// En-tête de checkpoint lu par `ByteReader::read_checkpoint_header`
+struct CheckpointHeader {
+    version: u8,
+    symbol: String,
+    /// Absentes avant la version 3
+    options: Option<BufferOptions>,
+    count: u64,
+}
+
+/// Lecteur little-endian sur un checkpoint binaire
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> PyResult<&'a [u8]> {
+        if self.data.len() - self.pos < n {
+            return Err(HftError::InvalidCheckpoint("données tronquées").into());
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+    
+    fn read_array<const N: usize>(&mut self) -> PyResult<[u8; N]> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+    
+    fn read_u32(&mut self) -> PyResult<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+    
+    fn read_u64(&mut self) -> PyResult<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+    
+    fn read_i64(&mut self) -> PyResult<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+    
+    fn read_f64(&mut self) -> PyResult<f64> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+    
+    /// Plafond de bougies, `NO_MAX_CANDLES` pour illimité
+    fn read_max_candles(&mut self) -> PyResult<Option<usize>> {
+        match self.read_u64()? {
+            NO_MAX_CANDLES => Ok(None),
+            max => usize::try_from(max)
+                .map(Some)
+                .map_err(|_| HftError::InvalidCheckpoint("plafond de bougies illisible").into()),
         }
+    }
+    
+    /// Options du buffer, voir `BufferOptions::write`
+    fn read_options(&mut self) -> PyResult<BufferOptions> {
+        let flags = self.take(1)?[0];
+        let price_source = match flags >> 3 {
+            0 => PriceSource::Mid,
+            1 => PriceSource::Bid,
+            2 => PriceSource::Ask,
+            _ => return Err(HftError::InvalidCheckpoint("options illisibles").into()),
+        };
+        let max_candles = self.read_max_candles()?;
         
-        let mut high = f64::MIN;
-        let mut low = f64::MAX;
-        let mut total_volume = 0i64;
+        let session_kind = self.take(1)?[0];
+        let open_us = self.read_i64()?;
+        let duration_us = self.read_i64()?;
+        let valid = (0..DAY_US).contains(&open_us) && (1..=DAY_US).contains(&duration_us);
+        let session = match session_kind {
+            0 => None,
+            1 | 2 if valid => {
+                Some(TradingSession { open_us, duration_us, drop_outside: session_kind == 2 })
+            }
+            _ => return Err(HftError::InvalidCheckpoint("session illisible").into()),
+        };
         
-        for tick in &ticks {
-            let mid = tick.mid_price();
-            if mid > high { high = mid; }
-            if mid < low { low = mid; }
-            total_volume += tick.volume;
+        let timeframe_count = self.read_u32()?;
+        let mut timeframes = Vec::new();
+        for _ in 0..timeframe_count {
+            let seconds = self.read_u64()?;
+            if seconds == 0 {
+                return Err(HftError::InvalidCheckpoint("timeframe nul").into());
+            }
+            timeframes.push((seconds, self.read_max_candles()?));
         }
         
-        Some(OHLC {
-            timestamp: ticks[0].timestamp,
-            open: ticks[0].mid_price(),
-            high,
-            low,
-            close: ticks[ticks.len() - 1].mid_price(),
-            volume: total_volume,
+        Ok(BufferOptions {
+            strict_ordering: flags & 1 != 0,
+            validate_ticks: flags & 0b10 != 0,
+            lazy_candles: flags & 0b100 != 0,
+            max_candles,
+            price_source,
+            session,
+            timeframes,
         })
     }
     
-    /// Récupère les bougies M1
-    fn get_m1_candles(&self, n: usize) -> Vec<OHLC> {
-        let candles = self.m1_candles.read();
-        let start = if candles.len() > n { candles.len() - n } else { 0 };
-        candles[start..].to_vec()
+    /// En-tête de checkpoint : version, symbole, options, nombre de ticks
+    fn read_checkpoint_header(&mut self) -> PyResult<CheckpointHeader> {
+        if self.take(4)? != CHECKPOINT_MAGIC {
+            return Err(HftError::InvalidCheckpoint("format inconnu").into());
+        }
+        let version = self.take(1)?[0];
+        if version == 0 || version > CHECKPOINT_VERSION {
+            return Err(HftError::InvalidCheckpoint("format inconnu").into());
+        }
+        
+        let symbol_len = self.read_u32()? as usize;
+        let symbol = String::from_utf8(self.take(symbol_len)?.to_vec())
+            .map_err(|_| HftError::InvalidCheckpoint("symbole illisible"))?;
+        let options = if version >= 3 { Some(self.read_options()?) } else { None };
+        let count = self.read_u64()?;
+        Ok(CheckpointHeader { version, symbol, options, count })
     }
     
-    /// Récupère les bougies M5
-    fn get_m5_candles(&self, n: usize) -> Vec<OHLC> {
-        let candles = self.m5_candles.read();
-        let start = if candles.len() > n { candles.len() - n } else { 0 };
-        candles[start..].to_vec()
+    /// Enregistrement de tick : bid, ask, timestamp, volume, puis les
+    /// tailles bid / ask si `with_sizes` (0 sinon)
+    fn read_tick(&mut self, symbol: &str, with_sizes: bool) -> PyResult<Tick> {
+        let mut tick = Tick {
+            symbol: symbol.to_string(),
+            bid: self.read_f64()?,
+            ask: self.read_f64()?,
+            timestamp: self.read_i64()?,
+            volume: self.read_i64()?,
+            bid_size: 0.0,
+            ask_size: 0.0,
+        };
+        if with_sizes {
+            tick.bid_size = self.read_f64()?;
+            tick.ask_size = self.read_f64()?;
+        }
+        Ok(tick)
+    }
+}
+
+/// Taille d'un enregistrement de tick, sans et avec tailles bid / ask
+const TICK_RECORD_LEN: usize = 32;
+const TICK_RECORD_SIZES_LEN: usize = 48;
+
+/// Ticks relus par prise de verrou dans `replay_file`
+const REPLAY_CHUNK: usize = 4096;
+
+/// Contenu d'un fichier en lecture seule : projeté en mémoire (mmap) sous
+/// Unix, lu entièrement sinon ou si la projection échoue
+enum FileBytes {
+    #[cfg(unix)]
+    Mapped { ptr: *mut libc::c_void, len: usize },
+    Owned(Vec<u8>),
+}
+
+impl FileBytes {
+    fn open(path: &str) -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            
+            let file = File::open(path)?;
+            let len = file.metadata()?.len() as usize;
+            if len > 0 {
+                // SAFETY: projection privée en lecture seule d'un fichier
+                // ouvert, libérée dans Drop ; le fichier ne doit pas être
+                // tronqué pendant la relecture
+                let ptr = unsafe {
+                    libc::mmap(
+                        std::ptr::null_mut(),
+                        len,
+                        libc::PROT_READ,
+                        libc::MAP_PRIVATE,
+                        file.as_raw_fd(),
+                        0,
+                    )
+                };
+                if ptr != libc::MAP_FAILED {
+                    return Ok(FileBytes::Mapped { ptr, len });
+                }
+            }
+        }
+        std::fs::read(path).map(FileBytes::Owned)
     }
     
-    /// Nombre de ticks dans le buffer
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            // SAFETY: `len` octets projetés et lisibles jusqu'au Drop
+            #[cfg(unix)]
+            FileBytes::Mapped { ptr, len } => unsafe {
+                std::slice::from_raw_parts(*ptr as *const u8, *len)
+            },
+            FileBytes::Owned(data) => data,
+        }
+    }
+}
+
+impl Drop for FileBytes {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let FileBytes::Mapped { ptr, len } = *self {
+            // SAFETY: projection obtenue par mmap avec cette longueur
+            unsafe {
+                libc::munmap(ptr, len);
+            }
+        }
+    }
+}
+
+/// Timeframes agrégés par défaut (M1, M5)
+const DEFAULT_TIMEFRAMES: [u64; 2] = [60, 300];
+
+/// Compteurs d'ingestion, mis à jour sans verrou
+#[derive(Default)]
+struct BufferStats {
+    out_of_order: AtomicU64,
+    rejected_outliers: AtomicU64,
+}
+
+impl BufferStats {
+    fn reset(&self) {
+        self.out_of_order.store(0, Ordering::Relaxed);
+        self.rejected_outliers.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Filtre de pics : rejette un tick dont le mid s'écarte de la moyenne des
+/// `window` derniers mids de plus de `max_sigma` écarts-types
+#[derive(Clone, Copy, Debug)]
+struct OutlierFilter {
+    max_sigma: f64,
+    window: usize,
+    /// Rejets consécutifs, remis à zéro à chaque tick accepté
+    consecutive: usize,
+}
+
+impl OutlierFilter {
+    /// Vrai si le tick doit être rejeté
+    /// Inactif tant que la fenêtre n'est pas remplie. L'écart-type est
+    /// borné par le spread moyen pour ne pas rejeter tout mouvement d'un
+    /// marché figé, et `window` rejets consécutifs valent changement de
+    /// régime : le tick suivant est accepté.
+    fn rejects(&mut self, ticks: &VecDeque<Tick>, tick: &Tick) -> bool {
+        if ticks.len() < self.window || self.consecutive >= self.window {
+            self.consecutive = 0;
+            return false;
+        }
+        
+        let recent = ticks.range(ticks.len() - self.window..);
+        let n = self.window as f64;
+        let (mut sum, mut sum_spread) = (0.0, 0.0);
+        for t in recent.clone() {
+            sum += t.mid_price();
+            sum_spread += t.spread();
+        }
+        let mean = sum / n;
+        let variance = recent.map(|t| (t.mid_price() - mean).powi(2)).sum::<f64>() / n;
+        let std = variance.sqrt().max(sum_spread / n);
+        
+        if (tick.mid_price() - mean).abs() > self.max_sigma * std {
+            self.consecutive += 1;
+            true
+        } else {
+            self.consecutive = 0;
+            false
+        }
+    }
+}
+
+/// Copie cohérente de l'état d'un TickBuffer à un instant donné
+#[pyclass]
+#[derive(Clone)]
+pub struct BufferSnapshot {
+    /// Ticks récents, du plus ancien au plus récent
+    #[pyo3(get)]
+    ticks: Vec<Tick>,
+    /// Bougies clôturées par timeframe (secondes)
+    #[pyo3(get)]
+    candles: HashMap<u64, Vec<OHLC>>,
+    /// Bougie en cours par timeframe (absente si aucun tick)
+    #[pyo3(get)]
+    current_candles: HashMap<u64, OHLC>,
+}
+
+#[pymethods]
+impl BufferSnapshot {
+    /// Nombre de ticks copiés
     fn tick_count(&self) -> usize {
-        self.ticks.read().len()
+        self.ticks.len()
+    }
+}
+
+/// Buffer circulaire thread-safe pour ticks
+/// Insertion et éviction en O(1) via VecDeque
+/// Un clone partage les mêmes données (Arc)
+#[pyclass]
+#[derive(Clone)]
+pub struct TickBuffer {
+    ticks: Arc<RwLock<VecDeque<Tick>>>,
+    /// Séries de bougies indexées par intervalle en secondes
+    candles: Arc<RwLock<HashMap<u64, CandleSeries>>>,
+    stats: Arc<BufferStats>,
+    /// Session de trading appliquée à l'agrégation (aucune par défaut)
+    session: Arc<RwLock<Option<TradingSession>>>,
+    /// Filtre de pics appliqué à l'insertion (désactivé par défaut)
+    outlier_filter: Arc<RwLock<Option<OutlierFilter>>>,
+    capacity: usize,
+    /// Rejette les ticks en retard au lieu de les insérer à leur place
+    strict_ordering: bool,
+    /// Rejette les ticks incohérents (désactivable pour données synthétiques)
+    validate_ticks: bool,
+    /// Bougies construites à la lecture plutôt qu'à chaque tick
+    lazy_candles: bool,
+    /// Ticks reçus depuis la dernière construction (mode paresseux)
+    candles_stale: Arc<AtomicBool>,
+    /// Mode paresseux : pour chaque tick stocké, dans le même ordre,
+    /// timestamp du dernier tick à son arrivée s'il était en retard ;
+    /// modifié sous le verrou des ticks
+    late_horizons: Arc<Mutex<VecDeque<Option<i64>>>>,
+    /// Plafond de bougies clôturées des nouveaux timeframes
+    max_candles: Option<usize>,
+    /// Prix des ticks utilisé par toutes les séries de bougies
+    price_source: PriceSource,
+    /// Appelé avec (bougie, timeframe) à chaque clôture (aucun par défaut)
+    candle_callback: Arc<RwLock<Option<PyObject>>>,
+    #[pyo3(get)]
+    symbol: String,
+}
+
+#[pymethods]
+impl TickBuffer {
+    /// Avec `lazy_candles`, les ticks sont stockés bruts et les bougies
+    /// reconstruites à la lecture (get_candles, snapshot...) à partir des
+    /// seuls ticks alors présents : ingestion plus rapide, mais les bougies
+    /// antérieures au plus ancien tick conservé sont perdues et la session
+    /// s'applique à tout le buffer. Les ticks en retard suivent la même règle
+    /// qu'en mode immédiat (intégrés à la bougie en cours à leur arrivée sans
+    /// changer la clôture, ignorés si elle est déjà close).
+    ///
+    /// `max_candles` borne le nombre de bougies clôturées conservées par
+    /// timeframe (les plus anciennes sont évincées, comme les ticks) ;
+    /// illimité par défaut
+    ///
+    /// `price_source` choisit le prix (mid, bid ou ask) qui forme les
+    /// bougies de tous les timeframes, ticks en retard compris
+    #[new]
+    #[pyo3(signature = (
+        capacity, symbol, strict_ordering=false, validate_ticks=true, lazy_candles=false,
+        max_candles=None, price_source=PriceSource::Mid
+    ))]
+    pub fn new(
+        capacity: usize,
+        symbol: String,
+        strict_ordering: bool,
+        validate_ticks: bool,
+        lazy_candles: bool,
+        max_candles: Option<usize>,
+        price_source: PriceSource,
+    ) -> PyResult<Self> {
+        check_capacity(capacity)?;
+        
+        Ok(TickBuffer {
+            ticks: Arc::new(RwLock::new(VecDeque::with_capacity(capacity))),
+            candles: Arc::new(RwLock::new(
+                DEFAULT_TIMEFRAMES
+                    .iter()
+                    .map(|&tf| (tf, CandleSeries::new(tf, max_candles, price_source)))
+                    .collect()
+            )),
+            stats: Arc::new(BufferStats::default()),
+            session: Arc::new(RwLock::new(None)),
+            outlier_filter: Arc::new(RwLock::new(None)),
+            capacity,
+            strict_ordering,
+            validate_ticks,
+            lazy_candles,
+            candles_stale: Arc::new(AtomicBool::new(false)),
+            late_horizons: Arc::new(Mutex::new(VecDeque::new())),
+            max_candles,
+            price_source,
+            candle_callback: Arc::new(RwLock::new(None)),
+            symbol,
+        })
+    }
+    
+    /// Ajoute un tick au buffer (thread-safe)
+    /// Met à jour toutes les séries de bougies à partir du timestamp du tick
+    ///
+    /// Un tick plus ancien que le dernier stocké est rejeté en mode
+    /// `strict_ordering` (retourne False), sinon inséré à sa place.
+    /// Les timestamps égaux sont considérés dans l'ordre.
+    ///
+    /// Avec `validate_ticks`, lève ValueError si bid <= 0, ask < bid, un
+    /// prix non fini, timestamp <= 0 ou volume < 0.
+    ///
+    /// Le GIL est relâché pendant l'attente des verrous et l'insertion ;
+    /// le callback de clôture est appelé ensuite, verrous libérés.
+    pub fn add_tick(&self, py: Python<'_>, tick: Tick) -> PyResult<bool> {
+        if self.validate_ticks {
+            tick.validate()?;
+        }
+        
+        let mut closed = Vec::new();
+        let accepted = py.allow_threads(|| {
+            // Verrou des bougies toujours pris après celui des ticks
+            let mut ticks = self.ticks.write();
+            let mut candles = self.candles.write();
+            self.insert_tick(&mut ticks, &mut candles, tick, &mut closed)
+        });
+        self.notify_closed(py, closed);
+        Ok(accepted)
+    }
+    
+    /// Ajoute un lot de ticks sous une seule prise de verrou
+    /// Même sémantique que `add_tick` pour chaque tick ; le lot est validé
+    /// entièrement avant insertion. Retourne le nombre de ticks acceptés.
+    pub fn add_ticks(&self, py: Python<'_>, batch: Vec<Tick>) -> PyResult<usize> {
+        if self.validate_ticks {
+            for tick in &batch {
+                tick.validate()?;
+            }
+        }
+        
+        Ok(self.insert_batch(py, batch))
+    }
+    
+    /// Définit l'appelable invoqué avec (bougie, timeframe_seconds) pour
+    /// chaque bougie clôturée par add_tick / add_ticks, dans l'ordre des
+    /// ticks puis des timeframes croissants ; None le supprime
+    /// Une exception du callback est signalée via sys.unraisablehook sans
+    /// interrompre l'ingestion. Indisponible en mode `lazy_candles`.
+    fn set_candle_callback(&self, callback: Option<PyObject>) -> PyResult<()> {
+        if self.lazy_candles && callback.is_some() {
+            return Err(HftError::InvalidParameter(
+                "callback de clôture incompatible avec lazy_candles".into()
+            )
+            .into());
+        }
+        
+        *self.candle_callback.write() = callback;
+        Ok(())
+    }
+    
+    /// Copie les `n` derniers ticks (tous par défaut) et l'ensemble des
+    /// bougies sous les deux verrous : aucune écriture ne peut s'intercaler
+    #[pyo3(signature = (n=None))]
+    fn snapshot(&self, py: Python<'_>, n: Option<usize>) -> BufferSnapshot {
+        py.allow_threads(|| {
+            self.materialize_candles();
+            
+            // Même ordre de verrouillage que l'écriture
+            let ticks = self.ticks.read();
+            let candles = self.candles.read();
+            
+            let start = ticks.len().saturating_sub(n.unwrap_or(usize::MAX));
+            BufferSnapshot {
+                ticks: ticks.range(start..).cloned().collect(),
+                candles: candles
+                    .iter()
+                    .map(|(&tf, series)| (tf, series.candles.iter().cloned().collect()))
+                    .collect(),
+                current_candles: candles
+                    .iter()
+                    .filter_map(|(&tf, series)| series.current.clone().map(|bar| (tf, bar)))
+                    .collect(),
+            }
+        })
+    }
+    
+    /// Nombre de ticks reçus en retard depuis la création
+    fn out_of_order_count(&self) -> u64 {
+        self.stats.out_of_order.load(Ordering::Relaxed)
+    }
+    
+    /// Nombre de ticks rejetés par le filtre de pics depuis la création
+    fn rejected_outlier_count(&self) -> u64 {
+        self.stats.rejected_outliers.load(Ordering::Relaxed)
+    }
+    
+    /// Active le filtre de pics : un tick dont le mid s'écarte de plus de
+    /// `max_sigma` écarts-types de la moyenne des `window` derniers mids
+    /// est rejeté (`add_tick` retourne False)
+    /// L'écart-type est borné par le spread moyen de la fenêtre ; après
+    /// `window` rejets consécutifs, le tick suivant est accepté (le prix
+    /// a réellement changé de niveau)
+    #[pyo3(signature = (max_sigma, window=50))]
+    fn set_outlier_filter(&self, max_sigma: f64, window: usize) -> PyResult<()> {
+        if max_sigma.is_nan() || max_sigma <= 0.0 {
+            return Err(HftError::InvalidParameter(
+                "max_sigma doit être strictement positif".into()
+            )
+            .into());
+        }
+        if window < 2 {
+            return Err(HftError::InvalidParameter(
+                "la fenêtre du filtre doit contenir au moins 2 ticks".into()
+            )
+            .into());
+        }
+        
+        *self.outlier_filter.write() = Some(OutlierFilter { max_sigma, window, consecutive: 0 });
+        Ok(())
+    }
+    
+    /// Désactive le filtre de pics
+    fn clear_outlier_filter(&self) {
+        *self.outlier_filter.write() = None;
+    }
+    
+    /// Récupère les N derniers ticks
+    fn get_recent_ticks(&self, n: usize) -> Vec<Tick> {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        ticks.range(start..).cloned().collect()
+    }
+    
+    /// Dernier tick, None si le buffer est vide
+    fn last_tick(&self) -> Option<Tick> {
+        self.ticks.read().back().cloned()
+    }
+    
+    /// Prix mid du dernier tick, None si le buffer est vide
+    fn last_mid_price(&self) -> Option<f64> {
+        self.ticks.read().back().map(|t| t.mid_price())
+    }
+    
+    /// Récupère les ticks dont le timestamp est dans [start_ts, end_ts]
+    /// Les ticks étant triés, les bornes sont trouvées par recherche binaire
+    fn get_ticks_between(&self, start_ts: i64, end_ts: i64) -> Vec<Tick> {
+        if start_ts > end_ts {
+            return Vec::new();
+        }
+        
+        let ticks = self.ticks.read();
+        let start = ticks.partition_point(|t| t.timestamp < start_ts);
+        let end = ticks.partition_point(|t| t.timestamp <= end_ts);
+        ticks.range(start..end).cloned().collect()
+    }
+    
+    /// Statistiques de spread (moyenne, min, max) sur les N derniers ticks
+    /// NaN si le buffer est vide
+    fn spread_stats(&self, n: usize) -> (f64, f64, f64) {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        let count = ticks.len() - start;
+        if count == 0 {
+            return (f64::NAN, f64::NAN, f64::NAN);
+        }
+        
+        let mut sum = 0.0;
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for tick in ticks.range(start..) {
+            let spread = tick.spread();
+            sum += spread;
+            min = min.min(spread);
+            max = max.max(spread);
+        }
+        
+        (sum / count as f64, min, max)
+    }
+    
+    /// Percentile `p` (0-100) du spread sur les N derniers ticks
+    /// Interpolation linéaire entre rangs ; NaN si le buffer est vide
+    fn spread_percentile(&self, n: usize, p: f64) -> PyResult<f64> {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(HftError::InvalidParameter(
+                "le percentile doit être dans [0, 100]".into()
+            )
+            .into());
+        }
+        
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        let mut spreads: Vec<f64> = ticks.range(start..).map(|t| t.spread()).collect();
+        Ok(percentile_of(&mut spreads, p))
+    }
+    
+    /// Médiane des volumes sur les N derniers ticks (NaN si vide)
+    fn volume_median(&self, n: usize) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        let mut volumes: Vec<f64> = ticks.range(start..).map(|t| t.volume as f64).collect();
+        percentile_of(&mut volumes, 50.0)
+    }
+    
+    /// Ticks par seconde sur la fenêtre glissante `window_us` se terminant
+    /// au dernier tick
+    fn tick_rate(&self, window_us: i64) -> f64 {
+        if window_us <= 0 {
+            return f64::NAN;
+        }
+        
+        let ticks = self.ticks.read();
+        let last_ts = match ticks.back() {
+            Some(tick) => tick.timestamp,
+            None => return 0.0,
+        };
+        
+        let start = ticks.partition_point(|t| t.timestamp <= last_ts - window_us);
+        (ticks.len() - start) as f64 / (window_us as f64 / 1_000_000.0)
+    }
+    
+    /// VWAP des prix mid sur les N derniers ticks (NaN si volume nul)
+    fn vwap(&self, n: usize) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        vwap_of(ticks.range(start..).map(|t| (t.mid_price(), t.volume)))
+    }
+    
+    /// TWAP des prix mid sur [start_ts, end_ts]
+    /// Chaque mid est pondéré par sa durée de validité (jusqu'au tick
+    /// suivant, jusqu'à end_ts pour le dernier) ; le dernier tick antérieur
+    /// à start_ts, s'il existe, couvre [start_ts, premier tick]. Moyenne
+    /// simple si la durée totale est nulle, NaN si l'intervalle ne contient
+    /// aucun tick.
+    fn twap(&self, start_ts: i64, end_ts: i64) -> f64 {
+        if start_ts > end_ts {
+            return f64::NAN;
+        }
+        
+        let ticks = self.ticks.read();
+        let start = ticks.partition_point(|t| t.timestamp < start_ts);
+        let end = ticks.partition_point(|t| t.timestamp <= end_ts);
+        if start == end {
+            return f64::NAN;
+        }
+        
+        // (début de validité, mid) de chaque cotation en vigueur
+        let prior = start.checked_sub(1).map(|i| (start_ts, ticks[i].mid_price()));
+        let quotes: Vec<(i64, f64)> = prior
+            .into_iter()
+            .chain(ticks.range(start..end).map(|t| (t.timestamp, t.mid_price())))
+            .collect();
+        
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        for (i, &(from, mid)) in quotes.iter().enumerate() {
+            let until = quotes.get(i + 1).map_or(end_ts, |&(next, _)| next);
+            let duration = (until - from) as f64;
+            weighted += mid * duration;
+            total += duration;
+        }
+        
+        if total > 0.0 {
+            weighted / total
+        } else {
+            ticks.range(start..end).map(|t| t.mid_price()).sum::<f64>() / (end - start) as f64
+        }
+    }
+    
+    /// Déséquilibre acheteur/vendeur sur les N derniers ticks, dans [-1, 1]
+    /// Tick-rule : hausse du mid → achat, baisse → vente, mid inchangé →
+    /// classification précédente. Le premier tick est classé par rapport
+    /// au tick qui le précède dans le buffer, sinon non classé.
+    /// Résultat = somme(signe * volume) / somme(volume), NaN si volume nul
+    fn tick_imbalance(&self, n: usize) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        
+        let mut prev_mid = start.checked_sub(1).map(|i| ticks[i].mid_price());
+        let mut side = 0.0;
+        let mut signed = 0.0;
+        let mut total = 0.0;
+        
+        for tick in ticks.range(start..) {
+            let mid = tick.mid_price();
+            if let Some(prev) = prev_mid {
+                if mid > prev {
+                    side = 1.0;
+                } else if mid < prev {
+                    side = -1.0;
+                }
+            }
+            prev_mid = Some(mid);
+            
+            signed += side * tick.volume as f64;
+            total += tick.volume as f64;
+        }
+        
+        if total > 0.0 { signed / total } else { f64::NAN }
+    }
+    
+    /// Volatilité réalisée annualisée sur les N derniers ticks
+    /// Écart-type (échantillon) des log-rendements du mid, multiplié par
+    /// sqrt(annualization_factor) : le facteur est le nombre de rendements
+    /// par an. NaN si moins de 3 ticks, prix mid <= 0 ou facteur négatif.
+    fn realized_vol(&self, n: usize, annualization_factor: f64) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        
+        let mids: Vec<f64> = ticks.range(start..).map(|t| t.mid_price()).collect();
+        let valid_prices = mids.iter().all(|&m| m > 0.0);
+        let valid_factor = annualization_factor >= 0.0;
+        if mids.len() < 3 || !valid_prices || !valid_factor {
+            return f64::NAN;
+        }
+        
+        let returns: Vec<f64> = mids.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+        let count = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / count;
+        let variance = returns.iter().map(|r| (r - mean) * (r - mean)).sum::<f64>() / (count - 1.0);
+        
+        (variance * annualization_factor).sqrt()
+    }
+    
+    /// Drawdown maximal du mid sur les N derniers ticks, en fraction du
+    /// pic : max((pic - creux) / pic), une passe sur le plus haut courant
+    /// 0.0 si la série ne fait que monter ou compte moins de 2 ticks
+    fn max_drawdown(&self, n: usize) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        
+        let mut peak = f64::NEG_INFINITY;
+        let mut drawdown: f64 = 0.0;
+        for mid in ticks.range(start..).map(|t| t.mid_price()) {
+            peak = peak.max(mid);
+            if peak > 0.0 {
+                drawdown = drawdown.max((peak - mid) / peak);
+            }
+        }
+        drawdown
+    }
+    
+    /// Profil de volume : histogramme volume / prix mid sur tout le buffer
+    /// Retourne (centres des bins, volume par bin) sur `bins` intervalles
+    /// égaux entre le mid minimum et maximum ; le maximum tombe dans le
+    /// dernier bin. Un seul bin si tous les mids sont égaux, vide si le
+    /// buffer est vide.
+    fn volume_profile(&self, bins: usize) -> PyResult<(Vec<f64>, Vec<f64>)> {
+        if bins == 0 {
+            return Err(HftError::InvalidParameter(
+                "le nombre de bins doit être strictement positif".into()
+            )
+            .into());
+        }
+        
+        Ok(volume_histogram(&self.ticks.read(), bins))
+    }
+    
+    /// Point de contrôle : centre du bin de plus fort volume du profil
+    /// (premier en cas d'égalité), None si le buffer est vide
+    #[pyo3(signature = (bins=50))]
+    fn point_of_control(&self, bins: usize) -> PyResult<Option<f64>> {
+        let (centers, volumes) = self.volume_profile(bins)?;
+        let poc = volumes
+            .iter()
+            .enumerate()
+            .fold(None, |best: Option<(usize, f64)>, (i, &v)| match best {
+                Some((_, max)) if max >= v => best,
+                _ => Some((i, v)),
+            });
+        Ok(poc.map(|(i, _)| centers[i]))
+    }
+    
+    /// VWAP depuis la dernière ouverture de session
+    /// session_start_utc : heure d'ouverture en secondes depuis minuit UTC
+    #[pyo3(signature = (session_start_utc=0))]
+    fn session_vwap(&self, session_start_utc: u32) -> f64 {
+        let ticks = self.ticks.read();
+        let last_ts = match ticks.back() {
+            Some(tick) => tick.timestamp,
+            None => return f64::NAN,
+        };
+        
+        // Dernière ouverture de session <= dernier tick
+        let offset = session_start_utc as i64 * 1_000_000;
+        let session_open = last_ts - (last_ts - offset).rem_euclid(DAY_US);
+        
+        let start = ticks.partition_point(|t| t.timestamp < session_open);
+        vwap_of(ticks.range(start..).map(|t| (t.mid_price(), t.volume)))
+    }
+    
+    /// Construit une bougie OHLC à partir des ticks
+    /// `source` choisit le prix agrégé (mid par défaut, bid ou ask)
+    #[pyo3(signature = (ticks, source=PriceSource::Mid))]
+    fn build_ohlc_from_ticks(&self, ticks: Vec<Tick>, source: PriceSource) -> Option<OHLC> {
+        if ticks.is_empty() {
+            return None;
+        }
+        
+        let mut bar = OHLC::from_tick(ticks[0].timestamp, &ticks[0], source);
+        for tick in &ticks[1..] {
+            bar.update(tick, source);
+        }
+        
+        Some(bar)
+    }
+    
+    /// Construit des barres de volume constant à partir des ticks stockés
+    /// Une barre se clôture dès que son volume atteint `threshold` ;
+    /// l'excédent du tick est reporté sur la barre suivante
+    ///
+    /// La dernière barre, dont le volume n'a pas encore atteint le seuil,
+    /// est omise par défaut ; avec `include_partial` elle est ajoutée en fin
+    /// de liste et se reconnaît à son volume inférieur à `threshold`
+    #[pyo3(signature = (threshold, source=PriceSource::Mid, include_partial=false))]
+    fn build_volume_bars(
+        &self,
+        threshold: i64,
+        source: PriceSource,
+        include_partial: bool,
+    ) -> PyResult<Vec<OHLC>> {
+        if threshold <= 0 {
+            return Err(HftError::InvalidParameter(
+                "le seuil de volume doit être strictement positif".into()
+            )
+            .into());
+        }
+        
+        let ticks = self.ticks.read();
+        let mut bars = Vec::new();
+        let mut current: Option<OHLC> = None;
+        
+        for tick in ticks.iter() {
+            let mut remaining = tick.volume.max(0);
+            loop {
+                let bar = current.get_or_insert_with(|| {
+                    let mut bar = OHLC::from_tick(tick.timestamp, tick, source);
+                    bar.volume = 0;
+                    bar
+                });
+                let price = source.of(tick);
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                
+                let take = remaining.min(threshold - bar.volume);
+                bar.volume += take;
+                remaining -= take;
+                
+                if bar.volume >= threshold {
+                    bars.extend(current.take());
+                }
+                if remaining == 0 {
+                    break;
+                }
+            }
+        }
+        if include_partial {
+            bars.extend(current);
+        }
+        
+        Ok(bars)
+    }
+    
+    /// Enregistre un nouveau timeframe (en secondes)
+    /// Les bougies sont reconstruites à partir des ticks déjà présents
+    fn register_timeframe(&self, seconds: u64) -> PyResult<()> {
+        if seconds == 0 {
+            return Err(HftError::InvalidParameter(
+                "le timeframe doit être strictement positif".into()
+            )
+            .into());
+        }
+        
+        let ticks = self.ticks.read();
+        let mut candles = self.candles.write();
+        let session = *self.session.read();
+        candles.entry(seconds).or_insert_with(|| {
+            let mut series = CandleSeries::new(seconds, self.max_candles, self.price_source);
+            if self.lazy_candles {
+                // Construite avec les autres à la prochaine lecture
+                self.candles_stale.store(true, Ordering::Release);
+            } else {
+                for tick in ticks.iter() {
+                    series.push_tick(tick, false, session.as_ref());
+                }
+            }
+            series
+        });
+        
+        Ok(())
+    }
+    
+    /// Définit la session de trading utilisée pour l'agrégation
+    /// start_utc / end_utc : secondes depuis minuit UTC ; une fermeture
+    /// antérieure à l'ouverture désigne une session à cheval sur minuit.
+    /// La bougie en cours est clôturée à la fermeture et une nouvelle
+    /// bougie démarre à l'ouverture suivante, alignée sur celle-ci.
+    /// Hors session, les ticks sont stockés mais ignorés par les bougies
+    /// (`drop_outside`) ou agrégés dans des bougies propres à la coupure.
+    /// S'applique aux ticks reçus ensuite ; les bougies existantes sont
+    /// conservées.
+    #[pyo3(signature = (start_utc, end_utc, drop_outside=true))]
+    fn set_session(&self, start_utc: u32, end_utc: u32, drop_outside: bool) -> PyResult<()> {
+        if start_utc >= 86_400 || end_utc >= 86_400 {
+            return Err(HftError::InvalidParameter(
+                "les heures de session doivent être dans [0, 86400)".into()
+            )
+            .into());
+        }
+        
+        let open_us = start_utc as i64 * 1_000_000;
+        let close_us = end_utc as i64 * 1_000_000;
+        let duration_us = match (close_us - open_us).rem_euclid(DAY_US) {
+            0 => DAY_US,
+            d => d,
+        };
+        *self.session.write() = Some(TradingSession { open_us, duration_us, drop_outside });
+        self.candles_stale.store(self.lazy_candles, Ordering::Release);
+        Ok(())
+    }
+    
+    /// Supprime la session : agrégation continue alignée sur l'horloge UTC
+    fn clear_session(&self) {
+        *self.session.write() = None;
+        self.candles_stale.store(self.lazy_candles, Ordering::Release);
+    }
+    
+    /// Modifie le plafond de bougies clôturées d'un timeframe (None :
+    /// illimité) ; l'excédent actuel est évincé immédiatement
+    fn set_max_candles(&self, timeframe_seconds: u64, max_candles: Option<usize>) -> PyResult<()> {
+        let mut candles = self.candles.write();
+        let series = candles
+            .get_mut(&timeframe_seconds)
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))?;
+        series.max_candles = max_candles;
+        series.evict();
+        Ok(())
+    }
+    
+    /// Nombre de bougies clôturées conservées pour un timeframe
+    fn candle_count(&self, timeframe_seconds: u64) -> PyResult<usize> {
+        self.candles_read()
+            .get(&timeframe_seconds)
+            .map(|series| series.candles.len())
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))
+    }
+    
+    /// Timeframes enregistrés, triés par ordre croissant
+    fn timeframes(&self) -> Vec<u64> {
+        let mut tfs: Vec<u64> = self.candles.read().keys().copied().collect();
+        tfs.sort_unstable();
+        tfs
+    }
+    
+    /// Enregistre sous `name` une copie d'un IchimokuState, d'un
+    /// EMACalculator ou d'un STCState, mise à jour à chaque clôture de
+    /// bougie du timeframe (EMA et STC sur la clôture), de façon atomique
+    /// avec l'agrégation
+    /// L'état est d'abord alimenté par les bougies déjà clôturées ; l'objet
+    /// passé n'est pas modifié. Lève ValueError si le nom est déjà pris.
+    fn register_indicator(
+        &self,
+        name: String,
+        timeframe_seconds: u64,
+        indicator: &PyAny,
+    ) -> PyResult<()> {
+        let initial = StreamingIndicator::extract(indicator)?;
+        
+        self.materialize_candles();
+        let mut candles = self.candles.write();
+        let taken = candles
+            .values()
+            .flat_map(|series| &series.indicators)
+            .any(|registered| registered.name == name);
+        if taken {
+            return Err(HftError::InvalidParameter(format!(
+                "indicateur '{}' déjà enregistré", name
+            ))
+            .into());
+        }
+        
+        let series = candles
+            .get_mut(&timeframe_seconds)
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))?;
+        let mut state = initial.clone();
+        let latest = series.candles.iter().map(|bar| state.update(bar)).last();
+        series.indicators.push(RegisteredIndicator { name, initial, state, latest });
+        Ok(())
+    }
+    
+    /// Dernières sorties d'un indicateur enregistré, None avant la première
+    /// bougie clôturée : [tenkan, kijun, senkou_a, senkou_b, chikou] pour
+    /// Ichimoku, [ema] pour l'EMA, [stc] pour le STC
+    fn latest_indicator(&self, name: &str) -> PyResult<Option<Vec<f64>>> {
+        self.candles_read()
+            .values()
+            .flat_map(|series| &series.indicators)
+            .find(|registered| registered.name == name)
+            .map(|registered| registered.latest.clone())
+            .ok_or_else(|| {
+                HftError::InvalidParameter(format!("indicateur '{}' inconnu", name)).into()
+            })
+    }
+    
+    /// Récupère les N dernières bougies clôturées d'un timeframe
+    fn get_candles(&self, timeframe_seconds: u64, n: usize) -> PyResult<Vec<OHLC>> {
+        self.candles_read()
+            .get(&timeframe_seconds)
+            .map(|series| series.recent(n))
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))
+    }
+    
+    /// Ichimoku sur les bougies clôturées d'un timeframe
+    /// Équivalent à IchimokuCalculator.calculate sur les colonnes high, low,
+    /// close de `get_candles(timeframe_seconds, n)` (tout l'historique par
+    /// défaut), sans aller-retour par Python
+    #[pyo3(signature = (
+        timeframe_seconds, n=None, tenkan_period=9, kijun_period=26, senkou_b_period=52,
+        displace=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn ichimoku(
+        &self,
+        py: Python<'_>,
+        timeframe_seconds: u64,
+        n: Option<usize>,
+        tenkan_period: usize,
+        kijun_period: usize,
+        senkou_b_period: usize,
+        displace: bool,
+    ) -> PyResult<IchimokuArrays> {
+        let (highs, lows, closes) = self.candle_columns(timeframe_seconds, n)?;
+        ichimoku_arrays(
+            py, &highs, &lows, &closes, tenkan_period, kijun_period, senkou_b_period, displace,
+        )
+    }
+    
+    /// STC sur les clôtures des bougies clôturées d'un timeframe
+    /// Équivalent à STCCalculator.calculate sur la colonne close de
+    /// `get_candles(timeframe_seconds, n)` (tout l'historique par défaut)
+    #[pyo3(signature = (
+        timeframe_seconds, n=None, period=10, fast_length=23, slow_length=50, smoothing=0.5,
+        flat_range="carry"
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn stc(
+        &self,
+        py: Python<'_>,
+        timeframe_seconds: u64,
+        n: Option<usize>,
+        period: usize,
+        fast_length: usize,
+        slow_length: usize,
+        smoothing: f64,
+        flat_range: &str,
+    ) -> PyResult<PySeries> {
+        let flat_range = FlatRange::parse(flat_range)?;
+        let (_, _, closes) = self.candle_columns(timeframe_seconds, n)?;
+        stc_array(py, &closes, period, fast_length, slow_length, smoothing, flat_range)
+    }
+    
+    /// Rendements des clôtures des `n + 1` dernières bougies clôturées
+    /// Alignés sur ces bougies : le premier élément, sans clôture
+    /// précédente, vaut NaN. Simples (c / c_prev - 1) ou logarithmiques
+    /// (`log`) ; NaN si l'une des deux clôtures est <= 0.
+    #[pyo3(signature = (timeframe_seconds, n, log=false))]
+    fn candle_returns(&self, timeframe_seconds: u64, n: usize, log: bool) -> PyResult<Vec<f64>> {
+        let (_, _, closes) = self.candle_columns(timeframe_seconds, Some(n.saturating_add(1)))?;
+        
+        let returns = closes.iter().enumerate().map(|(i, &close)| {
+            let prev = match i.checked_sub(1) {
+                Some(j) => closes[j],
+                None => return f64::NAN,
+            };
+            if !(prev > 0.0 && close > 0.0) {
+                f64::NAN
+            } else if log {
+                (close / prev).ln()
+            } else {
+                close / prev - 1.0
+            }
+        });
+        Ok(returns.collect())
+    }
+    
+    /// Récupère les `n` dernières bougies Heikin-Ashi d'un timeframe
+    /// Calculées sur tout l'historique pour ne pas dépendre de `n`
+    fn get_heikin_ashi(&self, timeframe_seconds: u64, n: usize) -> PyResult<Vec<OHLC>> {
+        let candles = self.candles_read();
+        let series = candles
+            .get(&timeframe_seconds)
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))?;
+        
+        let mut ha = heikin_ashi(series.candles.iter());
+        let start = ha.len().saturating_sub(n);
+        Ok(ha.split_off(start))
+    }
+    
+    /// Détecte les bougies manquantes (coupure de flux) d'un timeframe
+    /// Retourne des paires (gap_start_ts, gap_end_ts) : timestamps de début
+    /// de la première et de la dernière bougie manquante
+    fn find_candle_gaps(&self, timeframe_seconds: u64) -> PyResult<Vec<(i64, i64)>> {
+        self.candles_read()
+            .get(&timeframe_seconds)
+            .map(|series| series.gaps())
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))
+    }
+    
+    /// Version numpy de `get_candles` : dict de numpy.ndarray
+    /// (timestamp, open, high, low, close, volume)
+    fn get_candles_np<'py>(
+        &self,
+        py: Python<'py>,
+        timeframe_seconds: u64,
+        n: usize,
+    ) -> PyResult<&'py PyDict> {
+        let candles = self.candles_read();
+        let series = candles
+            .get(&timeframe_seconds)
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))?;
+        candles_to_dict(py, &series.recent(n))
+    }
+    
+    /// Version numpy de `get_m1_candles`
+    fn get_m1_candles_np<'py>(&self, py: Python<'py>, n: usize) -> PyResult<&'py PyDict> {
+        self.get_candles_np(py, 60, n)
+    }
+    
+    /// Version numpy de `get_m5_candles`
+    fn get_m5_candles_np<'py>(&self, py: Python<'py>, n: usize) -> PyResult<&'py PyDict> {
+        self.get_candles_np(py, 300, n)
+    }
+    
+    /// Version numpy de `get_recent_ticks` : dict de numpy.ndarray
+    /// (timestamp, bid, ask, mid, volume)
+    fn get_recent_ticks_np<'py>(&self, py: Python<'py>, n: usize) -> PyResult<&'py PyDict> {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        let recent = || ticks.range(start..);
+        
+        let dict = PyDict::new(py);
+        dict.set_item("timestamp", column(py, recent(), |t| t.timestamp))?;
+        dict.set_item("bid", column(py, recent(), |t| t.bid))?;
+        dict.set_item("ask", column(py, recent(), |t| t.ask))?;
+        dict.set_item("mid", column(py, recent(), |t| t.mid_price()))?;
+        dict.set_item("volume", column(py, recent(), |t| t.volume))?;
+        Ok(dict)
+    }
+    
+    /// Construit une bougie bid/ask à partir des ticks
+    /// Chaque côté est agrégé sur son propre prix ; voir worst_case_high /
+    /// worst_case_low pour les bornes pessimistes (ask haut, bid bas)
+    fn build_ohlc_bidask_from_ticks(&self, ticks: Vec<Tick>) -> Option<OHLCBidAsk> {
+        let first = ticks.first()?;
+        let last = &ticks[ticks.len() - 1];
+        
+        let mut bar = OHLCBidAsk {
+            timestamp: first.timestamp,
+            bid_open: first.bid,
+            bid_high: first.bid,
+            bid_low: first.bid,
+            bid_close: last.bid,
+            ask_open: first.ask,
+            ask_high: first.ask,
+            ask_low: first.ask,
+            ask_close: last.ask,
+            volume: 0,
+        };
+        
+        for tick in &ticks {
+            bar.bid_high = bar.bid_high.max(tick.bid);
+            bar.bid_low = bar.bid_low.min(tick.bid);
+            bar.ask_high = bar.ask_high.max(tick.ask);
+            bar.ask_low = bar.ask_low.min(tick.ask);
+            bar.volume += tick.volume;
+        }
+        
+        Some(bar)
+    }
+    
+    /// Récupère les bougies M1 clôturées
+    fn get_m1_candles(&self, n: usize) -> Vec<OHLC> {
+        self.candles_read().get(&60).map(|s| s.recent(n)).unwrap_or_default()
+    }
+    
+    /// Récupère les bougies M5 clôturées
+    fn get_m5_candles(&self, n: usize) -> Vec<OHLC> {
+        self.candles_read().get(&300).map(|s| s.recent(n)).unwrap_or_default()
+    }
+    
+    /// Exporte tous les ticks en CSV (avec en-tête)
+    /// Retourne le nombre de lignes de données écrites
+    fn export_csv(&self, path: &str) -> PyResult<usize> {
+        let io_err = |e: std::io::Error| PyErr::new::<pyo3::exceptions::PyIOError, _>(
+            format!("Échec de l'export CSV vers {} : {}", path, e)
+        );
+        
+        let ticks = self.ticks.read();
+        let mut writer = BufWriter::new(File::create(path).map_err(io_err)?);
+        
+        writeln!(writer, "symbol,bid,ask,mid,spread,timestamp,volume").map_err(io_err)?;
+        for tick in ticks.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                tick.symbol,
+                tick.bid,
+                tick.ask,
+                tick.mid_price(),
+                tick.spread(),
+                tick.timestamp,
+                tick.volume
+            ).map_err(io_err)?;
+        }
+        writer.flush().map_err(io_err)?;
+        
+        Ok(ticks.len())
+    }
+    
+    /// Sérialise les ticks et les options du buffer dans un format binaire
+    /// compact (little-endian)
+    ///
+    /// Format : "HFTB", version u8, longueur du symbole u32, symbole UTF-8,
+    /// options (ordre strict, validation, mode paresseux, plafonds de
+    /// bougies, session, timeframes), nombre de ticks u64, puis pour chaque
+    /// tick bid f64, ask f64, timestamp i64, volume i64, bid_size f64,
+    /// ask_size f64 (version 3). Les ticks partagent le symbole du buffer.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        let options = self.options();
+        let ticks = self.ticks.read();
+        
+        let capacity = 64 + self.symbol.len() + ticks.len() * TICK_RECORD_SIZES_LEN;
+        let mut buf = Vec::with_capacity(capacity);
+        buf.extend_from_slice(CHECKPOINT_MAGIC);
+        buf.push(CHECKPOINT_VERSION);
+        buf.extend_from_slice(&(self.symbol.len() as u32).to_le_bytes());
+        buf.extend_from_slice(self.symbol.as_bytes());
+        options.write(&mut buf);
+        buf.extend_from_slice(&(ticks.len() as u64).to_le_bytes());
+        
+        for tick in ticks.iter() {
+            buf.extend_from_slice(&tick.bid.to_le_bytes());
+            buf.extend_from_slice(&tick.ask.to_le_bytes());
+            buf.extend_from_slice(&tick.timestamp.to_le_bytes());
+            buf.extend_from_slice(&tick.volume.to_le_bytes());
+            buf.extend_from_slice(&tick.bid_size.to_le_bytes());
+            buf.extend_from_slice(&tick.ask_size.to_le_bytes());
+        }
+        
+        PyBytes::new(py, &buf)
+    }
+    
+    /// Restaure un buffer depuis `to_bytes` avec ses options ; les bougies
+    /// sont reconstruites et les ticks réinsérés sans nouvelle validation
+    /// Sans `symbol`, le symbole du checkpoint est utilisé ; sinon il
+    /// remplace celui du buffer et de chaque tick restauré. Les checkpoints
+    /// versions 1 et 2 sont acceptés (tailles à 0 en version 1) et prennent
+    /// les options par défaut du constructeur. Des octets après le dernier
+    /// tick rendent le checkpoint invalide.
+    #[staticmethod]
+    #[pyo3(signature = (data, capacity, symbol=None))]
+    fn from_bytes(
+        py: Python<'_>,
+        data: &[u8],
+        capacity: usize,
+        symbol: Option<String>,
+    ) -> PyResult<Self> {
+        let mut reader = ByteReader { data, pos: 0 };
+        let header = reader.read_checkpoint_header()?;
+        let options = header.options.unwrap_or_default();
+        let symbol = symbol.unwrap_or_else(|| header.symbol.clone());
+        
+        let buffer = TickBuffer::new(
+            capacity,
+            symbol.clone(),
+            options.strict_ordering,
+            options.validate_ticks,
+            options.lazy_candles,
+            options.max_candles,
+            options.price_source,
+        )?;
+        *buffer.session.write() = options.session;
+        {
+            let mut candles = buffer.candles.write();
+            for (seconds, max_candles) in options.timeframes {
+                candles
+                    .entry(seconds)
+                    .or_insert_with(|| {
+                        CandleSeries::new(seconds, max_candles, options.price_source)
+                    })
+                    .max_candles = max_candles;
+            }
+        }
+        
+        let mut ticks = Vec::with_capacity(header.count.min(capacity as u64) as usize);
+        for _ in 0..header.count {
+            ticks.push(reader.read_tick(&symbol, header.version >= 2)?);
+        }
+        if reader.pos != data.len() {
+            return Err(HftError::InvalidCheckpoint("octets en trop après les ticks").into());
+        }
+        buffer.insert_batch(py, ticks);
+        
+        Ok(buffer)
+    }
+    
+    /// Relit un fichier binaire de ticks par lots, par le même chemin que
+    /// `add_ticks` (validation, ordre, bougies, indicateurs), GIL relâché ;
+    /// le fichier est projeté en mémoire (mmap) sous Unix
+    ///
+    /// Formats (little-endian, enregistrements de taille fixe) :
+    /// - "raw" : suite d'enregistrements de 32 octets bid f64, ask f64,
+    ///   timestamp i64, volume i64, sans en-tête
+    /// - "raw_sizes" : idem suivi de bid_size f64, ask_size f64 (48 octets)
+    /// - "checkpoint" : fichier écrit depuis `to_bytes` (versions 1 à 3),
+    ///   dont les options sont ignorées
+    ///
+    /// Les ticks prennent le symbole du buffer. Avec `validate_ticks`, tout
+    /// le fichier est validé avant insertion. Le verrou est relâché tous
+    /// les 4096 ticks ; les callbacks de clôture sont appelés à la fin.
+    /// Retourne le nombre de ticks acceptés.
+    fn replay_file(&self, py: Python<'_>, path: &str, format: &str) -> PyResult<usize> {
+        let mut closed = Vec::new();
+        let accepted = py.allow_threads(|| -> PyResult<usize> {
+            let file = FileBytes::open(path).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(
+                    format!("Échec de la lecture de {} : {}", path, e)
+                )
+            })?;
+            let data = file.as_slice();
+            
+            // Position du premier enregistrement, tailles bid / ask présentes
+            let (start, with_sizes) = match format {
+                "raw" => (0, false),
+                "raw_sizes" => (0, true),
+                "checkpoint" => {
+                    let mut reader = ByteReader { data, pos: 0 };
+                    let CheckpointHeader { version, count, .. } =
+                        reader.read_checkpoint_header()?;
+                    let record_len = if version >= 2 {
+                        TICK_RECORD_SIZES_LEN
+                    } else {
+                        TICK_RECORD_LEN
+                    };
+                    let expected = count.checked_mul(record_len as u64);
+                    if expected != Some((data.len() - reader.pos) as u64) {
+                        return Err(
+                            HftError::InvalidCheckpoint("nombre de ticks incohérent").into()
+                        );
+                    }
+                    (reader.pos, version >= 2)
+                }
+                _ => {
+                    return Err(HftError::InvalidParameter(format!(
+                        "format de relecture inconnu '{}' (raw, raw_sizes, checkpoint)", format
+                    ))
+                    .into());
+                }
+            };
+            
+            let record_len = if with_sizes { TICK_RECORD_SIZES_LEN } else { TICK_RECORD_LEN };
+            let records = &data[start..];
+            if records.len() % record_len != 0 {
+                return Err(HftError::InvalidParameter(format!(
+                    "{} : taille non multiple de {} octets", path, record_len
+                ))
+                .into());
+            }
+            let read_tick = |record: &[u8]| {
+                ByteReader { data: record, pos: 0 }.read_tick(&self.symbol, with_sizes)
+            };
+            
+            if self.validate_ticks {
+                for record in records.chunks_exact(record_len) {
+                    read_tick(record)?.validate()?;
+                }
+            }
+            
+            let mut accepted = 0;
+            for chunk in records.chunks(record_len * REPLAY_CHUNK) {
+                let mut ticks = self.ticks.write();
+                let mut candles = self.candles.write();
+                for record in chunk.chunks_exact(record_len) {
+                    if self.insert_tick(&mut ticks, &mut candles, read_tick(record)?, &mut closed) {
+                        accepted += 1;
+                    }
+                }
+            }
+            Ok(accepted)
+        })?;
+        
+        self.notify_closed(py, closed);
+        Ok(accepted)
+    }
+    
+    /// Vide les ticks et toutes les séries de bougies
+    /// Les allocations et les timeframes enregistrés sont conservés
+    fn clear(&self) {
+        let mut ticks = self.ticks.write();
+        let mut candles = self.candles.write();
+        
+        ticks.clear();
+        self.late_horizons.lock().clear();
+        for series in candles.values_mut() {
+            series.clear();
+        }
+    }
+    
+    /// Comme `clear`, et remet aussi les compteurs à zéro
+    fn reset(&self) {
+        self.clear();
+        self.stats.reset();
+    }
+    
+    /// Mémoire occupée par les ticks, en octets (allocations des symboles
+    /// comprises, bougies exclues)
+    pub fn memory_bytes(&self) -> usize {
+        let ticks = self.ticks.read();
+        let symbols: usize = ticks.iter().map(|t| t.symbol.capacity()).sum();
+        ticks.capacity() * std::mem::size_of::<Tick>() + symbols
+    }
+    
+    /// Nombre de ticks dans le buffer
+    pub fn tick_count(&self) -> usize {
+        self.ticks.read().len()
+    }
+}
+
+impl TickBuffer {
+    /// Insère un lot sous une seule prise de verrou, sans validation
+    /// Retourne le nombre de ticks acceptés
+    fn insert_batch(&self, py: Python<'_>, batch: Vec<Tick>) -> usize {
+        let mut closed = Vec::new();
+        let accepted = py.allow_threads(|| {
+            let mut ticks = self.ticks.write();
+            let mut candles = self.candles.write();
+            
+            let mut accepted = 0;
+            for tick in batch {
+                if self.insert_tick(&mut ticks, &mut candles, tick, &mut closed) {
+                    accepted += 1;
+                }
+            }
+            accepted
+        });
+        self.notify_closed(py, closed);
+        accepted
+    }
+    
+    /// Options conservées par `to_bytes`
+    fn options(&self) -> BufferOptions {
+        let mut timeframes: Vec<(u64, Option<usize>)> = self
+            .candles
+            .read()
+            .iter()
+            .map(|(&seconds, series)| (seconds, series.max_candles))
+            .collect();
+        timeframes.sort_unstable();
+        
+        BufferOptions {
+            strict_ordering: self.strict_ordering,
+            validate_ticks: self.validate_ticks,
+            lazy_candles: self.lazy_candles,
+            max_candles: self.max_candles,
+            price_source: self.price_source,
+            session: *self.session.read(),
+            timeframes,
+        }
+    }
+    
+    /// Reconstruit les bougies depuis les ticks si le mode paresseux a
+    /// laissé passer des ticks ; à appeler sans verrou tenu
+    fn materialize_candles(&self) {
+        if !self.lazy_candles || !self.candles_stale.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        
+        let ticks = self.ticks.read();
+        let horizons = self.late_horizons.lock();
+        let mut candles = self.candles.write();
+        let session = *self.session.read();
+        for series in candles.values_mut() {
+            series.rebuild(&ticks, &horizons, session.as_ref());
+        }
+    }
+    
+    /// Verrou en lecture sur des bougies à jour
+    fn candles_read(&self) -> RwLockReadGuard<'_, HashMap<u64, CandleSeries>> {
+        self.materialize_candles();
+        self.candles.read()
+    }
+    
+    /// Colonnes (highs, lows, closes) des `n` dernières bougies clôturées
+    fn candle_columns(
+        &self,
+        timeframe_seconds: u64,
+        n: Option<usize>,
+    ) -> PyResult<(Vec<f64>, Vec<f64>, Vec<f64>)> {
+        let candles = self.candles_read();
+        let series = candles
+            .get(&timeframe_seconds)
+            .ok_or_else(|| unknown_timeframe(timeframe_seconds))?;
+        
+        let (older, newer) = series.recent_slices(n.unwrap_or(usize::MAX));
+        let bars = || older.iter().chain(newer);
+        Ok((
+            bars().map(|c| c.high).collect(),
+            bars().map(|c| c.low).collect(),
+            bars().map(|c| c.close).collect(),
+        ))
+    }
+    
+    /// Appelle le callback de clôture pour chaque bougie, GIL tenu et
+    /// verrous libérés (le callback peut relire le buffer)
+    fn notify_closed(&self, py: Python<'_>, closed: Vec<(u64, OHLC)>) {
+        if closed.is_empty() {
+            return;
+        }
+        let callback = match self.candle_callback.read().clone() {
+            Some(callback) => callback,
+            None => return,
+        };
+        
+        for (timeframe, bar) in closed {
+            if let Err(err) = callback.call1(py, (bar, timeframe)) {
+                err.write_unraisable(py, Some(callback.as_ref(py)));
+            }
+        }
+    }
+    
+    /// Insère un tick déjà validé, verrous tenus par l'appelant
+    /// Retourne false si le tick est rejeté (retard en mode strict, pic) ;
+    /// les bougies clôturées sont ajoutées à `closed`
+    fn insert_tick(
+        &self,
+        ticks: &mut VecDeque<Tick>,
+        candles: &mut HashMap<u64, CandleSeries>,
+        tick: Tick,
+        closed: &mut Vec<(u64, OHLC)>,
+    ) -> bool {
+        if let Some(filter) = self.outlier_filter.write().as_mut() {
+            if filter.rejects(ticks, &tick) {
+                self.stats.rejected_outliers.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        }
+        
+        let last_timestamp = ticks.back().map(|last| last.timestamp);
+        let late = last_timestamp.is_some_and(|last| tick.timestamp < last);
+        if late {
+            self.stats.out_of_order.fetch_add(1, Ordering::Relaxed);
+            if self.strict_ordering {
+                return false;
+            }
+        }
+        
+        // Agrégation multi-timeframe, différée en mode paresseux
+        if self.lazy_candles {
+            self.candles_stale.store(true, Ordering::Release);
+        } else {
+            let session = *self.session.read();
+            let start = closed.len();
+            for (&timeframe, series) in candles.iter_mut() {
+                if let Some(bar) = series.push_tick(&tick, late, session.as_ref()) {
+                    closed.push((timeframe, bar));
+                }
+            }
+            closed[start..].sort_unstable_by_key(|&(timeframe, _)| timeframe);
+        }
+        
+        // Horizons tenus à côté des ticks pour la reconstruction paresseuse
+        let mut horizons = self.lazy_candles.then(|| self.late_horizons.lock());
+        
+        // Buffer circulaire : supprimer le plus ancien si plein
+        if ticks.len() >= self.capacity {
+            ticks.pop_front();
+            if let Some(h) = horizons.as_mut() {
+                h.pop_front();
+            }
+        }
+        
+        if late {
+            let pos = ticks.partition_point(|t| t.timestamp <= tick.timestamp);
+            ticks.insert(pos, tick);
+            if let Some(h) = horizons.as_mut() {
+                h.insert(pos, last_timestamp);
+            }
+        } else {
+            ticks.push_back(tick);
+            if let Some(h) = horizons.as_mut() {
+                h.push_back(None);
+            }
+        }
+        
+        true
+    }
+}
+
+/// Tick compact : prix en f32, symbole porté par le buffer, tailles
+/// bid / ask non conservées (24 octets)
+#[derive(Clone, Copy, Debug)]
+struct CompactTick {
+    bid: f32,
+    ask: f32,
+    timestamp: i64,
+    volume: i64,
+}
+
+impl CompactTick {
+    fn from_tick(tick: &Tick) -> Self {
+        CompactTick {
+            bid: tick.bid as f32,
+            ask: tick.ask as f32,
+            timestamp: tick.timestamp,
+            volume: tick.volume,
+        }
+    }
+    
+    fn mid_price(&self) -> f64 {
+        (self.bid as f64 + self.ask as f64) / 2.0
+    }
+    
+    fn to_tick(self, symbol: &str) -> Tick {
+        Tick {
+            symbol: symbol.to_string(),
+            bid: self.bid as f64,
+            ask: self.ask as f64,
+            timestamp: self.timestamp,
+            volume: self.volume,
+            bid_size: 0.0,
+            ask_size: 0.0,
+        }
+    }
+}
+
+/// Buffer circulaire de ticks à prix stockés en f32
+/// Environ 24 octets par tick contre 72 plus l'allocation du symbole pour
+/// TickBuffer ; pas d'agrégation en bougies. Les prix sont restitués en
+/// f64 avec la précision f32 (~1e-4 autour de 2000, sous le pip de l'or).
+/// Un clone partage les mêmes données (Arc)
+#[pyclass]
+#[derive(Clone)]
+pub struct TickBufferF32 {
+    ticks: Arc<RwLock<VecDeque<CompactTick>>>,
+    capacity: usize,
+    #[pyo3(get)]
+    symbol: String,
+}
+
+#[pymethods]
+impl TickBufferF32 {
+    #[new]
+    pub fn new(capacity: usize, symbol: String) -> PyResult<Self> {
+        check_capacity(capacity)?;
+        
+        Ok(TickBufferF32 {
+            ticks: Arc::new(RwLock::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            symbol,
+        })
+    }
+    
+    /// Ajoute un tick validé ; un tick en retard est inséré à sa place
+    /// Mêmes rejets que `TickBuffer.add_tick` avec `validate_ticks`
+    fn add_tick(&self, tick: Tick) -> PyResult<()> {
+        tick.validate()?;
+        self.insert(CompactTick::from_tick(&tick));
+        Ok(())
+    }
+    
+    /// Ajoute un lot de ticks, validé entièrement avant insertion
+    pub fn add_ticks(&self, py: Python<'_>, batch: Vec<Tick>) -> PyResult<usize> {
+        for tick in &batch {
+            tick.validate()?;
+        }
+        
+        let count = batch.len();
+        py.allow_threads(|| {
+            for tick in &batch {
+                self.insert(CompactTick::from_tick(tick));
+            }
+        });
+        Ok(count)
+    }
+    
+    /// Récupère les N derniers ticks (prix convertis en f64)
+    fn get_recent_ticks(&self, n: usize) -> Vec<Tick> {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        ticks.range(start..).map(|t| t.to_tick(&self.symbol)).collect()
+    }
+    
+    /// Récupère les ticks dont le timestamp est dans [start_ts, end_ts]
+    fn get_ticks_between(&self, start_ts: i64, end_ts: i64) -> Vec<Tick> {
+        if start_ts > end_ts {
+            return Vec::new();
+        }
+        
+        let ticks = self.ticks.read();
+        let start = ticks.partition_point(|t| t.timestamp < start_ts);
+        let end = ticks.partition_point(|t| t.timestamp <= end_ts);
+        ticks.range(start..end).map(|t| t.to_tick(&self.symbol)).collect()
+    }
+    
+    /// Dernier tick, None si le buffer est vide
+    fn last_tick(&self) -> Option<Tick> {
+        self.ticks.read().back().map(|t| t.to_tick(&self.symbol))
+    }
+    
+    /// Prix mid du dernier tick, None si le buffer est vide
+    fn last_mid_price(&self) -> Option<f64> {
+        self.ticks.read().back().map(|t| t.mid_price())
+    }
+    
+    /// VWAP des prix mid sur les N derniers ticks (NaN si volume nul)
+    fn vwap(&self, n: usize) -> f64 {
+        let ticks = self.ticks.read();
+        let start = ticks.len().saturating_sub(n);
+        vwap_of(ticks.range(start..).map(|t| (t.mid_price(), t.volume)))
+    }
+    
+    /// Mémoire réservée pour les ticks, en octets
+    pub fn memory_bytes(&self) -> usize {
+        self.ticks.read().capacity() * std::mem::size_of::<CompactTick>()
+    }
+    
+    /// Vide le buffer en conservant l'allocation
+    fn clear(&self) {
+        self.ticks.write().clear();
+    }
+    
+    /// Nombre de ticks dans le buffer
+    pub fn tick_count(&self) -> usize {
+        self.ticks.read().len()
+    }
+}
+
+impl TickBufferF32 {
+    fn insert(&self, tick: CompactTick) {
+        let mut ticks = self.ticks.write();
+        if ticks.len() >= self.capacity {
+            ticks.pop_front();
+        }
+        
+        if ticks.back().is_some_and(|last| tick.timestamp < last.timestamp) {
+            let pos = ticks.partition_point(|t| t.timestamp <= tick.timestamp);
+            ticks.insert(pos, tick);
+        } else {
+            ticks.push_back(tick);
+        }
+    }
+}
+
+/// Ensemble de TickBuffer indexés par symbole
+/// Les ticks sont routés selon `tick.symbol` ; un buffer est créé avec
+/// `default_capacity` à la première apparition d'un symbole
+#[pyclass]
+pub struct MultiSymbolBuffer {
+    buffers: RwLock<HashMap<String, TickBuffer>>,
+    #[pyo3(get)]
+    default_capacity: usize,
+}
+
+#[pymethods]
+impl MultiSymbolBuffer {
+    #[new]
+    #[pyo3(signature = (default_capacity=10000))]
+    fn new(default_capacity: usize) -> PyResult<Self> {
+        check_capacity(default_capacity)?;
+        
+        Ok(MultiSymbolBuffer {
+            buffers: RwLock::new(HashMap::new()),
+            default_capacity,
+        })
+    }
+    
+    /// Ajoute un tick dans le buffer de son symbole
+    /// Même sémantique que `TickBuffer.add_tick`
+    fn add_tick(&self, py: Python<'_>, tick: Tick) -> PyResult<bool> {
+        self.buffer_for(&tick.symbol)?.add_tick(py, tick)
+    }
+    
+    /// Buffer d'un symbole (partagé, pas une copie), None si inconnu
+    fn get_buffer(&self, symbol: &str) -> Option<TickBuffer> {
+        self.buffers.read().get(symbol).cloned()
+    }
+    
+    /// Symboles connus, triés
+    fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.buffers.read().keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+impl MultiSymbolBuffer {
+    /// Buffer du symbole, créé au besoin
+    /// Le verrou de la table est relâché avant l'insertion du tick
+    fn buffer_for(&self, symbol: &str) -> PyResult<TickBuffer> {
+        if let Some(buffer) = self.buffers.read().get(symbol) {
+            return Ok(buffer.clone());
+        }
+        
+        let mut buffers = self.buffers.write();
+        if let Some(buffer) = buffers.get(symbol) {
+            return Ok(buffer.clone());
+        }
+        let buffer = TickBuffer::new(
+            self.default_capacity, symbol.to_string(), false, true, false, None, PriceSource::Mid
+        )?;
+        buffers.insert(symbol.to_string(), buffer.clone());
+        Ok(buffer)
+    }
+}
+
+/// Agrège des bougies d'un timeframe inférieur vers un timeframe supérieur
+/// (ex. M1 → M5) : open de la première, close de la dernière, extrêmes et
+/// volume cumulés. `to_seconds` doit être un multiple de `from_seconds` ;
+/// les bougies sont supposées triées par timestamp
+#[pyfunction]
+pub fn resample_ohlc(
+    bars: Vec<OHLC>,
+    from_seconds: u64,
+    to_seconds: u64,
+) -> PyResult<Vec<OHLC>> {
+    if from_seconds == 0 || to_seconds == 0 || !to_seconds.is_multiple_of(from_seconds) {
+        return Err(HftError::InvalidParameter(format!(
+            "{}s n'est pas un multiple de {}s", to_seconds, from_seconds
+        )).into());
+    }
+    
+    let interval_us = to_seconds as i64 * 1_000_000;
+    let mut result: Vec<OHLC> = Vec::new();
+    
+    for bar in bars {
+        let bar_start = bar_start(bar.timestamp, interval_us);
+        match result.last_mut() {
+            Some(current) if current.timestamp == bar_start => {
+                current.high = current.high.max(bar.high);
+                current.low = current.low.min(bar.low);
+                current.close = bar.close;
+                current.volume += bar.volume;
+            }
+            _ => result.push(OHLC { timestamp: bar_start, ..bar }),
+        }
+    }
+    
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Tick XAUUSD de spread 0.2 autour de `mid`, timestamp en secondes
+    fn tick_at(seconds: i64, mid: f64, volume: i64) -> Tick {
+        Tick {
+            symbol: "XAUUSD".into(),
+            bid: mid - 0.1,
+            ask: mid + 0.1,
+            timestamp: 1_700_000_000_000_000 + seconds * 1_000_000,
+            volume,
+            bid_size: 0.0,
+            ask_size: 0.0,
+        }
+    }
+    
+    fn buffer(capacity: usize) -> TickBuffer {
+        TickBuffer::new(capacity, "XAUUSD".into(), false, true, false, None, PriceSource::Mid)
+            .unwrap()
+    }
+    
+    /// Attribut `code` d'une HftException
+    fn error_code(err: PyErr) -> String {
+        Python::with_gil(|py| err.value(py).getattr("code").unwrap().extract().unwrap())
+    }
+    
+    #[test]
+    fn ring_buffer_keeps_last_capacity_ticks_in_order() {
+        Python::with_gil(|py| {
+            let buffer = buffer(1_000);
+            for i in 0..10_000 {
+                buffer.add_tick(py, tick_at(i, 2000.0, 1)).unwrap();
+            }
+            
+            assert_eq!(buffer.tick_count(), 1_000);
+            let ticks = buffer.get_recent_ticks(usize::MAX);
+            assert_eq!(ticks[0].timestamp, tick_at(9_000, 2000.0, 1).timestamp);
+            assert_eq!(ticks[999].timestamp, tick_at(9_999, 2000.0, 1).timestamp);
+            assert!(ticks.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        });
+    }
+    
+    #[test]
+    fn validation_rejects_each_invalid_field() {
+        let valid = tick_at(0, 2000.0, 1);
+        type Corrupt = fn(&mut Tick);
+        let cases: [(&str, Corrupt); 9] = [
+            ("bid nul", |t| t.bid = 0.0),
+            ("bid négatif", |t| t.bid = -5.0),
+            ("bid NaN", |t| t.bid = f64::NAN),
+            ("bid infini", |t| t.bid = f64::INFINITY),
+            ("ask sous le bid", |t| t.ask = t.bid - 0.01),
+            ("ask infini", |t| t.ask = f64::INFINITY),
+            ("timestamp nul", |t| t.timestamp = 0),
+            ("volume négatif", |t| t.volume = -1),
+            ("taille négative", |t| t.ask_size = -1.0),
+        ];
+        
+        Python::with_gil(|py| {
+            let buffer = buffer(10);
+            for (name, corrupt) in cases {
+                let mut tick = valid.clone();
+                corrupt(&mut tick);
+                let err = buffer.add_tick(py, tick).expect_err(name);
+                assert_eq!(error_code(err), "INVALID_TICK", "{}", name);
+            }
+            assert_eq!(buffer.tick_count(), 0);
+            
+            // Le lot est refusé entièrement
+            let mut bad = valid.clone();
+            bad.bid = -1.0;
+            assert!(buffer.add_ticks(py, vec![valid.clone(), bad]).is_err());
+            assert_eq!(buffer.tick_count(), 0);
+            
+            assert!(buffer.add_tick(py, valid.clone()).unwrap());
+        });
+    }
+    
+    #[test]
+    fn validation_can_be_disabled_for_synthetic_data() {
+        Python::with_gil(|py| {
+            let buffer =
+                TickBuffer::new(10, "X".into(), false, false, false, None, PriceSource::Mid)
+                    .unwrap();
+            let mut tick = tick_at(0, 2000.0, 1);
+            tick.bid = -5.0;
+            assert!(buffer.add_tick(py, tick).unwrap());
+            assert_eq!(buffer.tick_count(), 1);
+        });
+    }
+    
+    #[test]
+    fn zero_capacity_is_rejected() {
+        let err = TickBuffer::new(0, "X".into(), false, true, false, None, PriceSource::Mid)
+            .err()
+            .unwrap();
+        assert_eq!(error_code(err), "INVALID_PARAMETER");
+        assert!(TickBufferF32::new(0, "X".into()).is_err());
+        assert!(MultiSymbolBuffer::new(0).is_err());
+    }
+    
+    #[test]
+    fn checkpoint_round_trip_restores_every_field_and_option() {
+        Python::with_gil(|py| {
+            let buffer = TickBuffer::new(
+                2_000, "XAUUSD".into(), true, false, false, Some(50), PriceSource::Bid
+            ).unwrap();
+            buffer.register_timeframe(120).unwrap();
+            buffer.set_max_candles(60, Some(10)).unwrap();
+            buffer.set_session(3_600, 7_200, false).unwrap();
+            let ticks: Vec<Tick> = (0..1_000)
+                .map(|i| {
+                    let mut tick = tick_at(i, 2000.0 + (i as f64 / 7.0).sin(), i % 5);
+                    tick.bid_size = i as f64 * 0.5;
+                    tick.ask_size = i as f64 * 0.25;
+                    tick
+                })
+                .collect();
+            assert_eq!(buffer.add_ticks(py, ticks.clone()).unwrap(), 1_000);
+            
+            let data = buffer.to_bytes(py).as_bytes().to_vec();
+            let restored = TickBuffer::from_bytes(py, &data, 2_000, None).unwrap();
+            
+            assert_eq!(restored.symbol, "XAUUSD");
+            let restored_ticks = restored.get_recent_ticks(usize::MAX);
+            assert_eq!(restored_ticks.len(), 1_000);
+            for (original, copy) in ticks.iter().zip(&restored_ticks) {
+                assert_eq!(original.symbol, copy.symbol);
+                assert_eq!(original.bid.to_bits(), copy.bid.to_bits());
+                assert_eq!(original.ask.to_bits(), copy.ask.to_bits());
+                assert_eq!(original.timestamp, copy.timestamp);
+                assert_eq!(original.volume, copy.volume);
+                assert_eq!(original.bid_size.to_bits(), copy.bid_size.to_bits());
+                assert_eq!(original.ask_size.to_bits(), copy.ask_size.to_bits());
+            }
+            
+            assert!(restored.strict_ordering);
+            assert!(!restored.validate_ticks);
+            assert!(!restored.lazy_candles);
+            assert_eq!(restored.max_candles, Some(50));
+            assert_eq!(restored.price_source, PriceSource::Bid);
+            assert_eq!(restored.timeframes(), buffer.timeframes());
+            assert_eq!(restored.candles.read()[&60].max_candles, Some(10));
+            assert_eq!(restored.candles.read()[&120].max_candles, Some(50));
+            let session = restored.session.read().unwrap();
+            assert_eq!((session.open_us, session.duration_us), (3_600_000_000, 3_600_000_000));
+            assert!(!session.drop_outside);
+            for tf in buffer.timeframes() {
+                assert_eq!(restored.candle_count(tf).unwrap(), buffer.candle_count(tf).unwrap());
+            }
+            
+            // Ordre strict conservé : un tick en retard reste refusé
+            assert!(!restored.add_tick(py, tick_at(0, 2000.0, 1)).unwrap());
+            
+            // Symbole remplacé : buffer et ticks restaurés le portent
+            let renamed = TickBuffer::from_bytes(py, &data, 2_000, Some("GOLD".into())).unwrap();
+            assert_eq!(renamed.symbol, "GOLD");
+            let renamed_ticks = renamed.get_recent_ticks(usize::MAX);
+            assert_eq!(renamed_ticks.len(), 1_000);
+            assert!(renamed_ticks.iter().all(|tick| tick.symbol == "GOLD"));
+        });
+    }
+    
+    #[test]
+    fn truncated_checkpoint_is_rejected() {
+        Python::with_gil(|py| {
+            let buffer = buffer(10);
+            buffer.add_tick(py, tick_at(0, 2000.0, 1)).unwrap();
+            let data = buffer.to_bytes(py).as_bytes().to_vec();
+            
+            let err = TickBuffer::from_bytes(py, &data[..data.len() - 1], 10, None).err().unwrap();
+            assert_eq!(error_code(err), "INVALID_CHECKPOINT");
+        });
+    }
+    
+    #[test]
+    fn trailing_bytes_and_overflowing_counts_are_rejected() {
+        Python::with_gil(|py| {
+            let source = buffer(10);
+            source.add_tick(py, tick_at(0, 2000.0, 1)).unwrap();
+            let mut data = source.to_bytes(py).as_bytes().to_vec();
+            data.push(0);
+            let err = TickBuffer::from_bytes(py, &data, 10, None).err().unwrap();
+            assert_eq!(error_code(err), "INVALID_CHECKPOINT");
+            
+            // Checkpoint vide dont le nombre de ticks (8 derniers octets)
+            // déborde une fois multiplié par la taille d'enregistrement
+            let mut data = buffer(1).to_bytes(py).as_bytes().to_vec();
+            let count_at = data.len() - 8;
+            data[count_at..].copy_from_slice(&(u64::MAX / 8).to_le_bytes());
+            let path = replay_path("overflow");
+            std::fs::write(&path, &data).unwrap();
+            let err = source.replay_file(py, &path, "checkpoint").err().unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(error_code(err), "INVALID_CHECKPOINT");
+        });
+    }
+    
+    /// Fichier temporaire propre au processus
+    fn replay_path(name: &str) -> String {
+        let file = format!("hft_replay_{}_{}.bin", std::process::id(), name);
+        std::env::temp_dir().join(file).to_string_lossy().into_owned()
+    }
+    
+    #[test]
+    fn replaying_a_file_builds_the_same_candles_as_add_tick() {
+        Python::with_gil(|py| {
+            // 600 ticks à 1 s d'intervalle à partir de :20 : 11 minutes
+            // entamées, dont 10 closes en M1 et 2 en M5
+            let ticks: Vec<Tick> = (0..600).map(|i| tick_at(i, 2000.0 + i as f64, 1)).collect();
+            let mut raw = Vec::new();
+            for tick in &ticks {
+                raw.extend_from_slice(&tick.bid.to_le_bytes());
+                raw.extend_from_slice(&tick.ask.to_le_bytes());
+                raw.extend_from_slice(&tick.timestamp.to_le_bytes());
+                raw.extend_from_slice(&tick.volume.to_le_bytes());
+            }
+            let path = replay_path("raw");
+            std::fs::write(&path, &raw).unwrap();
+            let replayed = buffer(1_000);
+            assert_eq!(replayed.replay_file(py, &path, "raw").unwrap(), 600);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(replayed.candle_count(60).unwrap(), 10);
+            assert_eq!(replayed.candle_count(300).unwrap(), 2);
+            
+            let direct = buffer(1_000);
+            direct.add_ticks(py, ticks).unwrap();
+            assert_eq!(all_bars(&replayed, 60), all_bars(&direct, 60));
+            
+            let path = replay_path("checkpoint");
+            std::fs::write(&path, direct.to_bytes(py).as_bytes()).unwrap();
+            let from_checkpoint = buffer(1_000);
+            assert_eq!(from_checkpoint.replay_file(py, &path, "checkpoint").unwrap(), 600);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(all_bars(&from_checkpoint, 300), all_bars(&direct, 300));
+            
+            let err = buffer(10).replay_file(py, "/nonexistent/ticks.bin", "raw").err().unwrap();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyIOError>(py));
+        });
+    }
+    
+    #[test]
+    fn volume_bars_partition_volume_and_carry_remainder() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            let volumes = [3, 4, 10, 1, 2];
+            for (i, &volume) in volumes.iter().enumerate() {
+                buffer.add_tick(py, tick_at(i as i64, 2000.0 + i as f64, volume)).unwrap();
+            }
+            
+            // 20 lots par barres de 5 : le tick de 10 clôture deux barres et
+            // en reporte 2 sur la suivante
+            let bars = buffer.build_volume_bars(5, PriceSource::Mid, false).unwrap();
+            assert_eq!(bars.len(), 4);
+            assert!(bars.iter().all(|bar| bar.volume == 5));
+            assert_eq!((bars[0].open, bars[0].close), (2000.0, 2001.0));
+            assert_eq!((bars[1].open, bars[1].close), (2001.0, 2002.0));
+            assert_eq!((bars[2].open, bars[2].close), (2002.0, 2002.0));
+            assert_eq!((bars[3].open, bars[3].close), (2002.0, 2004.0));
+            
+            let with_partial = buffer.build_volume_bars(6, PriceSource::Mid, true).unwrap();
+            let volumes: Vec<i64> = with_partial.iter().map(|bar| bar.volume).collect();
+            assert_eq!(volumes, vec![6, 6, 6, 2]);
+            assert_eq!(buffer.build_volume_bars(6, PriceSource::Mid, false).unwrap().len(), 3);
+        });
+    }
+    
+    #[test]
+    fn candles_follow_the_buffer_price_source() {
+        // Le tick à 5 s arrive en retard : il élargit le plus haut sans
+        // toucher la clôture
+        let mut stream = vec![
+            tick_at(0, 2000.0, 1),
+            tick_at(10, 2001.0, 2),
+            tick_at(20, 1999.5, 3),
+            tick_at(5, 2003.0, 4),
+            tick_at(60, 2000.0, 1),
+        ];
+        stream[1].ask = 2001.5;
+        stream[2].bid = 1999.0;
+        
+        Python::with_gil(|py| {
+            for source in [PriceSource::Mid, PriceSource::Bid, PriceSource::Ask] {
+                let buffer = TickBuffer::new(
+                    100, "XAUUSD".into(), false, true, false, None, source
+                ).unwrap();
+                buffer.add_ticks(py, stream.clone()).unwrap();
+                
+                let prices: Vec<f64> = stream.iter().map(|t| source.of(t)).collect();
+                let bar = buffer.candles.read()[&60].candles[0].clone();
+                assert_eq!(bar.open, prices[0], "{:?}", source);
+                assert_eq!(bar.high, prices[3], "{:?}", source);
+                assert_eq!(bar.low, prices[2], "{:?}", source);
+                assert_eq!(bar.close, prices[2], "{:?}", source);
+                assert_eq!(bar.volume, 10);
+                
+                let built = buffer.build_ohlc_from_ticks(stream[..3].to_vec(), source).unwrap();
+                assert_eq!(built.high, prices[0].max(prices[1]).max(prices[2]));
+                assert_eq!(built.close, prices[2]);
+            }
+        });
+    }
+    
+    /// Tick à `seconds` après minuit UTC le 14/11/2023
+    fn tick_utc(seconds: i64, mid: f64) -> Tick {
+        let mut tick = tick_at(0, mid, 1);
+        tick.timestamp = (1_699_920_000 + seconds) * 1_000_000;
+        tick
+    }
+    
+    #[test]
+    fn session_end_closes_the_candle_and_ticks_outside_are_dropped_or_bucketed() {
+        const HOUR: i64 = 3_600;
+        let stream = [
+            tick_utc(15 * HOUR + 1_800, 2000.0),
+            tick_utc(15 * HOUR + 3_000, 2001.0),
+            // Après la fermeture de 16:00
+            tick_utc(16 * HOUR + 600, 2050.0),
+            // Ouverture du lendemain
+            tick_utc(32 * HOUR + 300, 2002.0),
+        ];
+        
+        Python::with_gil(|py| {
+            let dropping = buffer(100);
+            dropping.register_timeframe(3_600).unwrap();
+            dropping.set_session(8 * 3_600, 16 * 3_600, true).unwrap();
+            for tick in &stream[..2] {
+                dropping.add_tick(py, tick.clone()).unwrap();
+            }
+            assert_eq!(dropping.candle_count(3_600).unwrap(), 0);
+            
+            // Le premier tick hors session clôture la bougie de 15:00
+            dropping.add_tick(py, stream[2].clone()).unwrap();
+            let closed = dropping.candles.read()[&3_600].candles.clone();
+            assert_eq!(closed.len(), 1);
+            assert_eq!(closed[0].timestamp, stream[0].timestamp - 1_800_000_000);
+            assert_eq!((closed[0].high, closed[0].close), (2001.0, 2001.0));
+            
+            dropping.add_tick(py, stream[3].clone()).unwrap();
+            let series = &dropping.candles.read()[&3_600];
+            assert_eq!(series.candles.len(), 1);
+            let current = series.current.as_ref().unwrap();
+            assert_eq!(current.timestamp, stream[3].timestamp - 300_000_000);
+            assert_eq!((current.open, current.high), (2002.0, 2002.0));
+            
+            let bucketing = buffer(100);
+            bucketing.register_timeframe(3_600).unwrap();
+            bucketing.set_session(8 * 3_600, 16 * 3_600, false).unwrap();
+            bucketing.add_ticks(py, stream.to_vec()).unwrap();
+            let closed = bucketing.candles.read()[&3_600].candles.clone();
+            assert_eq!(closed.len(), 2);
+            assert_eq!(closed[0].high, 2001.0);
+            assert_eq!(closed[1].timestamp, stream[2].timestamp - 600_000_000);
+            assert_eq!((closed[1].open, closed[1].close), (2050.0, 2050.0));
+        });
+    }
+    
+    #[test]
+    fn twap_weights_each_quote_by_its_duration() {
+        Python::with_gil(|py| {
+            let even = buffer(100);
+            for (i, mid) in [2000.0, 2002.0, 2004.0, 2006.0].iter().enumerate() {
+                even.add_tick(py, tick_at(i as i64 * 10, *mid, 1)).unwrap();
+            }
+            let t = |seconds: i64| tick_at(seconds, 0.0, 0).timestamp;
+            // Quatre cotations de 10 s chacune
+            assert!((even.twap(t(0), t(40)) - 2003.0).abs() < 1e-9);
+            
+            let uneven = buffer(100);
+            for (seconds, mid) in [(0, 2000.0), (5, 2010.0), (35, 2004.0)] {
+                uneven.add_tick(py, tick_at(seconds, mid, 1)).unwrap();
+            }
+            // 2000 pendant 5 s, 2010 pendant 30 s, 2004 pendant 5 s
+            let expected = (2000.0 * 5.0 + 2010.0 * 30.0 + 2004.0 * 5.0) / 40.0;
+            assert!((uneven.twap(t(0), t(40)) - expected).abs() < 1e-9);
+            
+            // Le mid 2000 est en vigueur de 2 s jusqu'au tick de 5 s
+            let seeded = (2000.0 * 3.0 + 2010.0 * 30.0 + 2004.0 * 5.0) / 38.0;
+            assert!((uneven.twap(t(2), t(40)) - seeded).abs() < 1e-9);
+            
+            assert!(uneven.twap(t(36), t(40)).is_nan());
+            assert!(uneven.twap(t(40), t(0)).is_nan());
+        });
+    }
+    
+    /// Bougies clôturées puis bougie en cours d'un timeframe
+    fn all_bars(buffer: &TickBuffer, timeframe: u64) -> Vec<(i64, f64, f64, f64, f64, i64)> {
+        let candles = buffer.candles_read();
+        let series = &candles[&timeframe];
+        series.candles.iter()
+            .chain(series.current.iter())
+            .map(|c| (c.timestamp, c.open, c.high, c.low, c.close, c.volume))
+            .collect()
+    }
+    
+    #[test]
+    fn lazy_and_eager_modes_build_identical_candles() {
+        // Ticks toutes les 7 s, dont un sur 11 arrive en retard : de 3 s
+        // (même bougie en général) ou de 130 s (bougie M1 déjà close)
+        let mut stream = Vec::new();
+        for i in 0..2_000i64 {
+            let mid = 2000.0 + (i as f64 / 13.0).sin() * 4.0;
+            stream.push(tick_at(i * 7, mid, 1 + i % 3));
+            if i % 11 == 5 {
+                let delay = if i % 22 == 5 { 3 } else { 130 };
+                stream.push(tick_at(i * 7 - delay, mid + 2.5 - (i % 5) as f64, 4));
+            }
+        }
+        
+        Python::with_gil(|py| {
+            for session in [None, Some((3_600, 7_200, true)), Some((3_600, 7_200, false))] {
+                let eager = buffer(10_000);
+                let lazy = TickBuffer::new(
+                    10_000, "XAUUSD".into(), false, true, true, None, PriceSource::Mid
+                ).unwrap();
+                for buffer in [&eager, &lazy] {
+                    buffer.register_timeframe(900).unwrap();
+                    if let Some((start, end, drop_outside)) = session {
+                        buffer.set_session(start, end, drop_outside).unwrap();
+                    }
+                    for chunk in stream.chunks(97) {
+                        buffer.add_ticks(py, chunk.to_vec()).unwrap();
+                    }
+                }
+                
+                for tf in eager.timeframes() {
+                    let expected = all_bars(&eager, tf);
+                    assert!(!expected.is_empty());
+                    assert_eq!(all_bars(&lazy, tf), expected, "{}s {:?}", tf, session);
+                }
+            }
+        });
+    }
+    
+    #[test]
+    fn candle_cap_evicts_the_oldest_bars_first() {
+        Python::with_gil(|py| {
+            let buffer = TickBuffer::new(
+                10_000, "XAUUSD".into(), false, true, false, Some(5), PriceSource::Mid
+            ).unwrap();
+            let minute = |m: i64| bar_start(tick_at(m * 60, 0.0, 0).timestamp, 60_000_000);
+            
+            // 100 bougies M1 clôturées : l'anneau fait plusieurs tours
+            for m in 0..=100 {
+                buffer.add_tick(py, tick_at(m * 60, 2000.0 + m as f64, 1)).unwrap();
+                let closed = m.min(5) as usize;
+                assert_eq!(buffer.candle_count(60).unwrap(), closed);
+                
+                let recent = buffer.get_candles(60, 3).unwrap();
+                let expected: Vec<i64> = (m - closed.min(3) as i64..m).map(minute).collect();
+                let timestamps: Vec<i64> = recent.iter().map(|c| c.timestamp).collect();
+                assert_eq!(timestamps, expected, "après {} minutes", m);
+                
+                let (highs, _, closes) = buffer.candle_columns(60, None).unwrap();
+                assert_eq!(highs.len(), closed);
+                assert_eq!(closes.last().copied(), (m > 0).then_some(1999.0 + m as f64));
+            }
+            let all = buffer.get_candles(60, usize::MAX).unwrap();
+            assert_eq!(all[0].timestamp, minute(95));
+            
+            buffer.set_max_candles(60, Some(2)).unwrap();
+            assert_eq!(buffer.candle_count(60).unwrap(), 2);
+            assert_eq!(buffer.get_candles(60, 10).unwrap()[0].timestamp, minute(98));
+        });
+    }
+    
+    #[test]
+    fn late_ticks_are_inserted_in_place_or_refused_in_strict_mode() {
+        Python::with_gil(|py| {
+            let strict = TickBuffer::new(
+                100, "XAUUSD".into(), true, true, false, None, PriceSource::Mid
+            ).unwrap();
+            let relaxed = buffer(100);
+            for buffer in [&strict, &relaxed] {
+                for i in 0..10 {
+                    assert!(buffer.add_tick(py, tick_at(i * 10, 2000.0, 1)).unwrap());
+                }
+                assert_eq!(buffer.out_of_order_count(), 0);
+                
+                // Horodatage identique au dernier : en ordre
+                assert!(buffer.add_tick(py, tick_at(90, 2001.0, 1)).unwrap());
+                assert_eq!(buffer.out_of_order_count(), 0);
+                assert_eq!(buffer.tick_count(), 11);
+            }
+            
+            // Tick de 35 s arrivé après celui de 90 s
+            assert!(!strict.add_tick(py, tick_at(35, 2002.0, 1)).unwrap());
+            assert_eq!(strict.tick_count(), 11);
+            assert_eq!(strict.out_of_order_count(), 1);
+            
+            assert!(relaxed.add_tick(py, tick_at(35, 2002.0, 1)).unwrap());
+            assert_eq!(relaxed.tick_count(), 12);
+            assert_eq!(relaxed.out_of_order_count(), 1);
+            let ticks = relaxed.get_recent_ticks(usize::MAX);
+            assert!(ticks.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+            assert_eq!(ticks[4].timestamp, tick_at(35, 0.0, 0).timestamp);
+            
+            // Doublon d'un tick en retard : placé après son jumeau
+            assert!(relaxed.add_tick(py, tick_at(35, 2003.0, 1)).unwrap());
+            let ticks = relaxed.get_recent_ticks(usize::MAX);
+            assert_eq!((ticks[4].bid, ticks[5].bid), (2002.0 - 0.1, 2003.0 - 0.1));
+            assert_eq!(relaxed.out_of_order_count(), 2);
+        });
+    }
+    
+    #[test]
+    fn csv_export_writes_a_header_and_one_row_per_tick() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            for i in 0..25 {
+                buffer.add_tick(py, tick_at(i, 2000.0 + i as f64 * 0.5, i + 1)).unwrap();
+            }
+            
+            let path = replay_path("export.csv");
+            assert_eq!(buffer.export_csv(&path).unwrap(), 25);
+            let text = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            
+            let mut lines = text.lines();
+            assert_eq!(lines.next(), Some("symbol,bid,ask,mid,spread,timestamp,volume"));
+            let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+            assert_eq!(rows.len(), 25);
+            for (row, tick) in rows.iter().zip(buffer.get_recent_ticks(usize::MAX)) {
+                assert_eq!(row.len(), 7);
+                assert_eq!(row[0], "XAUUSD");
+                assert_eq!(row[1].parse::<f64>().unwrap(), tick.bid);
+                assert_eq!(row[3].parse::<f64>().unwrap(), tick.mid_price());
+                assert_eq!(row[5].parse::<i64>().unwrap(), tick.timestamp);
+                assert_eq!(row[6].parse::<i64>().unwrap(), tick.volume);
+            }
+            
+            let err = buffer.export_csv("/nonexistent/ticks.csv").err().unwrap();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyIOError>(py));
+        });
+    }
+    
+    #[test]
+    fn time_range_bounds_are_inclusive() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            for i in 0..10 {
+                buffer.add_tick(py, tick_at(i * 10, 2000.0, 1)).unwrap();
+            }
+            let t = |seconds: i64| tick_at(seconds, 0.0, 0).timestamp;
+            let between = |start: i64, end: i64| -> Vec<i64> {
+                buffer.get_ticks_between(start, end).iter().map(|tick| tick.timestamp).collect()
+            };
+            
+            assert_eq!(between(t(20), t(40)), vec![t(20), t(30), t(40)]);
+            assert_eq!(between(t(21), t(39)), vec![t(30)]);
+            assert_eq!(between(t(90), t(90)), vec![t(90)]);
+            assert_eq!(between(t(0), t(0)), vec![t(0)]);
+            assert_eq!(between(i64::MIN, i64::MAX).len(), 10);
+            
+            // Plages vides, hors buffer ou inversées
+            assert!(between(t(21), t(29)).is_empty());
+            assert!(between(t(91), t(200)).is_empty());
+            assert!(between(t(-50), t(0) - 1).is_empty());
+            assert!(between(t(40), t(20)).is_empty());
+        });
+    }
+    
+    #[test]
+    fn batch_larger_than_capacity_keeps_the_last_ticks() {
+        Python::with_gil(|py| {
+            let batched = buffer(100);
+            let batch: Vec<Tick> = (0..200).map(|i| tick_at(i, 2000.0 + i as f64, 1)).collect();
+            assert_eq!(batched.add_ticks(py, batch.clone()).unwrap(), 200);
+            
+            assert_eq!(batched.tick_count(), 100);
+            let kept: Vec<i64> = batched.get_recent_ticks(usize::MAX)
+                .iter()
+                .map(|tick| tick.timestamp)
+                .collect();
+            let expected: Vec<i64> = batch[100..].iter().map(|tick| tick.timestamp).collect();
+            assert_eq!(kept, expected);
+            
+            // Les bougies voient tout le lot, pas seulement les ticks conservés
+            let single = buffer(100);
+            for tick in batch {
+                single.add_tick(py, tick).unwrap();
+            }
+            assert_eq!(all_bars(&batched, 60), all_bars(&single, 60));
+        });
+    }
+    
+    #[test]
+    fn column_getters_match_the_object_getters() {
+        Python::with_gil(|py| {
+            let buffer = buffer(1_000);
+            for i in 0..600 {
+                let mid = 2000.0 + (i as f64 / 9.0).sin() * 3.0;
+                buffer.add_tick(py, tick_at(i, mid, 1 + i % 4)).unwrap();
+            }
+            let bars = buffer.get_m1_candles(5);
+            assert_eq!(bars.len(), 5);
+            
+            let (highs, lows, closes) = buffer.candle_columns(60, Some(5)).unwrap();
+            assert_eq!(highs, bars.iter().map(|c| c.high).collect::<Vec<_>>());
+            assert_eq!(lows, bars.iter().map(|c| c.low).collect::<Vec<_>>());
+            assert_eq!(closes, bars.iter().map(|c| c.close).collect::<Vec<_>>());
+            
+            // Les versions numpy exigent le module numpy côté Python
+            if py.import("numpy").is_err() {
+                return;
+            }
+            let dict = buffer.get_m1_candles_np(py, 5).unwrap();
+            let array = |name: &str| -> Vec<f64> {
+                let item = dict.get_item(name).unwrap().unwrap();
+                item.downcast::<PyArray1<f64>>().unwrap().to_vec().unwrap()
+            };
+            let opens = array("open");
+            assert_eq!(opens.len(), bars.len());
+            assert_eq!((opens[0], opens[4]), (bars[0].open, bars[4].open));
+            assert_eq!(array("close"), closes);
+            
+            let ticks = buffer.get_recent_ticks(10);
+            let dict = buffer.get_recent_ticks_np(py, 10).unwrap();
+            let mids: Vec<f64> = dict.get_item("mid").unwrap().unwrap()
+                .downcast::<PyArray1<f64>>().unwrap()
+                .to_vec().unwrap();
+            assert_eq!(mids.len(), 10);
+            assert_eq!((mids[0], mids[9]), (ticks[0].mid_price(), ticks[9].mid_price()));
+        });
+    }
+    
+    #[test]
+    fn missing_minutes_are_reported_as_one_gap() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            let minute = |m: i64| bar_start(tick_at(m * 60, 0.0, 0).timestamp, 60_000_000);
+            
+            // Coupure de flux : aucune cotation pendant les minutes 3, 4 et 5
+            for m in [0, 1, 2, 6, 7, 8] {
+                buffer.add_tick(py, tick_at(m * 60, 2000.0 + m as f64, 1)).unwrap();
+            }
+            assert_eq!(buffer.find_candle_gaps(60).unwrap(), vec![(minute(3), minute(5))]);
+            assert!(buffer.find_candle_gaps(300).unwrap().is_empty());
+            
+            // Une seule minute manquante : début et fin confondus
+            buffer.add_tick(py, tick_at(10 * 60, 2010.0, 1)).unwrap();
+            let gaps = buffer.find_candle_gaps(60).unwrap();
+            assert_eq!(gaps, vec![(minute(3), minute(5)), (minute(9), minute(9))]);
+            
+            let err = buffer.find_candle_gaps(42).err().unwrap();
+            assert_eq!(error_code(err), "UNKNOWN_TIMEFRAME");
+        });
+    }
+    
+    #[test]
+    fn spread_stats_and_tick_rate_on_a_known_stream() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            assert!(buffer.spread_stats(10).0.is_nan());
+            assert_eq!(buffer.tick_rate(1_000_000), 0.0);
+            
+            // Spreads 0.1, 0.2, 0.3, 0.4 répétés, un tick toutes les 500 ms
+            for i in 0..20 {
+                let spread = 0.1 * (1 + i % 4) as f64;
+                let mut tick = tick_at(0, 2000.0, 1);
+                tick.timestamp += i * 500_000;
+                tick.bid = 2000.0;
+                tick.ask = 2000.0 + spread;
+                buffer.add_tick(py, tick).unwrap();
+            }
+            
+            let (mean, min, max) = buffer.spread_stats(4);
+            assert!((mean - 0.25).abs() < 1e-9);
+            assert!((min - 0.1).abs() < 1e-9 && (max - 0.4).abs() < 1e-9);
+            let (all_mean, _, _) = buffer.spread_stats(1_000);
+            assert!((all_mean - 0.25).abs() < 1e-9);
+            let (last, _, _) = buffer.spread_stats(1);
+            assert!((last - 0.4).abs() < 1e-9);
+            
+            // 2 ticks par seconde ; la borne gauche de la fenêtre est exclue
+            assert_eq!(buffer.tick_rate(5_000_000), 2.0);
+            assert_eq!(buffer.tick_rate(1_000_000), 2.0);
+            assert_eq!(buffer.tick_rate(100_000_000), 0.2);
+            assert!(buffer.tick_rate(0).is_nan());
+        });
+    }
+    
+    #[test]
+    fn clear_empties_the_buffer_but_keeps_its_allocation() {
+        Python::with_gil(|py| {
+            let buffer = buffer(500);
+            let stream: Vec<Tick> = (0..600).map(|i| tick_at(i, 2000.0, 1)).collect();
+            buffer.add_ticks(py, stream.clone()).unwrap();
+            buffer.add_tick(py, tick_at(3, 2000.0, 1)).unwrap();
+            let allocated = buffer.ticks.read().capacity();
+            assert!(allocated >= 500);
+            
+            buffer.clear();
+            assert_eq!(buffer.tick_count(), 0);
+            assert_eq!(buffer.candle_count(60).unwrap(), 0);
+            assert!(buffer.candles.read()[&60].current.is_none());
+            assert_eq!(buffer.ticks.read().capacity(), allocated);
+            assert_eq!(buffer.out_of_order_count(), 1);
+            
+            // Le remplissage suivant réutilise l'allocation
+            buffer.add_ticks(py, stream).unwrap();
+            assert_eq!(buffer.tick_count(), 500);
+            assert_eq!(buffer.ticks.read().capacity(), allocated);
+            
+            buffer.reset();
+            assert_eq!(buffer.tick_count(), 0);
+            assert_eq!(buffer.out_of_order_count(), 0);
+            assert_eq!(buffer.timeframes(), vec![60, 300]);
+        });
+    }
+    
+    #[test]
+    fn bid_ask_bar_aggregates_each_side_separately() {
+        let quotes = [(2000.0, 2000.3), (2001.0, 2001.1), (1999.5, 2000.5), (2000.4, 2000.6)];
+        let ticks: Vec<Tick> = quotes
+            .iter()
+            .enumerate()
+            .map(|(i, &(bid, ask))| {
+                let mut tick = tick_at(i as i64, 0.0, i as i64 + 1);
+                tick.bid = bid;
+                tick.ask = ask;
+                tick
+            })
+            .collect();
+        
+        let bar = buffer(10).build_ohlc_bidask_from_ticks(ticks.clone()).unwrap();
+        assert_eq!(bar.timestamp, ticks[0].timestamp);
+        assert_eq!((bar.bid_open, bar.bid_high, bar.bid_low, bar.bid_close),
+            (2000.0, 2001.0, 1999.5, 2000.4));
+        assert_eq!((bar.ask_open, bar.ask_high, bar.ask_low, bar.ask_close),
+            (2000.3, 2001.1, 2000.3, 2000.6));
+        assert_eq!(bar.volume, 10);
+        
+        // Bornes pessimistes : plus haut des asks, plus bas des bids
+        assert_eq!((bar.worst_case_high(), bar.worst_case_low()), (2001.1, 1999.5));
+        assert!(buffer(10).build_ohlc_bidask_from_ticks(Vec::new()).is_none());
+    }
+    
+    #[test]
+    fn heikin_ashi_matches_a_known_sequence() {
+        let raw = [
+            (10.0, 12.0, 9.0, 11.0),
+            (11.0, 13.0, 10.0, 12.5),
+            (12.0, 12.5, 8.0, 8.5),
+            (8.5, 9.0, 8.0, 8.75),
+        ];
+        let bars: Vec<OHLC> = raw
+            .iter()
+            .enumerate()
+            .map(|(i, &(open, high, low, close))| OHLC {
+                timestamp: i as i64 * 60_000_000,
+                open,
+                high,
+                low,
+                close,
+                volume: 1,
+            })
+            .collect();
+        
+        // (open, high, low, close) attendus ; la dernière ouverture HA
+        // (10.59375) dépasse le plus haut brut et devient le plus haut
+        let expected = [
+            (10.0, 12.0, 9.0, 10.5),
+            (10.25, 13.0, 10.0, 11.625),
+            (10.9375, 12.5, 8.0, 10.25),
+            (10.59375, 10.59375, 8.0, 8.5625),
+        ];
+        let ha = heikin_ashi(bars.iter());
+        assert_eq!(ha.len(), 4);
+        for (bar, want) in ha.iter().zip(expected) {
+            assert_eq!((bar.open, bar.high, bar.low, bar.close), want);
+        }
+        assert!(ha.iter().zip(&bars).all(|(h, b)| h.timestamp == b.timestamp));
+        
+        // Sur le buffer, les N dernières HA ne dépendent pas de N
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            for (m, bar) in raw.iter().enumerate() {
+                let m = m as i64;
+                buffer.add_tick(py, tick_at(m * 60, bar.0, 1)).unwrap();
+                buffer.add_tick(py, tick_at(m * 60 + 10, bar.3, 1)).unwrap();
+            }
+            buffer.add_tick(py, tick_at(4 * 60, 2000.0, 1)).unwrap();
+            let all = buffer.get_heikin_ashi(60, 10).unwrap();
+            let last_two = buffer.get_heikin_ashi(60, 2).unwrap();
+            assert_eq!(all.len(), 4);
+            assert_eq!(last_two[0].open, all[2].open);
+            assert_eq!(last_two[1].close, all[3].close);
+        });
+    }
+    
+    #[test]
+    fn interleaved_symbols_are_routed_to_their_own_buffers() {
+        Python::with_gil(|py| {
+            let multi = MultiSymbolBuffer::new(50).unwrap();
+            let symbols = ["XAUUSD", "XAGUSD", "EURUSD"];
+            for i in 0..90 {
+                let symbol = symbols[i % 3];
+                let mut tick = tick_at(i as i64, 100.0 * (1 + i % 3) as f64, 1);
+                tick.symbol = symbol.into();
+                assert!(multi.add_tick(py, tick).unwrap());
+            }
+            
+            assert_eq!(multi.symbols(), vec!["EURUSD", "XAGUSD", "XAUUSD"]);
+            for (k, symbol) in symbols.iter().enumerate() {
+                let buffer = multi.get_buffer(symbol).unwrap();
+                assert_eq!(buffer.symbol, *symbol);
+                assert_eq!(buffer.capacity, 50);
+                assert_eq!(buffer.tick_count(), 30);
+                let ticks = buffer.get_recent_ticks(usize::MAX);
+                assert!(ticks.iter().all(|t| t.symbol == *symbol));
+                assert!(ticks.iter().all(|t| t.mid_price() == 100.0 * (1 + k) as f64));
+                assert!(ticks.windows(2).all(|w| w[1].timestamp - w[0].timestamp == 3_000_000));
+            }
+            
+            // Le buffer retourné est partagé, pas une copie
+            let gold = multi.get_buffer("XAUUSD").unwrap();
+            let mut tick = tick_at(1_000, 2000.0, 1);
+            tick.symbol = "XAUUSD".into();
+            multi.add_tick(py, tick).unwrap();
+            assert_eq!(gold.tick_count(), 31);
+            assert!(multi.get_buffer("GBPUSD").is_none());
+        });
+    }
+    
+    /// Intégrité seule : la libération du GIL pendant l'attente du verrou
+    /// dépend de l'ordonnancement et serait instable en CI, elle est
+    /// vérifiée par le benchmark `gil_released_while_add_tick_waits`
+    /// (benches/tick_buffer.rs)
+    #[test]
+    fn concurrent_writers_lose_no_tick() {
+        const WRITERS: i64 = 4;
+        const PER_WRITER: i64 = 2_500;
+        let shared = buffer(100_000);
+        
+        // Chaque thread prend le GIL pour ses appels, comme un thread Python ;
+        // add_tick / add_ticks le relâchent pendant l'attente du verrou
+        let handles: Vec<_> = (0..WRITERS)
+            .map(|w| {
+                let buffer = shared.clone();
+                std::thread::spawn(move || {
+                    for i in 0..PER_WRITER {
+                        let tick = tick_at(i * WRITERS + w, 2000.0 + w as f64, 1);
+                        Python::with_gil(|py| {
+                            if i % 2 == 0 {
+                                buffer.add_tick(py, tick).unwrap();
+                            } else {
+                                buffer.add_ticks(py, vec![tick]).unwrap();
+                            }
+                        });
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        
+        assert_eq!(shared.tick_count(), (WRITERS * PER_WRITER) as usize);
+        let ticks = shared.get_recent_ticks(usize::MAX);
+        assert!(ticks.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        let expected: Vec<i64> = (0..WRITERS * PER_WRITER)
+            .map(|seconds| tick_at(seconds, 0.0, 0).timestamp)
+            .collect();
+        let timestamps: Vec<i64> = ticks.iter().map(|tick| tick.timestamp).collect();
+        assert_eq!(timestamps, expected);
+    }
+    
+    #[test]
+    fn snapshots_taken_during_writes_are_self_consistent() {
+        const TICKS: i64 = 5_000;
+        let shared = buffer(10_000);
+        let writer = {
+            let buffer = shared.clone();
+            std::thread::spawn(move || {
+                for i in 0..TICKS {
+                    Python::with_gil(|py| buffer.add_tick(py, tick_at(i, 2000.0, 1)).unwrap());
+                }
+            })
+        };
+        
+        // Un tick de volume 1 par seconde : dans une vue cohérente, chaque
+        // timeframe a agrégé exactement les ticks copiés
+        let mut seen = 0;
+        while seen < TICKS as usize {
+            let snapshot = Python::with_gil(|py| shared.snapshot(py, None));
+            seen = snapshot.tick_count();
+            for tf in [60, 300] {
+                let closed: i64 = snapshot.candles[&tf].iter().map(|bar| bar.volume).sum();
+                let current = snapshot.current_candles.get(&tf).map_or(0, |bar| bar.volume);
+                assert_eq!((closed + current) as usize, seen, "{}s", tf);
+                
+                let last = snapshot.ticks.last().map(|tick| tick.timestamp);
+                let bar = snapshot.current_candles.get(&tf).map(|bar| bar.timestamp);
+                assert_eq!(bar, last.map(|ts| bar_start(ts, tf as i64 * 1_000_000)));
+            }
+        }
+        writer.join().unwrap();
+    }
+    
+    #[test]
+    fn five_m1_bars_resample_into_one_m5() {
+        let start = bar_start(tick_at(0, 0.0, 0).timestamp, 300_000_000);
+        let bar = |k: i64, open: f64, high: f64, low: f64, close: f64| OHLC {
+            timestamp: start + k * 60_000_000,
+            open,
+            high,
+            low,
+            close,
+            volume: 10 + k,
+        };
+        let m1 = vec![
+            bar(0, 2000.0, 2001.0, 1999.5, 2000.5),
+            bar(1, 2000.5, 2003.0, 2000.0, 2002.0),
+            bar(2, 2002.0, 2002.5, 1998.0, 1998.5),
+            bar(3, 1998.5, 2000.0, 1998.2, 1999.8),
+            bar(4, 1999.8, 2001.2, 1999.1, 2001.0),
+        ];
+        
+        let m5 = resample_ohlc(m1.clone(), 60, 300).unwrap();
+        assert_eq!(m5.len(), 1);
+        let m5 = &m5[0];
+        assert_eq!(m5.timestamp, start);
+        assert_eq!((m5.open, m5.high, m5.low, m5.close), (2000.0, 2003.0, 1998.0, 2001.0));
+        assert_eq!(m5.volume, 60);
+        
+        // Une sixième bougie ouvre la M5 suivante
+        let mut six = m1.clone();
+        six.push(bar(5, 2001.0, 2001.5, 2000.5, 2001.2));
+        let m5 = resample_ohlc(six, 60, 300).unwrap();
+        assert_eq!(m5.len(), 2);
+        assert_eq!((m5[1].timestamp, m5[1].open), (start + 300_000_000, 2001.0));
+        
+        for (from, to) in [(60, 90), (0, 300), (60, 0)] {
+            let err = resample_ohlc(m1.clone(), from, to).err().unwrap();
+            assert_eq!(error_code(err), "INVALID_PARAMETER");
+        }
+    }
+    
+    #[test]
+    fn up_heavy_stream_has_a_positive_imbalance() {
+        Python::with_gil(|py| {
+            let buffer = buffer(100);
+            assert!(buffer.tick_imbalance(10).is_nan());
+            
+            // Premier tick non classé, le mid plat reprend la hausse
+            let stream = [(2000.0, 5), (2001.0, 3), (2001.0, 2), (2000.5, 1), (2002.0, 4)];
+            for (i, &(mid, volume)) in stream.iter().enumerate() {
+                buffer.add_tick(py, tick_at(i as i64, mid, volume)).unwrap();
+            }
+            
+            assert!((buffer.tick_imbalance(100) - 8.0 / 15.0).abs() < 1e-12);
+            // Fenêtre de 4 : son premier tick est classé sur celui d'avant
+            assert!((buffer.tick_imbalance(4) - 0.8).abs() < 1e-12);
+            assert_eq!(buffer.tick_imbalance(1), 1.0);
+            
+            // Volume nul
+            let flat = TickBuffer::new(10, "X".into(), false, false, false, None, PriceSource::Mid)
+                .unwrap();
+            flat.add_tick(py, tick_at(0, 2000.0, 0)).unwrap();
+            assert!(flat.tick_imbalance(10).is_nan());
+        });
+    }
+    
+    #[test]
+    fn realized_vol_matches_a_known_return_series() {
+        Python::with_gil(|py| {
+            let short = buffer(100);
+            short.add_tick(py, tick_at(0, 2000.0, 1)).unwrap();
+            short.add_tick(py, tick_at(1, 2001.0, 1)).unwrap();
+            assert!(short.realized_vol(100, 252.0).is_nan());
+            
+            // Log-rendements 0.002, -0.001, 0.003, -0.004 : moyenne 0,
+            // variance d'échantillon (4 + 1 + 9 + 16) * 1e-6 / 3 = 1e-5
+            let returns = [0.002, -0.001, 0.003, -0.004];
+            let known = buffer(100);
+            let mut mid = 2000.0;
+            known.add_tick(py, tick_at(0, mid, 1)).unwrap();
+            for (i, r) in returns.iter().enumerate() {
+                mid *= f64::exp(*r);
+                known.add_tick(py, tick_at(i as i64 + 1, mid, 1)).unwrap();
+            }
+            let expected = (1e-5f64 * 252.0).sqrt();
+            assert!((known.realized_vol(100, 252.0) - expected).abs() < 1e-9);
+            assert!((known.realized_vol(5, 1.0) - 1e-5f64.sqrt()).abs() < 1e-9);
+            // Trois derniers ticks : rendements 0.003 et -0.004
+            let last_two = (0.007f64 * 0.007 / 2.0).sqrt();
+            assert!((known.realized_vol(3, 1.0) - last_two).abs() < 1e-9);
+            assert!(known.realized_vol(100, -1.0).is_nan());
+            
+            let flat = buffer(10);
+            for i in 0..5 {
+                flat.add_tick(py, tick_at(i, 2000.0, 1)).unwrap();
+            }
+            assert_eq!(flat.realized_vol(10, 252.0), 0.0);
+        });
+    }
+    
+    #[test]
+    fn utc_minute_boundary_splits_candles_and_datetimes() {
+        const NOON: i64 = 12 * 3_600;
+        let mut before = tick_utc(NOON + 34 * 60 + 59, 2000.0);
+        before.timestamp += 999_999;
+        let after = tick_utc(NOON + 35 * 60, 2001.0);
+        assert_eq!(after.timestamp - before.timestamp, 1);
+        
+        let at = |dt: DateTime<Utc>| (dt.hour(), dt.minute(), dt.second(), dt.nanosecond());
+        assert_eq!(before.datetime().date_naive().to_string(), "2023-11-14");
+        assert_eq!(at(before.datetime()), (12, 34, 59, 999_999_000));
+        assert_eq!(at(after.datetime()), (12, 35, 0, 0));
+        
+        Python::with_gil(|py| {
+            let buffer = buffer(10);
+            buffer.add_tick(py, before.clone()).unwrap();
+            buffer.add_tick(py, after.clone()).unwrap();
+            
+            let closed = buffer.get_m1_candles(10);
+            assert_eq!(closed.len(), 1);
+            assert_eq!(at(closed[0].datetime()), (12, 34, 0, 0));
+            assert_eq!(closed[0].close, before.mid_price());
+            let current = buffer.candles.read()[&60].current.clone().unwrap();
+            assert_eq!(at(current.datetime()), (12, 35, 0, 0));
+            assert_eq!(current.timestamp, after.timestamp);
+            
+            // Getter Python : datetime.datetime avec tzinfo UTC
+            let dt = closed[0].py_datetime(py).unwrap();
+            let dt = dt.as_ref(py);
+            assert_eq!(dt.getattr("minute").unwrap().extract::<u32>().unwrap(), 34);
+            let iso = dt.call_method0("isoformat").unwrap().to_string();
+            assert_eq!(iso, "2023-11-14T12:34:00+00:00");
+        });
+    }
+    
+    #[test]
+    fn spike_filter_rejects_a_single_glitch() {
+        let smooth = |i: i64| 2000.0 + (i as f64 / 10.0).sin();
+        let mut stream: Vec<Tick> = (0..200).map(|i| tick_at(i, smooth(i), 1)).collect();
+        stream[100] = tick_at(100, 200_000.0, 1);
+        
+        Python::with_gil(|py| {
+            // Filtre inactif par défaut : le pic fausse le plus haut
+            let unfiltered = buffer(1_000);
+            unfiltered.add_ticks(py, stream.clone()).unwrap();
+            assert_eq!(unfiltered.rejected_outlier_count(), 0);
+            assert!(all_bars(&unfiltered, 60).iter().any(|bar| bar.2 > 100_000.0));
+            
+            let filtered = buffer(1_000);
+            filtered.set_outlier_filter(5.0, 20).unwrap();
+            for tick in &stream {
+                let accepted = filtered.add_tick(py, tick.clone()).unwrap();
+                assert_eq!(accepted, tick.timestamp != stream[100].timestamp);
+            }
+            assert_eq!(filtered.rejected_outlier_count(), 1);
+            assert_eq!(filtered.tick_count(), 199);
+            assert!(all_bars(&filtered, 60).iter().all(|bar| bar.2 < 2002.0));
+            
+            // Changement de niveau durable : accepté après `window` rejets
+            for i in 0..21 {
+                let accepted = filtered.add_tick(py, tick_at(200 + i, 2100.0, 1)).unwrap();
+                assert_eq!(accepted, i == 20, "tick {}", i);
+            }
+            assert_eq!(filtered.rejected_outlier_count(), 21);
+            
+            assert!(filtered.set_outlier_filter(0.0, 20).is_err());
+            assert!(filtered.set_outlier_filter(3.0, 1).is_err());
+        });
+    }
+    
+    #[test]
+    fn indicator_shortcuts_match_the_manual_candle_path() {
+        Python::with_gil(|py| {
+            let source = buffer(10_000);
+            for i in 0..6_000 {
+                let mid = 2000.0 + (i as f64 / 200.0).sin() * 4.0 + i as f64 * 0.001;
+                source.add_tick(py, tick_at(i, mid, 1)).unwrap();
+            }
+            let bars = source.get_candles(60, usize::MAX).unwrap();
+            assert_eq!(bars.len(), 100);
+            let column = |f: fn(&OHLC) -> f64| bars.iter().map(f).collect::<Vec<_>>();
+            let (highs, lows) = (column(|c| c.high), column(|c| c.low));
+            let closes = column(|c| c.close);
+            
+            // Mêmes erreurs que le chemin manuel
+            let unknown = source.stc(py, 42, None, 10, 23, 50, 0.5, "carry").unwrap_err();
+            assert_eq!(error_code(unknown), "UNKNOWN_TIMEFRAME");
+            let short = source.stc(py, 60, Some(40), 10, 23, 50, 0.5, "carry").unwrap_err();
+            let manual = stc_array(py, &closes[60..], 10, 23, 50, 0.5, FlatRange::Carry);
+            assert_eq!(error_code(short), error_code(manual.unwrap_err()));
+            let short = source.ichimoku(py, 60, Some(30), 9, 26, 52, false).unwrap_err();
+            let manual = ichimoku_arrays(py, &highs[70..], &lows[70..], &closes[70..], 9, 26, 52,
+                false);
+            assert_eq!(error_code(short), error_code(manual.unwrap_err()));
+            
+            // Les tableaux résultats exigent le module numpy côté Python
+            if py.import("numpy").is_err() {
+                return;
+            }
+            let values = |series: &PySeries| series.as_ref(py).to_vec().unwrap();
+            let same = |a: &PySeries, b: &PySeries| {
+                let (a, b) = (values(a), values(b));
+                assert_eq!(a.len(), b.len());
+                assert!(a.iter().zip(&b).all(|(x, y)| x == y || (x.is_nan() && y.is_nan())));
+            };
+            
+            let shortcut = source.stc(py, 60, None, 10, 23, 50, 0.5, "carry").unwrap();
+            let manual = stc_array(py, &closes, 10, 23, 50, 0.5, FlatRange::Carry).unwrap();
+            same(&shortcut, &manual);
+            
+            let shortcut = source.ichimoku(py, 60, Some(80), 9, 26, 52, true).unwrap();
+            let manual = ichimoku_arrays(py, &highs[20..], &lows[20..], &closes[20..], 9, 26, 52,
+                true).unwrap();
+            same(&shortcut.0, &manual.0);
+            same(&shortcut.1, &manual.1);
+            same(&shortcut.2, &manual.2);
+            same(&shortcut.3, &manual.3);
+            same(&shortcut.4, &manual.4);
+        });
+    }
+    
+    #[test]
+    fn spread_percentile_and_volume_median_on_a_small_set() {
+        Python::with_gil(|py| {
+            let small = buffer(100);
+            assert!(small.spread_percentile(10, 50.0).unwrap().is_nan());
+            assert!(small.volume_median(10).is_nan());
+            
+            let spreads = [0.1, 0.5, 0.3, 0.2, 0.4, 0.3];
+            let volumes = [3, 1, 4, 1, 5, 9];
+            for (i, (spread, volume)) in spreads.iter().zip(volumes).enumerate() {
+                let mut tick = tick_at(i as i64, 2000.0, volume);
+                tick.bid = 2000.0 - spread / 2.0;
+                tick.ask = 2000.0 + spread / 2.0;
+                small.add_tick(py, tick).unwrap();
+            }
+            
+            // Cinq derniers spreads triés : 0.2, 0.3, 0.3, 0.4, 0.5
+            let percentile = |n, p| small.spread_percentile(n, p).unwrap();
+            let expected = [(0.0, 0.2), (25.0, 0.3), (50.0, 0.3), (90.0, 0.46), (100.0, 0.5)];
+            for (p, want) in expected {
+                let got = percentile(5, p);
+                assert!((got - want).abs() < 1e-9, "p{} : {}", p, got);
+            }
+            assert!((percentile(1_000, 0.0) - 0.1).abs() < 1e-9);
+            assert!(percentile(0, 50.0).is_nan());
+            
+            // Effectif pair : moyenne des deux rangs centraux
+            assert_eq!(small.volume_median(6), 3.5);
+            assert_eq!(small.volume_median(5), 4.0);
+            assert_eq!(small.volume_median(1), 9.0);
+            
+            for p in [-0.1, 100.5, f64::NAN] {
+                let err = small.spread_percentile(5, p).unwrap_err();
+                assert_eq!(error_code(err), "INVALID_PARAMETER");
+            }
+        });
+    }
+    
+    #[test]
+    fn f32_storage_round_trips_prices_within_f32_tolerance() {
+        Python::with_gil(|py| {
+            let compact = TickBufferF32::new(1_000, "XAUUSD".into()).unwrap();
+            let wide = buffer(1_000);
+            let stream: Vec<Tick> = (0..500)
+                .map(|i| tick_at(i, 1950.0 + (i as f64 / 7.0).sin() * 40.0 + 0.013 * i as f64, 2))
+                .collect();
+            compact.add_ticks(py, stream.clone()).unwrap();
+            wide.add_ticks(py, stream.clone()).unwrap();
+            
+            // Deux ulp f32 autour de 2000 (2^-13 ≈ 1.2e-4 chacun)
+            let tolerance = 2000.0 * f32::EPSILON as f64;
+            let restored = compact.get_recent_ticks(500);
+            assert_eq!(restored.len(), stream.len());
+            for (back, tick) in restored.iter().zip(&stream) {
+                assert_eq!((back.timestamp, back.volume), (tick.timestamp, tick.volume));
+                assert!((back.bid - tick.bid).abs() <= tolerance, "{} {}", back.bid, tick.bid);
+                assert!((back.ask - tick.ask).abs() <= tolerance, "{} {}", back.ask, tick.ask);
+                assert_eq!(back.symbol, "XAUUSD");
+            }
+            
+            let last = compact.last_mid_price().unwrap();
+            assert!((last - wide.last_mid_price().unwrap()).abs() <= tolerance);
+            assert!((compact.vwap(100) - wide.vwap(100)).abs() <= tolerance);
+            assert!(compact.memory_bytes() * 2 < wide.memory_bytes());
+        });
+    }
+    
+    #[test]
+    fn volume_profile_buckets_a_known_distribution() {
+        Python::with_gil(|py| {
+            let profiled = buffer(100);
+            assert_eq!(profiled.volume_profile(4).unwrap(), (Vec::new(), Vec::new()));
+            assert_eq!(profiled.point_of_control(4).unwrap(), None);
+            
+            // Plage [2000, 2004] en bins de 1 : volumes 1+2, 10, 4, 3+5
+            let levels = [
+                (2000.0, 1), (2000.6, 2), (2001.4, 10), (2002.2, 4), (2003.3, 3), (2004.0, 5),
+            ];
+            for (i, &(mid, volume)) in levels.iter().enumerate() {
+                profiled.add_tick(py, tick_at(i as i64, mid, volume)).unwrap();
+            }
+            let (centers, volumes) = profiled.volume_profile(4).unwrap();
+            assert_eq!(volumes, vec![3.0, 10.0, 4.0, 8.0]);
+            let expected = [2000.5, 2001.5, 2002.5, 2003.5];
+            let matches = centers.iter().zip(expected).all(|(c, e)| (c - e).abs() < 1e-9);
+            assert!(matches, "{:?}", centers);
+            let poc = profiled.point_of_control(4).unwrap().unwrap();
+            assert!((poc - 2001.5).abs() < 1e-9);
+            
+            // Prix unique : tout le volume dans un seul bin
+            let flat = buffer(100);
+            for i in 0..5 {
+                flat.add_tick(py, tick_at(i, 2010.0, 2)).unwrap();
+            }
+            let (centers, volumes) = flat.volume_profile(10).unwrap();
+            assert_eq!(volumes, vec![10.0]);
+            assert!((centers[0] - 2010.0).abs() < 1e-9);
+            
+            assert_eq!(error_code(flat.volume_profile(0).unwrap_err()), "INVALID_PARAMETER");
+        });
+    }
+    
+    #[test]
+    fn candle_returns_are_simple_or_log_with_a_leading_nan() {
+        Python::with_gil(|py| {
+            // Sans validation, pour alimenter une clôture nulle
+            let raw = TickBuffer::new(100, "XAUUSD".into(), false, false, false, None,
+                PriceSource::Mid).unwrap();
+            let closes = [2000.0, 2010.0, 1990.0, 1990.0, 2005.0];
+            for (minute, mid) in closes.into_iter().enumerate() {
+                raw.add_tick(py, tick_at(minute as i64 * 60, mid, 1)).unwrap();
+            }
+            assert_eq!(raw.get_candles(60, 10).unwrap().len(), 4);
+            
+            let simple = raw.candle_returns(60, 3, false).unwrap();
+            let log = raw.candle_returns(60, 3, true).unwrap();
+            let (expected_simple, expected_log) = (
+                [0.005, -20.0 / 2010.0, 0.0],
+                [1.005f64.ln(), (1990.0f64 / 2010.0).ln(), 0.0],
+            );
+            assert_eq!((simple.len(), log.len()), (4, 4));
+            assert!(simple[0].is_nan() && log[0].is_nan());
+            for i in 0..3 {
+                assert!((simple[i + 1] - expected_simple[i]).abs() < 1e-12, "{:?}", simple);
+                assert!((log[i + 1] - expected_log[i]).abs() < 1e-12, "{:?}", log);
+            }
+            // Historique plus court que n + 1 : toutes les bougies
+            assert_eq!(raw.candle_returns(60, 50, false).unwrap().len(), 4);
+            
+            // Clôtures 1990, 2005, 0, -3, 2000 : NaN autour des clôtures <= 0
+            for (minute, mid) in [(5, 0.0), (6, -3.0), (7, 2000.0), (8, 2020.0)] {
+                raw.add_tick(py, tick_at(minute * 60, mid, 1)).unwrap();
+            }
+            let guarded = raw.candle_returns(60, 4, true).unwrap();
+            assert!((guarded[1] - (2005.0f64 / 1990.0).ln()).abs() < 1e-12);
+            assert!([0, 2, 3, 4].iter().all(|&i| guarded[i].is_nan()), "{:?}", guarded);
+            let simple = raw.candle_returns(60, 4, false).unwrap();
+            assert!([0, 2, 3, 4].iter().all(|&i| simple[i].is_nan()), "{:?}", simple);
+        });
+    }
+    
+    #[test]
+    fn registered_indicators_stay_readable_while_ticks_arrive() {
+        const TICKS: i64 = 3_000;
+        let shared = buffer(10_000);
+        let mid = |i: i64| 2000.0 + (i as f64 / 40.0).sin() * 8.0;
+        
+        let (ema, cloud) = Python::with_gil(|py| {
+            let ema = py.get_type::<EMACalculator>().call1((5,)).unwrap();
+            let cloud = py.get_type::<IchimokuState>().call0().unwrap();
+            shared.register_indicator("ema".into(), 60, ema).unwrap();
+            shared.register_indicator("cloud".into(), 60, cloud).unwrap();
+            
+            let taken = shared.register_indicator("ema".into(), 300, ema).unwrap_err();
+            assert_eq!(error_code(taken), "INVALID_PARAMETER");
+            let unknown = shared.latest_indicator("rsi").unwrap_err();
+            assert_eq!(error_code(unknown), "INVALID_PARAMETER");
+            let ema: EMACalculator = ema.extract().unwrap();
+            let cloud: IchimokuState = cloud.extract().unwrap();
+            (ema, cloud)
+        });
+        assert_eq!(shared.latest_indicator("ema").unwrap(), None);
+        
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (buffer, done) = (shared.clone(), done.clone());
+            std::thread::spawn(move || {
+                let mut reads = 0;
+                while !done.load(Ordering::Acquire) {
+                    // Une EMA des clôtures reste dans leur plage, NaN pendant
+                    // ses quatre premières bougies
+                    if let Some(latest) = buffer.latest_indicator("ema").unwrap() {
+                        assert_eq!(latest.len(), 1);
+                        let in_range = (1991.9..=2008.1).contains(&latest[0]);
+                        assert!(in_range || latest[0].is_nan(), "{:?}", latest);
+                    }
+                    let cloud = buffer.latest_indicator("cloud").unwrap();
+                    assert!(cloud.is_none_or(|lines| lines.len() == 5));
+                    reads += 1;
+                }
+                reads
+            })
+        };
+        for i in 0..TICKS {
+            Python::with_gil(|py| shared.add_tick(py, tick_at(i * 10, mid(i), 1)).unwrap());
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.join().unwrap() > 0);
+        
+        // Valeurs finales identiques à un rejeu des bougies clôturées
+        let (mut ema, mut cloud) = (ema, cloud);
+        let bars = shared.get_candles(60, usize::MAX).unwrap();
+        assert_eq!(bars.len(), 500);
+        let expected_ema = bars.iter().map(|bar| ema.update(bar.close)).last().unwrap();
+        let (t, k, a, b, c) =
+            bars.iter().map(|bar| cloud.update(bar.high, bar.low, bar.close)).last().unwrap();
+        assert_eq!(shared.latest_indicator("ema").unwrap(), Some(vec![expected_ema]));
+        let same = |x: f64, y: f64| x == y || (x.is_nan() && y.is_nan());
+        let lines = shared.latest_indicator("cloud").unwrap().unwrap();
+        assert!(lines.iter().zip([t, k, a, b, c]).all(|(&x, y)| same(x, y)), "{:?}", lines);
+    }
+    
+    #[test]
+    fn candle_callback_receives_each_closed_bar_once() {
+        const CALLBACK: &str = r#"
+import sys
+
+closed = []
+errors = []
+
+def on_close(bar, timeframe):
+    closed.append((timeframe, bar.timestamp, bar.close))
+    if len(closed) == 3:
+        raise RuntimeError("callback en échec")
+
+def record(unraisable):
+    errors.append(unraisable.exc_type.__name__)
+"#;
+        // Un tick toutes les 20 s pendant 11 minutes : la minute m clôt sur
+        // le tick de m:40, à 2000 + 3m + 2
+        let stream: Vec<Tick> = (0..=33).map(|i| tick_utc(i * 20, 2000.0 + i as f64)).collect();
+        let start = stream[0].timestamp;
+        let mut expected = Vec::new();
+        for m in 0..11 {
+            expected.push((60, start + m * 60_000_000, 2000.0 + (3 * m + 2) as f64));
+            // M5 après la M1 qui le termine
+            if m % 5 == 4 {
+                expected.push((300, start + (m - 4) * 60_000_000, 2000.0 + (3 * m + 2) as f64));
+            }
+        }
+        
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(py, CALLBACK, "callback.py", "callback").unwrap();
+            let sys = py.import("sys").unwrap();
+            sys.setattr("unraisablehook", module.getattr("record").unwrap()).unwrap();
+            
+            let buffer = buffer(100);
+            let on_close: PyObject = module.getattr("on_close").unwrap().into();
+            buffer.set_candle_callback(Some(on_close)).unwrap();
+            for tick in &stream {
+                assert!(buffer.add_tick(py, tick.clone()).unwrap());
+            }
+            sys.setattr("unraisablehook", sys.getattr("__unraisablehook__").unwrap()).unwrap();
+            
+            // L'exception du troisième appel est signalée, l'ingestion continue
+            let closed: Vec<(u64, i64, f64)> = module.getattr("closed").unwrap().extract().unwrap();
+            assert_eq!(closed, expected);
+            let errors: Vec<String> = module.getattr("errors").unwrap().extract().unwrap();
+            assert_eq!(errors, ["RuntimeError"]);
+            assert_eq!(buffer.tick_count(), stream.len());
+            assert_eq!(buffer.get_m1_candles(100).len(), 11);
+            assert_eq!(buffer.get_m5_candles(100).len(), 2);
+            
+            let lazy = TickBuffer::new(10, "XAUUSD".into(), false, true, true, None, PriceSource::Mid)
+                .unwrap();
+            assert!(lazy.set_candle_callback(Some(py.None())).is_err());
+        });
+    }
+    
+    #[test]
+    fn microprice_leans_towards_the_thin_side_and_falls_back_to_mid() {
+        let quote = |bid_size, ask_size| Tick {
+            bid: 2000.0,
+            ask: 2000.4,
+            bid_size,
+            ask_size,
+            ..tick_at(0, 2000.2, 1)
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        
+        // Bid trois fois plus gros : le microprix monte aux trois quarts du spread
+        let heavy_bid = quote(3.0, 1.0);
+        assert!(close(heavy_bid.mid_price(), 2000.2));
+        assert!(close(heavy_bid.microprice(), 2000.3));
+        assert!(close(heavy_bid.size_imbalance(), 0.5));
+        let heavy_ask = quote(1.0, 3.0);
+        assert!(close(heavy_ask.microprice(), 2000.1));
+        assert!(close(heavy_ask.size_imbalance(), -0.5));
+        
+        // Tailles égales ou absentes : mid et déséquilibre nul
+        assert!(close(quote(2.0, 2.0).microprice(), 2000.2));
+        assert!(close(quote(0.0, 0.0).microprice(), 2000.2));
+        assert_eq!(quote(0.0, 0.0).size_imbalance(), 0.0);
+        
+        // Constructeur Python sans tailles
+        Python::with_gil(|py| {
+            let tick = py.get_type::<Tick>().call1(("XAUUSD", 2000.0, 2000.4, 1_i64, 1_i64)).unwrap();
+            let tick: Tick = tick.extract().unwrap();
+            assert_eq!((tick.bid_size, tick.ask_size), (0.0, 0.0));
+            assert!(close(tick.microprice(), tick.mid_price()));
+        });
+    }
+    
+    #[test]
+    fn max_drawdown_measures_the_deepest_fall_from_the_running_peak() {
+        // Repli de 0.25 % depuis 2010, puis de 1 % entre le pic 2020 et 1999.8
+        let mids = [2000.0, 2010.0, 2005.0, 2020.0, 1999.8, 2015.0, 2025.0];
+        Python::with_gil(|py| {
+            let dipping = buffer(100);
+            assert_eq!(dipping.max_drawdown(10), 0.0);
+            for (i, &mid) in mids.iter().enumerate() {
+                dipping.add_tick(py, tick_at(i as i64, mid, 1)).unwrap();
+            }
+            
+            let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+            assert!(close(dipping.max_drawdown(usize::MAX), 0.01));
+            assert!(close(dipping.max_drawdown(4), 0.01));
+            assert!(close(dipping.max_drawdown(3), 0.0));
+            assert!(close(dipping.max_drawdown(2), 0.0));
+            assert_eq!(dipping.max_drawdown(0), 0.0);
+            
+            // Série strictement croissante
+            let rising = buffer(100);
+            for i in 0..50 {
+                rising.add_tick(py, tick_at(i, 2000.0 + i as f64, 1)).unwrap();
+            }
+            assert_eq!(rising.max_drawdown(50), 0.0);
+        });
     }
 }
@@ -1371,7 +1371,7 @@ class HFTStrategy:
                     self.config.stc_slow_length
                 )
                 
-                if not stc_values or len(stc_values) == 0:
+                if stc_values is None or len(stc_values) == 0:
                     return None
                 
                 # Prendre la dernière valeur